@@ -1,14 +1,25 @@
 use std::collections::HashMap;
 
-use bevy::prelude::*;
+use bevy::{prelude::*, reflect::TypeUuid};
 use map_macro::map;
-use types::{Animation, AnimationType, Area, DummyAnimation, Model, StickPosition};
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use types::{Animation, AnimationType, Area, DummyAnimation, Model};
 
 use crate::{Inventory, Item, ItemId, Move, MoveId};
 
-#[derive(Debug, Component, Clone)]
+use super::{DefinitionError, Problem};
+
+#[derive(Debug, Component, Clone, PartialEq, Serialize, Deserialize, TypeUuid)]
+#[uuid = "4d8ea6a1-2c0b-4a0e-9a7e-0a7c5a9f6e31"]
+#[serde(default)]
 pub struct Character {
+    #[serde(serialize_with = "types::serialize_sorted_map")]
     moves: HashMap<MoveId, Move>,
+    /// When more than one move is buffered, the one that comes first here starts.
+    /// Moves that are left out come after these, in name order
+    priority: Vec<MoveId>,
+    #[serde(serialize_with = "types::serialize_sorted_map")]
     items: HashMap<ItemId, Item>,
     pub model: Model,
     pub low_block_height: f32,
@@ -17,7 +28,7 @@ pub struct Character {
     pub crouching_hurtbox: Area,
     pub standing_pushbox: Area,
     pub crouching_pushbox: Area,
-    pub charge_directions: Vec<StickPosition>,
+    #[serde(serialize_with = "types::serialize_sorted_map")]
     pub generic_animations: HashMap<AnimationType, Animation>,
 }
 
@@ -25,6 +36,7 @@ impl Default for Character {
     fn default() -> Self {
         Self {
             moves: Default::default(),
+            priority: Default::default(),
             items: Default::default(),
             model: Model::Dummy,
             generic_animations: map! {
@@ -39,12 +51,6 @@ impl Default for Character {
             },
             low_block_height: 0.5,
             high_block_height: 1.2,
-            charge_directions: vec![
-                StickPosition::SE,
-                StickPosition::S,
-                StickPosition::SW,
-                StickPosition::W,
-            ],
             // TODO: Make theses a part of the constructor:
             standing_hurtbox: Area::from_center_size(Vec2::Y * 0.9, Vec2::new(0.5, 1.8)),
             crouching_hurtbox: Area::from_center_size(Vec2::Y * 0.6, Vec2::new(0.5, 1.2)),
@@ -54,18 +60,49 @@ impl Default for Character {
     }
 }
 impl Character {
-    pub(crate) fn new(moves: HashMap<MoveId, Move>, items: HashMap<ItemId, Item>) -> Character {
-        Character {
-            moves,
-            items,
-            ..default()
+    /// Parses a definition file, fields that are left out use the defaults.
+    /// Fails on problems that would crash the game, use lint for the rest
+    pub fn from_ron(bytes: &[u8]) -> Result<Character, DefinitionError> {
+        let character: Character = ron::de::from_bytes(bytes)?;
+        if let Some(problem) = character.lint().into_iter().find(Problem::is_fatal) {
+            return Err(DefinitionError::Invalid(problem));
         }
+        Ok(character)
+    }
+
+    /// Sorted by id, so the order is the same on every run
+    pub fn moves(&self) -> Vec<(MoveId, &Move)> {
+        let mut moves: Vec<_> = self.moves.iter().map(|(id, data)| (*id, data)).collect();
+        moves.sort_by_key(|(id, _)| *id);
+        moves
+    }
+
+    pub fn has_move(&self, id: MoveId) -> bool {
+        self.moves.contains_key(&id)
+    }
+
+    /// Items that can show up in the shop
+    pub fn has_item(&self, id: ItemId) -> bool {
+        self.items.contains_key(&id)
+    }
+
+    /// Listed in priority, can refer to moves that don't exist
+    pub fn prioritized_moves(&self) -> &[MoveId] {
+        &self.priority
     }
 
     pub fn get_move(&self, id: MoveId) -> Move {
         self.moves.get(&id).unwrap().to_owned()
     }
 
+    /// Lower goes first, for picking between buffered moves
+    pub fn priority(&self, id: MoveId) -> usize {
+        self.priority
+            .iter()
+            .position(|prioritized| *prioritized == id)
+            .unwrap_or(self.priority.len())
+    }
+
     pub fn get_pushbox(&self, crouching: bool) -> Area {
         if crouching {
             self.crouching_pushbox
@@ -82,20 +119,71 @@ impl Character {
         }
     }
 
-    pub fn get_inputs(&self) -> HashMap<MoveId, &'static str> {
+    pub fn get_inputs(&self) -> HashMap<MoveId, &str> {
         self.moves
             .iter()
-            .filter_map(|(key, move_data)| move_data.input.map(|input| (*key, input)))
+            .filter_map(|(key, move_data)| {
+                move_data.input.as_ref().map(|input| (*key, input.as_str()))
+            })
             .collect()
     }
 
-    pub fn roll_items(&self, max_amount: usize, inventory: &Inventory) -> Vec<(ItemId, Item)> {
-        self.items
+    /// Same rng state gives the same items, so pass in the seeded gameplay rng
+    pub fn roll_items(
+        &self,
+        max_amount: usize,
+        inventory: &Inventory,
+        rng: &mut impl Rng,
+    ) -> Vec<(ItemId, Item)> {
+        // Sorted, since map order is different on every run
+        let mut available: Vec<_> = self
+            .items
             .iter()
             .filter(|(id, _)| !inventory.contains(id))
-            .take(max_amount)
-            .map(|(id, item)| (id.to_owned(), item.to_owned()))
+            .collect();
+        available.sort_by_key(|(id, _)| **id);
+
+        available
+            .choose_multiple(rng, max_amount)
+            .map(|(id, item)| (**id, (*item).to_owned()))
             .collect()
-        // TODO random selection that doesn't break rollback
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unknown_branch_target_is_an_error() {
+        let error = Character::from_ron(
+            br#"(
+                moves: {
+                    "Shoot": (phases: [(default: Phase((duration: 5))), (default: Move("Gunshot"))]),
+                },
+            )"#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Shoot.phases[1].default refers to Gunshot, which is not a move"
+        );
+    }
+
+    #[test]
+    fn priority_comes_from_the_definition() {
+        let character = Character::from_ron(
+            br#"(
+                moves: {"Punch": (), "Kick": (), "Fireball": ()},
+                priority: ["Fireball", "Punch"],
+            )"#,
+        )
+        .unwrap();
+
+        assert!(
+            character.priority(MoveId::new("Fireball")) < character.priority(MoveId::new("Punch"))
+        );
+        assert!(character.priority(MoveId::new("Punch")) < character.priority(MoveId::new("Kick")));
     }
 }
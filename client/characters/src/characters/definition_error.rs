@@ -0,0 +1,27 @@
+use std::{fmt::Display, io};
+
+use super::Problem;
+
+/// Why a character definition can't be used
+#[derive(Debug)]
+pub enum DefinitionError {
+    Read(io::Error),
+    Parse(ron::Error),
+    /// Would crash the game, like a reference to a move that doesn't exist
+    Invalid(Problem),
+}
+impl Display for DefinitionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DefinitionError::Read(error) => write!(f, "{}", error),
+            DefinitionError::Parse(error) => write!(f, "{}", error),
+            DefinitionError::Invalid(problem) => write!(f, "{}", problem),
+        }
+    }
+}
+impl std::error::Error for DefinitionError {}
+impl From<ron::Error> for DefinitionError {
+    fn from(error: ron::Error) -> Self {
+        DefinitionError::Parse(error)
+    }
+}
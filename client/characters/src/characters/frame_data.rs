@@ -0,0 +1,277 @@
+use serde::Serialize;
+
+use crate::{Branch, Move, MoveAction, MoveId, Phase, PhaseKind};
+
+use super::Character;
+
+/// Frame data for one way a move can play out, frames are counted from 1
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FrameData {
+    pub move_id: MoveId,
+    /// Branch that was taken instead of the default, like "phases[2].branches[0]"
+    pub variant: Option<String>,
+    /// Moves the move went on to, in order
+    pub continues_into: Vec<MoveId>,
+    /// Frame the first hitbox or grab comes out on
+    pub startup: Option<usize>,
+    /// Frames spent in attack or grab phases
+    pub active: usize,
+    /// Frames after the last active frame
+    pub recovery: Option<usize>,
+    pub total: usize,
+    /// First and last frame of each window where the move can be cancelled
+    pub cancel_windows: Vec<(usize, usize)>,
+    pub damage_on_hit: Option<i32>,
+    pub damage_on_block: Option<i32>,
+    pub stun_on_hit: Option<usize>,
+    pub stun_on_block: Option<usize>,
+    /// Assuming the first active frame connects. None for launchers and grabs
+    pub advantage_on_hit: Option<i32>,
+    pub advantage_on_block: Option<i32>,
+}
+
+impl Character {
+    /// Every move with the defaults, followed by a variant for each branch the move has
+    pub fn frame_data(&self) -> Vec<FrameData> {
+        self.moves()
+            .into_iter()
+            .flat_map(|(id, move_data)| {
+                let variants =
+                    move_data
+                        .phases
+                        .iter()
+                        .enumerate()
+                        .flat_map(|(phase_index, branch)| {
+                            (0..branch.branches.len()).map(move |index| (phase_index, index))
+                        });
+
+                std::iter::once(self.variant_frame_data(id, move_data, None))
+                    .chain(
+                        variants
+                            .map(|variant| self.variant_frame_data(id, move_data, Some(variant))),
+                    )
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Variant is the phase index and the branch index to take there
+    fn variant_frame_data(
+        &self,
+        id: MoveId,
+        move_data: &Move,
+        variant: Option<(usize, usize)>,
+    ) -> FrameData {
+        let mut continues_into = vec![];
+        let mut phases: Vec<Phase> = vec![];
+
+        let mut branches: Vec<Branch> = move_data.phases.clone();
+        let mut index = 0;
+        while let Some(branch) = branches.get(index) {
+            let action = match variant {
+                Some((phase_index, branch_index))
+                    if phase_index == index && continues_into.is_empty() =>
+                {
+                    &branch.branches[branch_index].1
+                }
+                _ => &branch.default,
+            };
+
+            match action.to_owned() {
+                MoveAction::Phase(phase) => {
+                    phases.push(phase);
+                    index += 1;
+                }
+                MoveAction::Move(target) => {
+                    // The rest of the move is never reached, it plays the target from the start
+                    if target == id || continues_into.contains(&target) || !self.has_move(target) {
+                        break;
+                    }
+                    continues_into.push(target);
+                    branches = self.get_move(target).phases;
+                    index = 0;
+                }
+            }
+        }
+
+        timeline(id, variant, continues_into, &phases)
+    }
+}
+
+fn timeline(
+    move_id: MoveId,
+    variant: Option<(usize, usize)>,
+    continues_into: Vec<MoveId>,
+    phases: &[Phase],
+) -> FrameData {
+    let mut frame_data = FrameData {
+        move_id,
+        variant: variant.map(|(phase_index, branch_index)| {
+            format!("phases[{}].branches[{}]", phase_index, branch_index)
+        }),
+        continues_into,
+        startup: None,
+        active: 0,
+        recovery: None,
+        total: 0,
+        cancel_windows: vec![],
+        damage_on_hit: None,
+        damage_on_block: None,
+        stun_on_hit: None,
+        stun_on_block: None,
+        advantage_on_hit: None,
+        advantage_on_block: None,
+    };
+
+    let mut last_active = None;
+    for phase in phases {
+        let first_frame = frame_data.total + 1;
+        frame_data.total += phase.duration;
+
+        if phase.cancellable && phase.duration > 0 {
+            match frame_data.cancel_windows.last_mut() {
+                // Back to back cancellable phases are one window
+                Some((_, last)) if *last + 1 == first_frame => *last = frame_data.total,
+                _ => frame_data
+                    .cancel_windows
+                    .push((first_frame, frame_data.total)),
+            }
+        }
+
+        if matches!(phase.kind, PhaseKind::Animation) {
+            continue;
+        }
+        frame_data.active += phase.duration;
+        last_active = Some(frame_data.total);
+
+        if frame_data.startup.is_some() {
+            // Numbers are for the first hit
+            continue;
+        }
+        frame_data.startup = Some(first_frame);
+
+        match &phase.kind {
+            PhaseKind::Attack(spawn) => {
+                frame_data.damage_on_hit = Some(spawn.damage.on_hit);
+                frame_data.damage_on_block = Some(spawn.damage.on_block);
+                frame_data.stun_on_hit = Some(spawn.stun.on_hit);
+                frame_data.stun_on_block = Some(spawn.stun.on_block);
+
+                // Launched opponents are in the air until they land, there is no stun to compare to
+                if spawn.knockback.on_hit.y <= 0.0 {
+                    frame_data.advantage_on_hit = Some(spawn.stun.on_hit as i32);
+                }
+                if spawn.knockback.on_block.y <= 0.0 {
+                    frame_data.advantage_on_block = Some(spawn.stun.on_block as i32);
+                }
+            }
+            PhaseKind::Grab(grab) => frame_data.damage_on_hit = Some(grab.damage),
+            PhaseKind::Animation => unreachable!(),
+        }
+    }
+
+    if let (Some(startup), Some(last_active)) = (frame_data.startup, last_active) {
+        frame_data.recovery = Some(frame_data.total - last_active);
+
+        // Stun starts on the frame of the hit, the attacker still has the rest of the move to go
+        let remaining = (frame_data.total - startup) as i32;
+        for stun in [
+            &mut frame_data.advantage_on_hit,
+            &mut frame_data.advantage_on_block,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *stun -= remaining;
+        }
+    }
+
+    frame_data
+}
+
+#[cfg(test)]
+mod test {
+    use crate::CharacterId;
+
+    use super::*;
+
+    fn frame_data(definition: &str, id: &'static str) -> Vec<FrameData> {
+        let character = Character::from_ron(definition.as_bytes()).unwrap();
+        character
+            .frame_data()
+            .into_iter()
+            .filter(|data| data.move_id == MoveId::new(id))
+            .collect()
+    }
+
+    #[test]
+    fn punch() {
+        let punch = crate::characters::load_shipped(CharacterId::Dummy).frame_data();
+        let punch = punch
+            .iter()
+            .find(|data| data.move_id == MoveId::new("Punch"))
+            .unwrap();
+
+        assert_eq!(punch.variant, None);
+        assert_eq!(punch.startup, Some(6));
+        assert_eq!(punch.active, 10);
+        assert_eq!(punch.recovery, Some(10));
+        assert_eq!(punch.total, 25);
+        assert_eq!(punch.damage_on_hit, Some(10));
+        assert_eq!(punch.stun_on_block, Some(5));
+        // 15 and 5 frames of stun against the 19 frames left after the first active frame
+        assert_eq!(punch.advantage_on_hit, Some(-4));
+        assert_eq!(punch.advantage_on_block, Some(-14));
+    }
+
+    #[test]
+    fn branch_variants() {
+        let rows = frame_data(
+            r#"(
+                moves: {
+                    "CommandPunch": (
+                        input: Some("6f"),
+                        phases: [
+                            (default: Phase((duration: 10))),
+                            (default: Phase((kind: Attack(()), duration: 5))),
+                            (
+                                default: Phase((duration: 30)),
+                                branches: [((has_hit: Some(true)), Phase((duration: 2, cancellable: true)))],
+                            ),
+                        ],
+                    ),
+                },
+            )"#,
+            "CommandPunch",
+        );
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].variant, None);
+        assert_eq!(rows[0].recovery, Some(30));
+        assert_eq!(rows[0].advantage_on_hit, Some(15 - 34));
+        assert_eq!(rows[0].cancel_windows, vec![]);
+
+        assert_eq!(rows[1].variant.as_deref(), Some("phases[2].branches[0]"));
+        assert_eq!(rows[1].recovery, Some(2));
+        assert_eq!(rows[1].total, 17);
+        assert_eq!(rows[1].advantage_on_hit, Some(15 - 6));
+        assert_eq!(rows[1].cancel_windows, vec![(16, 17)]);
+    }
+
+    #[test]
+    fn follows_into_other_moves() {
+        let rows = frame_data(
+            r#"(
+                moves: {
+                    "Shoot": (input: Some("e"), phases: [(default: Phase((duration: 5))), (default: Move("Gunshot"))]),
+                    "Gunshot": (phases: [(default: Phase((kind: Attack(()), duration: 1))), (default: Phase((duration: 20)))]),
+                },
+            )"#,
+            "Shoot",
+        );
+
+        assert_eq!(rows[0].continues_into, vec![MoveId::new("Gunshot")]);
+        assert_eq!(rows[0].startup, Some(6));
+        assert_eq!(rows[0].total, 26);
+    }
+}
@@ -0,0 +1,433 @@
+use std::{collections::HashSet, fmt::Display};
+
+use types::{CompoundArea, GameButton};
+
+use crate::{resources::Meter, Branch, Move, MoveAction, MoveId, PhaseKind, Requirements};
+
+use super::Character;
+
+/// Something wrong with a character definition
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// Where in the definition, like "CommandPunch.phases[2].branches[0]"
+    pub path: String,
+    pub kind: ProblemKind,
+}
+impl Problem {
+    fn new(path: impl Into<String>, kind: ProblemKind) -> Self {
+        Self {
+            path: path.into(),
+            kind,
+        }
+    }
+
+    /// The game would crash, the rest only make the character behave oddly
+    pub fn is_fatal(&self) -> bool {
+        matches!(self.kind, ProblemKind::UnknownMove(_))
+    }
+}
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ", self.path)?;
+        match &self.kind {
+            ProblemKind::UnknownMove(id) => write!(f, "refers to {}, which is not a move", id),
+            ProblemKind::NeverEnds(targets) => write!(
+                f,
+                "can never end, it always goes on to {}",
+                targets
+                    .iter()
+                    .map(|id| id.name())
+                    .collect::<Vec<_>>()
+                    .join(" or ")
+            ),
+            ProblemKind::ZeroDuration => write!(f, "lasts 0 frames"),
+            ProblemKind::Unreachable => write!(f, "has no input and no move leads to it"),
+            ProblemKind::HitboxOutOfReach => {
+                write!(
+                    f,
+                    "has a hitbox that can't reach a standing or crouching hurtbox"
+                )
+            }
+            ProblemKind::ImpossibleRequirements(reason) => {
+                write!(f, "can never be met, {}", reason)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProblemKind {
+    UnknownMove(MoveId),
+    /// Every way out of the move leads back into it, these are the moves it can go on to
+    NeverEnds(Vec<MoveId>),
+    ZeroDuration,
+    /// No input and no other move leads to it
+    Unreachable,
+    /// Grounded move with a hitbox above or below where a grounded opponent can be hit
+    HitboxOutOfReach,
+    ImpossibleRequirements(String),
+}
+
+impl Character {
+    /// Every problem at once, in move order
+    pub fn lint(&self) -> Vec<Problem> {
+        let mut problems = vec![];
+
+        for (index, id) in self.prioritized_moves().iter().enumerate() {
+            if !self.has_move(*id) {
+                problems.push(Problem::new(
+                    format!("priority[{}]", index),
+                    ProblemKind::UnknownMove(*id),
+                ));
+            }
+        }
+
+        // A move that only leads back into itself still can't be started
+        let referenced: HashSet<MoveId> = self
+            .moves()
+            .into_iter()
+            .flat_map(|(id, move_data)| {
+                move_targets(move_data)
+                    .into_iter()
+                    .filter(move |target| *target != id)
+            })
+            .collect();
+        let never_ending = self.never_ending_moves();
+
+        for (id, move_data) in self.moves() {
+            if move_data.input.is_none() && !referenced.contains(&id) {
+                problems.push(Problem::new(id.name(), ProblemKind::Unreachable));
+            }
+
+            if let Some(targets) = never_ending.get(&id) {
+                problems.push(Problem::new(
+                    id.name(),
+                    ProblemKind::NeverEnds(targets.clone()),
+                ));
+            }
+
+            if let Some(reason) = self.impossible(&move_data.requirements, None) {
+                problems.push(Problem::new(
+                    format!("{}.requirements", id),
+                    ProblemKind::ImpossibleRequirements(reason),
+                ));
+            }
+
+            for (phase_index, branch) in move_data.phases.iter().enumerate() {
+                // Something has to have hit before a branch can check for it
+                let can_have_hit = move_data.phases[..phase_index].iter().any(can_hit);
+
+                for (path, requirements, action) in options(branch) {
+                    let path = format!("{}.phases[{}].{}", id, phase_index, path);
+
+                    if let Some(requirements) = requirements {
+                        if let Some(reason) = self.impossible(requirements, Some(can_have_hit)) {
+                            problems.push(Problem::new(
+                                path.clone(),
+                                ProblemKind::ImpossibleRequirements(reason),
+                            ));
+                        }
+                    }
+
+                    match action {
+                        MoveAction::Move(target) => {
+                            if !self.has_move(*target) {
+                                problems
+                                    .push(Problem::new(path, ProblemKind::UnknownMove(*target)));
+                            }
+                        }
+                        MoveAction::Phase(phase) => {
+                            if phase.duration == 0 {
+                                problems
+                                    .push(Problem::new(path.clone(), ProblemKind::ZeroDuration));
+                            }
+
+                            if let PhaseKind::Attack(ref spawn) = phase.kind {
+                                // Moving up or down, or started in the air, it could reach
+                                if move_data.requirements.grounded == Some(true)
+                                    && spawn.speed.y == 0.0
+                                    && !self.can_reach(&spawn.hitbox)
+                                {
+                                    problems
+                                        .push(Problem::new(path, ProblemKind::HitboxOutOfReach));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        problems
+    }
+
+    /// Moves that can't end, with the moves they go on to
+    fn never_ending_moves(&self) -> std::collections::HashMap<MoveId, Vec<MoveId>> {
+        // Start from the moves that can run out of phases and work back from there
+        let mut ending: HashSet<MoveId> = self
+            .moves()
+            .into_iter()
+            .filter(|(_, move_data)| exits(move_data).0)
+            .map(|(id, _)| id)
+            .collect();
+
+        loop {
+            let newly_ending: Vec<MoveId> = self
+                .moves()
+                .into_iter()
+                .filter(|(id, move_data)| {
+                    !ending.contains(id)
+                        && exits(move_data).1.iter().any(|target| {
+                            // Unknown moves are reported on their own
+                            ending.contains(target) || !self.has_move(*target)
+                        })
+                })
+                .map(|(id, _)| id)
+                .collect();
+
+            if newly_ending.is_empty() {
+                break;
+            }
+            ending.extend(newly_ending);
+        }
+
+        self.moves()
+            .into_iter()
+            .filter(|(id, _)| !ending.contains(id))
+            .map(|(id, move_data)| (id, exits(move_data).1))
+            .collect()
+    }
+
+    /// Vertically, since the opponent can be anywhere horizontally
+    fn can_reach(&self, hitbox: &CompoundArea) -> bool {
+        hitbox.areas().iter().any(|area| {
+            [self.standing_hurtbox, self.crouching_hurtbox]
+                .iter()
+                .any(|hurtbox| area.bottom() < hurtbox.top() && area.top() > hurtbox.bottom())
+        })
+    }
+
+    /// Reason why the requirements can't be met, if there is one
+    /// can_have_hit is None for the requirements to start the move
+    fn impossible(
+        &self,
+        requirements: &Requirements,
+        can_have_hit: Option<bool>,
+    ) -> Option<String> {
+        if requirements.has_hit == Some(true) && can_have_hit == Some(false) {
+            return Some("nothing can hit before it".into());
+        }
+
+        if let Some(cost) = requirements.cost {
+            let max = Meter::default().max();
+            if cost.meter > max {
+                return Some(format!(
+                    "costs {} meter, but the most there can be is {}",
+                    cost.meter, max
+                ));
+            }
+        }
+
+        if let Some(items) = &requirements.items {
+            let mut items: Vec<_> = items.iter().collect();
+            items.sort();
+            if let Some(item) = items.into_iter().find(|item| !self.has_item(**item)) {
+                return Some(format!(
+                    "needs {:?}, which is not one of the character's items",
+                    item
+                ));
+            }
+        }
+
+        if let Some(buttons) = &requirements.buttons_held {
+            if buttons.contains(&GameButton::Default) {
+                return Some("needs the Default button held, which is not a real button".into());
+            }
+        }
+
+        None
+    }
+}
+
+/// Each option of a branch with its path within the phase, default first
+fn options(branch: &Branch) -> Vec<(String, Option<&Requirements>, &MoveAction)> {
+    std::iter::once(("default".to_owned(), None, &branch.default))
+        .chain(
+            branch
+                .branches
+                .iter()
+                .enumerate()
+                .map(|(index, (requirements, action))| {
+                    (format!("branches[{}]", index), Some(requirements), action)
+                }),
+        )
+        .collect()
+}
+
+fn move_targets(move_data: &Move) -> Vec<MoveId> {
+    move_data
+        .phases
+        .iter()
+        .flat_map(options)
+        .filter_map(|(_, _, action)| match action {
+            MoveAction::Move(id) => Some(*id),
+            MoveAction::Phase(_) => None,
+        })
+        .collect()
+}
+
+/// If the move can run out of phases, and which moves it can go on to before that
+fn exits(move_data: &Move) -> (bool, Vec<MoveId>) {
+    let mut targets = vec![];
+    for branch in &move_data.phases {
+        let mut continues = false;
+        for (_, _, action) in options(branch) {
+            match action {
+                MoveAction::Move(id) => {
+                    if !targets.contains(id) {
+                        targets.push(*id);
+                    }
+                }
+                MoveAction::Phase(_) => continues = true,
+            }
+        }
+
+        if !continues {
+            // Every option goes to another move, the later phases are never reached
+            return (false, targets);
+        }
+    }
+    (true, targets)
+}
+
+fn can_hit(branch: &Branch) -> bool {
+    options(branch).into_iter().any(|(_, _, action)| {
+        matches!(
+            action,
+            MoveAction::Phase(phase) if !matches!(phase.kind, PhaseKind::Animation)
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lint(definition: &str) -> Vec<String> {
+        let character: Character = ron::from_str(definition).unwrap();
+        character
+            .lint()
+            .into_iter()
+            .map(|problem| problem.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn shipped_characters_are_clean() {
+        for id in crate::CharacterId::ALL {
+            assert_eq!(
+                crate::characters::load_shipped(id).lint(),
+                vec![],
+                "{:?}",
+                id
+            );
+        }
+    }
+
+    #[test]
+    fn all_problems_are_reported() {
+        let problems = lint(
+            r#"#![enable(unwrap_newtypes)]
+            (
+                moves: {
+                    "Punch": (
+                        input: Some("f"),
+                        requirements: (grounded: Some(true)),
+                        phases: [
+                            (default: Phase((duration: 0))),
+                            (default: Phase((
+                                kind: Attack((hitbox: [(center: (1.0, 5.0), width: 0.2, height: 0.2)])),
+                                duration: 5,
+                            ))),
+                            (
+                                default: Phase((duration: 10)),
+                                branches: [((has_hit: Some(true)), Move("Missing"))],
+                            ),
+                        ],
+                    ),
+                    "Orphan": (),
+                    "Echo": (
+                        phases: [
+                            (default: Phase((duration: 5)), branches: [((grounded: Some(true)), Move("Echo"))]),
+                        ],
+                    ),
+                },
+                priority: ["Kick"],
+            )"#,
+        );
+
+        assert_eq!(
+            problems,
+            vec![
+                "priority[0] refers to Kick, which is not a move",
+                "Echo has no input and no move leads to it",
+                "Orphan has no input and no move leads to it",
+                "Punch.phases[0].default lasts 0 frames",
+                "Punch.phases[1].default has a hitbox that can't reach a standing or crouching hurtbox",
+                "Punch.phases[2].branches[0] refers to Missing, which is not a move",
+            ]
+        );
+    }
+
+    #[test]
+    fn loop_without_exit() {
+        let problems = lint(
+            r#"(
+                moves: {
+                    "Spin": (input: Some("f"), phases: [(default: Move("Twirl"))]),
+                    "Twirl": (phases: [(default: Phase((duration: 5))), (default: Move("Spin"))]),
+                    // Has a way out, so it's fine
+                    "Gunshot": (
+                        input: Some("e"),
+                        phases: [(default: Phase((duration: 5)), branches: [((has_hit: Some(false)), Move("Gunshot"))])],
+                    ),
+                },
+            )"#,
+        );
+
+        assert_eq!(
+            problems,
+            vec![
+                "Spin can never end, it always goes on to Twirl",
+                "Twirl can never end, it always goes on to Spin",
+            ]
+        );
+    }
+
+    #[test]
+    fn impossible_requirements() {
+        let problems = lint(
+            r#"(
+                moves: {
+                    "Super": (input: Some("236f"), requirements: (cost: Some((meter: 200, bullet: false)))),
+                    "Slash": (input: Some("s"), requirements: (items: Some([Gun]))),
+                    "Followup": (
+                        input: Some("f"),
+                        phases: [
+                            (default: Phase((duration: 5)), branches: [((has_hit: Some(true)), Phase((duration: 1)))]),
+                        ],
+                    ),
+                },
+            )"#,
+        );
+
+        assert_eq!(
+            problems,
+            vec![
+                "Followup.phases[0].branches[0] can never be met, nothing can hit before it",
+                "Slash.requirements can never be met, needs Gun, which is not one of the character's items",
+                "Super.requirements can never be met, costs 200 meter, but the most there can be is 100",
+            ]
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+
+use super::Character;
+
+/// Reads character definitions, files ending in .character.ron
+#[derive(Debug, Default)]
+pub struct CharacterLoader {
+    errors: CharacterLoadErrors,
+}
+impl CharacterLoader {
+    /// Files that can't be parsed get reported to errors
+    pub fn new(errors: CharacterLoadErrors) -> Self {
+        Self { errors }
+    }
+}
+
+impl AssetLoader for CharacterLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            match Character::from_ron(bytes) {
+                Ok(character) => {
+                    load_context.set_default_asset(LoadedAsset::new(character));
+                    Ok(())
+                }
+                Err(error) => {
+                    self.errors.push(load_context.path(), error.to_string());
+                    Err(error.into())
+                }
+            }
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["character.ron"]
+    }
+}
+
+/// Definition files that failed to parse and why.
+/// Shared with the loader, otherwise the errors only end up in the log
+#[derive(Debug, Default, Clone)]
+pub struct CharacterLoadErrors(Arc<Mutex<Vec<(PathBuf, String)>>>);
+impl CharacterLoadErrors {
+    fn push(&self, path: &Path, error: String) {
+        self.0.lock().unwrap().push((path.to_owned(), error));
+    }
+
+    /// Path is relative to the assets folder, like CharacterId::path
+    pub fn drain(&self) -> Vec<(PathBuf, String)> {
+        self.0.lock().unwrap().drain(..).collect()
+    }
+}
@@ -1,9 +1,132 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
 mod character;
-mod dummy;
-mod equipment;
-mod helpers;
+mod definition_error;
+mod frame_data;
+mod lint;
+mod loader;
 
 pub use character::Character;
-pub use dummy::dummy;
+pub use definition_error::DefinitionError;
+pub use frame_data::FrameData;
+pub use lint::{Problem, ProblemKind};
+pub use loader::{CharacterLoadErrors, CharacterLoader};
+
+/// Identifies a character in saved data, like replays
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Component, Serialize, Deserialize)]
+pub enum CharacterId {
+    #[default]
+    Dummy,
+}
+impl CharacterId {
+    pub const ALL: [CharacterId; 1] = [CharacterId::Dummy];
+
+    /// Definition file, relative to the assets folder
+    pub fn path(self) -> &'static str {
+        match self {
+            CharacterId::Dummy => "characters/dummy.character.ron",
+        }
+    }
+
+    /// Reads the definition from an assets folder, the same file the game loads and reloads
+    pub fn load(self, assets: &Path) -> Result<Character, DefinitionError> {
+        let definition = fs::read(assets.join(self.path())).map_err(DefinitionError::Read)?;
+        Character::from_ron(&definition)
+    }
+}
+
+/// The definitions the game ships with, from a test in any crate of the workspace
+#[cfg(test)]
+pub(crate) fn load_shipped(id: CharacterId) -> Character {
+    id.load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("../main/assets"))
+        .unwrap_or_else(|error| panic!("Shipped {:?} is invalid: {}", id, error))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{CounterType, MoveAction, MoveId, MoveSituation, PhaseKind};
+
+    use super::*;
+
+    #[test]
+    fn shipped_characters_parse() {
+        for id in CharacterId::ALL {
+            load_shipped(id);
+        }
+    }
+
+    #[test]
+    fn dummy_definition() {
+        let dummy = load_shipped(CharacterId::Dummy);
+
+        let punch = dummy.get_move(MoveId::new("Punch"));
+        assert_eq!(punch.input.as_deref(), Some("f"));
+        assert_eq!(punch.phases.len(), 3);
+        match punch.phases[1].default {
+            MoveAction::Phase(ref phase) => assert!(matches!(phase.kind, PhaseKind::Attack(_))),
+            MoveAction::Move(_) => panic!("Punch should attack in the second phase"),
+        }
+
+        // Left out fields use the defaults
+        assert_eq!(
+            dummy.get_move(MoveId::new("Gunshot")).input,
+            None,
+            "Gunshot is only reached through Shoot"
+        );
+        assert!(dummy.get_inputs().contains_key(&MoveId::new("BudgetBoom")));
+    }
+
+    #[test]
+    fn hurtbox_follows_the_phase() {
+        let dummy = load_shipped(CharacterId::Dummy);
+        let punch = dummy.get_move(MoveId::new("Punch"));
+        let base = dummy.get_hurtbox(false);
+        let mut situation = MoveSituation {
+            start_frame: 100,
+            ..default()
+        };
+
+        assert_eq!(punch.get_hurtbox(&situation, 102, base).areas(), [base]);
+
+        // Arm is out during the active frames
+        situation.phase_index = 1;
+        assert_eq!(punch.frames_into_phase(&situation, 107), 2);
+        let extended = punch.get_hurtbox(&situation, 107, base);
+        assert_eq!(extended.areas().len(), 2);
+        assert!(extended.bounds().right() > base.right());
+    }
+
+    #[test]
+    fn counter_type_follows_the_phase() {
+        let dummy = load_shipped(CharacterId::Dummy);
+        let punch = dummy.get_move(MoveId::new("Punch"));
+        let mut situation = MoveSituation::default();
+
+        for (phase_index, counter_type) in [
+            (0, CounterType::CounterHit),
+            (1, CounterType::CounterHit),
+            (2, CounterType::PunishCounter),
+        ] {
+            situation.phase_index = phase_index;
+            assert_eq!(
+                punch.get_counter_type(&situation),
+                Some(counter_type),
+                "phase {}",
+                phase_index
+            );
+        }
 
-use helpers::{dash, jump};
+        // Moves that never attack themselves can't be countered, even if they lead to one that does
+        for id in ["DashForward", "NeutralJump", "Shoot"] {
+            situation.phase_index = 0;
+            assert_eq!(
+                dummy.get_move(MoveId::new(id)).get_counter_type(&situation),
+                None,
+                "{}",
+                id
+            );
+        }
+    }
+}
@@ -3,12 +3,14 @@ use std::collections::HashSet;
 use super::ItemId;
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Component, Eq, PartialEq, Inspectable, Clone)]
+#[derive(Debug, Default, Component, Eq, PartialEq, Inspectable, Clone, Serialize, Deserialize)]
 pub struct Inventory {
     pub tier: usize,
     pub money: usize,
     #[inspectable(ignore)]
+    #[serde(serialize_with = "types::serialize_sorted")]
     pub items: HashSet<ItemId>,
 }
 impl Inventory {
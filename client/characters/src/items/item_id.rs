@@ -1,6 +1,20 @@
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
-#[derive(Inspectable, Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[derive(
+    Inspectable,
+    Clone,
+    Copy,
+    Debug,
+    Hash,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Default,
+    Serialize,
+    Deserialize,
+)]
 pub enum ItemId {
     Gi,
     Gun,
@@ -1,10 +1,13 @@
+use serde::{Deserialize, Serialize};
+
 mod inventory;
 mod item_id;
 
 pub use inventory::Inventory;
 pub use item_id::ItemId;
 
-#[derive(Debug, Default, Clone, Eq, PartialEq, PartialOrd)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Item {
     pub tier: usize,
     pub cost: usize,
@@ -3,14 +3,17 @@ mod items; // Defines things for items
 mod moves;
 mod resources; // Defines things for moves
 
-use items::{Item, ItemId};
-use moves::{Branch, GrabDescription, Phase, Requirements};
+use items::Item;
 use resources::Cost;
 
-pub use self::characters::{dummy, Character};
-pub use items::Inventory;
+pub use self::characters::{
+    Character, CharacterId, CharacterLoadErrors, CharacterLoader, DefinitionError, FrameData,
+    Problem, ProblemKind,
+};
+pub use items::{Inventory, ItemId};
 pub use moves::{
-    AttackHeight, Grabable, HitTracker, Hitbox, Hurtbox, Lifetime, Move, MoveAction, MoveId,
-    MoveMobility, MoveSituation, OnHitEffect, PhaseKind, SpawnDescriptor,
+    Armor, AttackHeight, Branch, CounterType, GrabDescription, Grabable, HitTracker, Hitbox,
+    HitboxKeyframe, Hurtbox, HurtboxKeyframe, Invulnerability, Lifetime, Move, MoveAction, MoveId,
+    MoveMobility, MoveSituation, OnHitEffect, Phase, PhaseKind, Requirements, SpawnDescriptor,
 };
-pub use resources::Resources;
+pub use resources::{Charge, Resources};
@@ -1,4 +1,5 @@
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
 mod situation;
 pub use situation::MoveSituation;
@@ -9,7 +10,9 @@ pub use move_data::{Branch, Move, Requirements};
 mod move_properties;
 pub use move_properties::*;
 
-#[derive(PartialEq, PartialOrd, Debug, Inspectable, Clone, Copy, Eq, Default)]
+#[derive(
+    PartialEq, PartialOrd, Debug, Inspectable, Clone, Copy, Eq, Default, Serialize, Deserialize,
+)]
 pub enum MoveType {
     #[default]
     Normal,
@@ -2,15 +2,18 @@ use std::collections::HashSet;
 
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
-use types::{Animation, GameButton};
+use serde::{Deserialize, Serialize};
+use types::{Animation, Area, CompoundArea, GameButton};
 
 use crate::{Cost, ItemId};
 
-use super::{MoveAction, MoveId, MoveSituation, MoveType, Phase};
+use super::{CounterType, MoveAction, MoveId, MoveSituation, MoveType, Phase, PhaseKind};
 
-#[derive(Debug, Default, Inspectable, Clone, PartialEq)]
+#[derive(Debug, Default, Inspectable, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Move {
-    pub input: Option<&'static str>,
+    /// Motion notation, like "[41]6f" for a charge. None if the move is only reached from other moves
+    pub input: Option<String>,
     pub animation: Animation,
     pub move_type: MoveType,
     pub phases: Vec<Branch>,
@@ -50,6 +53,58 @@ impl Move {
         Some(switch.get(situation))
     }
 
+    /// Frames since the current phase started
+    pub fn frames_into_phase(&self, situation: &MoveSituation, current_frame: i32) -> usize {
+        let earlier_phases: usize = self
+            .phases
+            .iter()
+            .take(situation.phase_index)
+            .filter_map(|resolver| resolver.get(situation).0.get_duration())
+            .sum();
+
+        (current_frame - situation.start_frame - earlier_phases as i32).max(0) as usize
+    }
+
+    /// Base is the standing or crouching hurtbox, phases can add to it or replace it
+    pub fn get_hurtbox(
+        &self,
+        situation: &MoveSituation,
+        current_frame: i32,
+        base: Area,
+    ) -> CompoundArea {
+        if let Some(phase) = self.get_phase(situation) {
+            phase.hurtbox(base, self.frames_into_phase(situation, current_frame))
+        } else {
+            base.into()
+        }
+    }
+
+    /// None between phases, when the move is going on to another move
+    pub fn get_phase(&self, situation: &MoveSituation) -> Option<Phase> {
+        match self.get_action(situation)?.0 {
+            MoveAction::Phase(phase) => Some(phase),
+            MoveAction::Move(_) => None,
+        }
+    }
+
+    /// What a clean hit on the character would be, None if the move never attacks.
+    /// Open to a counter hit until the last attacking phase is over, to a punish counter after that
+    pub fn get_counter_type(&self, situation: &MoveSituation) -> Option<CounterType> {
+        let attacks = |action: &MoveAction| match action {
+            MoveAction::Phase(phase) => !matches!(phase.kind, PhaseKind::Animation),
+            MoveAction::Move(_) => false,
+        };
+        let last_attack = self.phases.iter().rposition(|branch| {
+            attacks(&branch.default) || branch.branches.iter().any(|(_, action)| attacks(action))
+        })?;
+
+        Some(if situation.phase_index > last_attack {
+            CounterType::PunishCounter
+        } else {
+            CounterType::CounterHit
+        })
+    }
+
     pub fn get_animation(&self, situation: &MoveSituation) -> Animation {
         if let Some(action) = self.get_action(situation) {
             if let MoveAction::Phase(phase) = action.0 {
@@ -62,7 +117,8 @@ impl Move {
     }
 }
 
-#[derive(Debug, Inspectable, Clone, PartialEq, Default)]
+#[derive(Debug, Inspectable, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Branch {
     pub default: MoveAction,
     pub branches: Vec<(Requirements, MoveAction)>, // This way order is maintained
@@ -94,13 +150,16 @@ impl From<MoveId> for Branch {
     }
 }
 
-#[derive(Debug, Default, Inspectable, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Inspectable, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Requirements {
     pub has_hit: Option<bool>,
     pub cost: Option<Cost>,
     #[inspectable(ignore)]
+    #[serde(serialize_with = "types::serialize_sorted_option")]
     pub items: Option<HashSet<ItemId>>,
     #[inspectable(ignore)]
+    #[serde(serialize_with = "types::serialize_sorted_option")]
     pub buttons_held: Option<HashSet<GameButton>>,
     pub grounded: Option<bool>,
 }
@@ -1,37 +1,85 @@
-use bevy_inspector_egui::Inspectable;
-
-#[derive(Inspectable, Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Default)]
-pub enum MoveId {
-    #[default]
-    Default, // To satisfy Inspectable.
-
-    // Universal
-    DashForward,
-    DashBack,
-    NeutralSuperJump,
-    ForwardSuperJump,
-    BackSuperJump,
-    NeutralJump,
-    ForwardJump,
-    BackJump,
-
-    // Equipment
-    HandMeDownKen,
-    Gunshot,
-    Shoot,
-
-    // Test moves
-    TestMove,
-    SecondTestMove,
-
-    // Dummy moves
-    Grab,
-    SonicBoom,
-    BudgetBoom,
-    HeavyHadouken,
-    Hadouken,
-    AirPunch,
-    Low,
-    CommandPunch,
-    Punch,
+use std::{collections::HashSet, fmt::Display, sync::Mutex};
+
+use bevy_inspector_egui::{egui, Context, Inspectable};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Names read from files, each one is only ever allocated once
+static INTERNED: Lazy<Mutex<HashSet<&'static str>>> = Lazy::new(Default::default);
+
+/// Name of a move, like "Punch". Each character declares their own, so only unique within a character.
+/// Compared by name, so the order is the same on every run
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct MoveId(&'static str);
+impl MoveId {
+    pub const fn new(name: &'static str) -> Self {
+        Self(name)
+    }
+
+    /// For names that are not known at compile time, like ones read from a file
+    pub fn intern(name: &str) -> Self {
+        let mut interned = INTERNED.lock().unwrap();
+        if let Some(existing) = interned.get(name) {
+            return Self(existing);
+        }
+
+        // Leaks, but only once per distinct name
+        let name: &'static str = Box::leak(name.to_owned().into_boxed_str());
+        interned.insert(name);
+        Self(name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.0
+    }
+}
+impl Display for MoveId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl Serialize for MoveId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+impl<'de> Deserialize<'de> for MoveId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|name| MoveId::intern(&name))
+    }
+}
+impl Inspectable for MoveId {
+    type Attributes = ();
+
+    fn ui(&mut self, ui: &mut egui::Ui, _: Self::Attributes, _: &mut Context) -> bool {
+        ui.label(self.0);
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn interned_matches_literal() {
+        let read = MoveId::intern(&String::from("Punch"));
+
+        assert_eq!(read, MoveId::new("Punch"));
+        assert_eq!(
+            read.name().as_ptr(),
+            MoveId::intern("Punch").name().as_ptr()
+        );
+    }
+
+    #[test]
+    fn serialized_as_name() {
+        let serialized = ron::to_string(&MoveId::new("CommandPunch")).unwrap();
+
+        assert_eq!(serialized, "\"CommandPunch\"");
+        assert_eq!(
+            ron::from_str::<MoveId>(&serialized).unwrap(),
+            MoveId::new("CommandPunch")
+        );
+    }
 }
@@ -1,11 +1,14 @@
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
-use types::{Animation, Area};
+use types::{Animation, Area, CompoundArea};
 
 use crate::MoveId;
 
-#[derive(Debug, Inspectable, Clone, PartialEq)]
+// Phases are what moves are mostly made of, so the size is rarely wasted
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Inspectable, Clone, PartialEq, Serialize, Deserialize)]
 pub enum MoveAction {
     Move(MoveId),
     Phase(Phase),
@@ -48,16 +51,108 @@ impl MoveAction {
     }
 }
 
-#[derive(Debug, Default, Inspectable, Clone, PartialEq)]
+#[derive(Debug, Default, Inspectable, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Phase {
     pub kind: PhaseKind,
     pub duration: usize,
     pub cancellable: bool,
     pub mobility: Option<MoveMobility>,
     pub animation: Option<Animation>,
+    /// Changes to the hurtbox while the phase lasts
+    pub hurtboxes: Vec<HurtboxKeyframe>,
+    pub invulnerability: Option<Invulnerability>,
+    pub armor: Option<Armor>,
+}
+impl Phase {
+    /// Hurtbox on a frame of the phase, base is the standing or crouching one
+    pub fn hurtbox(&self, base: Area, frame: usize) -> CompoundArea {
+        match latest(&self.hurtboxes, frame, |keyframe| keyframe.frame) {
+            Some(keyframe) => keyframe
+                .replacement
+                .clone()
+                .unwrap_or_else(|| base.into())
+                .extended(&keyframe.extra),
+            None => base.into(),
+        }
+    }
+}
+
+/// What can't connect with the character
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Inspectable, Serialize, Deserialize)]
+pub enum Invulnerability {
+    Full,
+    /// Attacks attached to the attacker, like punches
+    Strike,
+    Throw,
+    /// Attacks that move on their own, like fireballs
+    Projectile,
+    /// Anything from an opponent in the air, for anti airs
+    Air,
+}
+impl Invulnerability {
+    pub fn avoids_attack(self, projectile: bool, attacker_airborne: bool) -> bool {
+        match self {
+            Invulnerability::Full => true,
+            Invulnerability::Strike => !projectile,
+            Invulnerability::Throw => false,
+            Invulnerability::Projectile => projectile,
+            Invulnerability::Air => attacker_airborne,
+        }
+    }
+
+    pub fn avoids_grab(self) -> bool {
+        matches!(self, Invulnerability::Full | Invulnerability::Throw)
+    }
+}
+
+/// Takes hits without stun or knockback, grabs go through it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Inspectable, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Armor {
+    /// How many hits it takes in a phase before it breaks
+    pub hits: u8,
+    /// Damage taken on armor comes back over time, unless a hit gets through
+    pub recoverable: bool,
+}
+impl Default for Armor {
+    fn default() -> Self {
+        Self {
+            hits: 1,
+            recoverable: false,
+        }
+    }
+}
+
+/// Hurtbox from a frame of the phase on, until the next keyframe or the end of the phase
+#[derive(Debug, Default, Inspectable, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HurtboxKeyframe {
+    pub frame: usize,
+    /// Added to the standing or crouching hurtbox, like an extended limb
+    pub extra: CompoundArea,
+    /// Used instead of the standing or crouching hurtbox, like a low profile during a slide
+    pub replacement: Option<CompoundArea>,
+}
+
+/// Hitbox from a frame after it came out on, until the next keyframe
+#[derive(Debug, Default, Inspectable, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HitboxKeyframe {
+    pub frame: usize,
+    pub hitbox: Hitbox,
+}
+
+/// Last keyframe that has started by the frame
+fn latest<T>(keyframes: &[T], frame: usize, start: impl Fn(&T) -> usize) -> Option<&T> {
+    keyframes
+        .iter()
+        .filter(|keyframe| start(keyframe) <= frame)
+        .max_by_key(|keyframe| start(keyframe))
 }
 
-#[derive(Debug, Inspectable, Clone, PartialEq, Default)]
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Inspectable, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub enum PhaseKind {
     #[default]
     Animation,
@@ -65,16 +160,27 @@ pub enum PhaseKind {
     Attack(SpawnDescriptor),
 }
 
-#[derive(Debug, Inspectable, Copy, Clone, PartialEq)]
+#[derive(Debug, Inspectable, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum MoveMobility {
     Impulse(Vec3),
     Perpetual(Vec3),
 }
 
-#[derive(Debug, Clone, Copy, Default, Component, DerefMut, Deref, Inspectable)]
-pub struct Hurtbox(pub Area);
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    Component,
+    DerefMut,
+    Deref,
+    Inspectable,
+    PartialEq,
+    Serialize,
+    Deserialize,
+)]
+pub struct Hurtbox(pub CompoundArea);
 
-#[derive(Clone, Component)]
+#[derive(Debug, Clone, Component, Serialize, Deserialize)]
 pub struct Grabable {
     pub size: f32,
     pub queue: Vec<GrabDescription>,
@@ -89,10 +195,21 @@ impl Default for Grabable {
     }
 }
 
-#[derive(Default, Clone, Copy, Deref, DerefMut, Debug, Component, Inspectable, PartialEq)]
-pub struct Hitbox(pub Area);
+#[derive(
+    Default,
+    Clone,
+    Deref,
+    DerefMut,
+    Debug,
+    Component,
+    Inspectable,
+    PartialEq,
+    Serialize,
+    Deserialize,
+)]
+pub struct Hitbox(pub CompoundArea);
 
-#[derive(Clone, Copy, Debug, Inspectable, Eq, PartialEq, Default)]
+#[derive(Clone, Copy, Debug, Inspectable, Eq, PartialEq, Default, Serialize, Deserialize)]
 pub enum AttackHeight {
     Low,
     #[default]
@@ -100,7 +217,7 @@ pub enum AttackHeight {
     High,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Inspectable, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Inspectable, Default, Serialize, Deserialize)]
 pub enum Lifetime {
     #[default]
     Phase,
@@ -109,7 +226,7 @@ pub enum Lifetime {
     Forever,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Default, Inspectable)]
+#[derive(Debug, Clone, Copy, PartialEq, Default, Inspectable, Serialize, Deserialize)]
 pub struct HitProperty<PropType: Clone + Copy + PartialEq + Default + Inspectable> {
     pub on_hit: PropType,
     pub on_block: PropType,
@@ -149,16 +266,63 @@ pub type Stun = HitProperty<usize>;
 pub type Knockback = HitProperty<Vec3>;
 pub type Pushback = HitProperty<Vec3>;
 
-#[derive(Debug, Clone, Copy, PartialEq, Inspectable, Component, Default)]
+/// Clean hit on an opponent that is committed to a move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Inspectable, Serialize, Deserialize)]
+pub enum CounterType {
+    /// Landed in the startup or active phases
+    CounterHit,
+    /// Landed in the recovery
+    PunishCounter,
+}
+impl CounterType {
+    pub fn meter_reward(self) -> i32 {
+        match self {
+            CounterType::CounterHit => 10,
+            CounterType::PunishCounter => 20,
+        }
+    }
+}
+
+/// Replaces the regular on hit value for counter hits, None keeps the regular one
+#[derive(Debug, Clone, Copy, PartialEq, Default, Inspectable, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CounterProperty<PropType: Clone + Copy + PartialEq + Default + Inspectable> {
+    pub counter_hit: Option<PropType>,
+    pub punish_counter: Option<PropType>,
+}
+impl<T: Clone + Copy + PartialEq + Default + Inspectable> CounterProperty<T> {
+    pub fn get(&self, counter: Option<CounterType>, regular: T) -> T {
+        match counter {
+            Some(CounterType::CounterHit) => self.counter_hit,
+            Some(CounterType::PunishCounter) => self.punish_counter,
+            None => None,
+        }
+        .unwrap_or(regular)
+    }
+}
+
+pub type CounterDamage = CounterProperty<i32>;
+pub type CounterStun = CounterProperty<usize>;
+/// Used instead of the knockback, can launch where the regular hit wouldn't
+pub type CounterLaunch = CounterProperty<Vec3>;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Inspectable, Component, Default, Serialize, Deserialize,
+)]
 pub struct OnHitEffect {
     pub fixed_height: Option<AttackHeight>,
+    /// Not attached to the attacker, for projectile invulnerability
+    pub projectile: bool,
     pub damage: Damage,
     pub stun: Stun,
     pub knockback: Knockback,
     pub pushback: Pushback,
+    pub counter_damage: CounterDamage,
+    pub counter_stun: CounterStun,
+    pub counter_launch: CounterLaunch,
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Inspectable, Component)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Inspectable, Component, Serialize, Deserialize)]
 pub struct HitTracker {
     pub hits: usize,
     pub last_hit_frame: Option<usize>,
@@ -177,17 +341,23 @@ impl Default for HitTracker {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Inspectable)]
+#[derive(Debug, Clone, PartialEq, Inspectable, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SpawnDescriptor {
     pub damage: Damage,
     pub stun: Stun,
     pub hits: usize,
     pub knockback: Knockback,
     pub pushback: Pushback,
+    pub counter_damage: CounterDamage,
+    pub counter_stun: CounterStun,
+    pub counter_launch: CounterLaunch,
 
     /// Hitbox is moved at this constant speed
     pub speed: Vec3,
     pub hitbox: Hitbox,
+    /// Changes to the hitbox after it came out, positioned like the first one
+    pub keyframes: Vec<HitboxKeyframe>,
     pub fixed_height: Option<AttackHeight>,
     pub lifetime: Lifetime,
     pub attached_to_player: bool,
@@ -200,17 +370,22 @@ impl Default for SpawnDescriptor {
             stun: (15, 5).into(),
             speed: Vec3::ZERO,
             hits: 1,
-            hitbox: Hitbox(Area::new(1.0, 1.2, 0.2, 0.2)),
+            hitbox: Hitbox(Area::new(1.0, 1.2, 0.2, 0.2).into()),
+            keyframes: vec![],
             fixed_height: None,
             lifetime: Lifetime::default(),
             attached_to_player: true,
             knockback: (Vec3::X * 2.0, Vec3::X * 1.0).into(),
             pushback: (Vec3::X * 1.0, Vec3::X * 0.5).into(),
+            counter_damage: CounterDamage::default(),
+            counter_stun: CounterStun::default(),
+            counter_launch: CounterLaunch::default(),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Inspectable)]
+#[derive(Debug, Clone, Copy, PartialEq, Inspectable, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GrabDescription {
     pub damage: i32,
     pub impulse: Vec3,
@@ -229,3 +404,104 @@ impl Default for GrabDescription {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn invulnerability_kinds() {
+        // Projectile, attacker in the air
+        let strike = (false, false);
+        let projectile = (true, false);
+        let air_strike = (false, true);
+
+        for (
+            invulnerability,
+            strike_avoided,
+            projectile_avoided,
+            air_strike_avoided,
+            grab_avoided,
+        ) in [
+            (Invulnerability::Full, true, true, true, true),
+            (Invulnerability::Strike, true, false, true, false),
+            (Invulnerability::Throw, false, false, false, true),
+            (Invulnerability::Projectile, false, true, false, false),
+            (Invulnerability::Air, false, false, true, false),
+        ] {
+            for ((projectile, airborne), avoided) in [
+                (strike, strike_avoided),
+                (projectile, projectile_avoided),
+                (air_strike, air_strike_avoided),
+            ] {
+                assert_eq!(
+                    invulnerability.avoids_attack(projectile, airborne),
+                    avoided,
+                    "{:?} against projectile: {}, airborne: {}",
+                    invulnerability,
+                    projectile,
+                    airborne
+                );
+            }
+            assert_eq!(
+                invulnerability.avoids_grab(),
+                grab_avoided,
+                "{:?}",
+                invulnerability
+            );
+        }
+    }
+
+    #[test]
+    fn defensive_properties_parse() {
+        let phase: Phase =
+            ron::from_str("(duration: 10, invulnerability: Some(Throw), armor: Some((hits: 2)))")
+                .unwrap();
+
+        assert_eq!(phase.invulnerability, Some(Invulnerability::Throw));
+        assert_eq!(
+            phase.armor,
+            Some(Armor {
+                hits: 2,
+                recoverable: false
+            })
+        );
+    }
+
+    #[test]
+    fn counter_overrides() {
+        let descriptor: SpawnDescriptor = ron::from_str(
+            "(counter_stun: (counter_hit: Some(25)), counter_launch: (punish_counter: Some((1.0, 6.0, 0.0))))",
+        )
+        .unwrap();
+        let stun = descriptor.stun.on_hit;
+        let knockback = descriptor.knockback.on_hit;
+
+        assert_eq!(descriptor.counter_stun.get(None, stun), stun);
+        assert_eq!(
+            descriptor
+                .counter_stun
+                .get(Some(CounterType::CounterHit), stun),
+            25
+        );
+        assert_eq!(
+            descriptor
+                .counter_stun
+                .get(Some(CounterType::PunishCounter), stun),
+            stun
+        );
+
+        assert_eq!(
+            descriptor
+                .counter_launch
+                .get(Some(CounterType::CounterHit), knockback),
+            knockback
+        );
+        assert_eq!(
+            descriptor
+                .counter_launch
+                .get(Some(CounterType::PunishCounter), knockback),
+            Vec3::new(1.0, 6.0, 0.0)
+        );
+    }
+}
@@ -2,14 +2,15 @@ use std::collections::HashSet;
 
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 use types::GameButton;
 
 use crate::{resources::Cost, Inventory, Resources};
 
-use super::{MoveId, MoveType, Requirements};
+use super::{Armor, MoveId, MoveType, Requirements};
 
 /// Situation is supposed to contain everything needed to deduce the next phase of a move
-#[derive(Inspectable, Eq, PartialEq, Debug, Component, Clone, Default)]
+#[derive(Inspectable, Eq, PartialEq, Debug, Component, Clone, Default, Serialize, Deserialize)]
 pub struct MoveSituation {
     // Owned
     pub start_frame: i32,
@@ -19,6 +20,8 @@ pub struct MoveSituation {
     pub move_type: Option<MoveType>,
     pub cancellable: bool,
     pub cancellable_since: Option<usize>,
+    /// Hits taken on armor in the current phase
+    pub absorbed_hits: u8,
 
     // Other components
     // Clone into this whenever initialized or changed
@@ -26,6 +29,7 @@ pub struct MoveSituation {
     pub resources: Resources,
     pub inventory: Inventory,
     #[inspectable(ignore)]
+    #[serde(serialize_with = "types::serialize_sorted")]
     pub buttons_held: HashSet<GameButton>,
     pub grounded: bool,
 }
@@ -80,4 +84,14 @@ impl MoveSituation {
     pub fn register_hit(&mut self) {
         self.hit_registered = true;
     }
+
+    /// False once the armor has taken all the hits it can
+    pub fn absorb_hit(&mut self, armor: Armor) -> bool {
+        if self.absorbed_hits < armor.hits {
+            self.absorbed_hits += 1;
+            true
+        } else {
+            false
+        }
+    }
 }
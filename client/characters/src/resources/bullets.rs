@@ -1,7 +1,8 @@
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
-#[derive(Inspectable, Component, Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Inspectable, Component, Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Bullets {
     available: i32,
 }
@@ -14,6 +15,9 @@ impl Bullets {
     pub fn reset(&mut self) {
         *self = Self::default();
     }
+    pub fn set(&mut self, amount: i32) {
+        self.available = amount;
+    }
     pub fn has_one(&self) -> bool {
         self.available > 0
     }
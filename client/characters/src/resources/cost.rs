@@ -1,8 +1,8 @@
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Inspectable, Clone, Eq, PartialEq, Copy)]
+#[derive(Debug, Default, Inspectable, Clone, Eq, PartialEq, Copy, Serialize, Deserialize)]
 pub struct Cost {
     pub meter: i32,
-    pub charge: bool,
     pub bullet: bool,
 }
@@ -1,7 +1,8 @@
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
-#[derive(Inspectable, Component, Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Inspectable, Component, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Meter {
     value: i32,
     max: i32,
@@ -20,9 +21,18 @@ impl Meter {
     pub fn reset(&mut self) {
         *self = Self::default();
     }
+    pub fn get(&self) -> i32 {
+        self.value
+    }
+    pub fn max(&self) -> i32 {
+        self.max
+    }
     pub fn get_percentage(&self) -> f32 {
         (self.value as f32 / self.max as f32) * 100.0
     }
+    pub fn set(&mut self, value: i32) {
+        self.value = value.min(self.max);
+    }
     pub fn can_afford(&self, amount: i32) -> bool {
         self.value >= amount
     }
@@ -10,8 +10,11 @@ pub use meter::Meter;
 
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
-#[derive(Inspectable, Debug, Component, Clone, Copy, Default, PartialEq, Eq)]
+#[derive(
+    Inspectable, Debug, Component, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
 pub struct Resources {
     pub bullets: Bullets,
     pub charge: Charge,
@@ -26,9 +29,7 @@ impl Resources {
 
     pub fn can_afford(&self, cost: &Option<Cost>) -> bool {
         if let Some(costs) = cost {
-            self.meter.can_afford(costs.meter)
-                && (!costs.charge || self.charge.is_charged())
-                && (!costs.bullet || self.bullets.has_one())
+            self.meter.can_afford(costs.meter) && (!costs.bullet || self.bullets.has_one())
         } else {
             true
         }
@@ -38,10 +39,6 @@ impl Resources {
         if let Some(costs) = cost {
             self.meter.pay(costs.meter);
 
-            if costs.charge {
-                self.charge.consume_charge();
-            }
-
             if costs.bullet {
                 self.bullets.use_one();
             }
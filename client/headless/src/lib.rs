@@ -0,0 +1,207 @@
+use bevy::{input::InputPlugin, prelude::*, transform::TransformPlugin};
+use characters::Character;
+use input_parsing::{
+    testing::{parse_input, PreWrittenInputBundle, PreWrittenStream},
+    InputEvent, InvalidInput,
+};
+use std::path::PathBuf;
+use time::{Clock, ClockControl, CombatApp, CombatStage, GameState, RoundResult};
+use types::{Player, Players};
+use whoops_all_grapplers_lib::{
+    AssetFolder, ChecksumLog, GameRng, Netplay, ReplayPlayback, WAGHeadless,
+};
+
+mod scenario;
+pub use scenario::{Scenario, ScenarioError, ScenarioInput};
+
+mod setup;
+pub use setup::{PlayerSetup, RoundSetup};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundOutcome {
+    Win(Player),
+    Draw,
+    /// Stopped before anyone won or the time ran out
+    Unfinished,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundReport {
+    /// Frame the round ended or was stopped on
+    pub frames: usize,
+    pub outcome: RoundOutcome,
+}
+
+/// The game's assets folder, headless runs read the same character definitions the game does
+pub fn game_assets() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../main/assets")
+}
+
+/// Steps the combat simulation one frame per update, without a window, audio or assets.
+/// Nothing waits for real time, so a round runs as fast as the CPU allows
+pub struct HeadlessRunner {
+    app: App,
+}
+impl Default for HeadlessRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl HeadlessRunner {
+    /// A new match, waiting in the shop like the game does when it starts.
+    /// Always rolls with the same seed, so the same inputs give the same checksums
+    pub fn new() -> Self {
+        Self::seeded(0)
+    }
+
+    pub fn seeded(seed: u64) -> Self {
+        let mut app = App::new();
+        app.insert_resource(GameRng::new(seed));
+        Self::build(app)
+    }
+
+    /// Rounds start on their own and play out the recorded inputs
+    pub fn replay(playback: ReplayPlayback) -> Self {
+        let mut app = App::new();
+        // Picks the characters and the seed, so it has to be in before the plugins
+        app.insert_resource(playback);
+        Self::build(app)
+    }
+
+    /// One side of an online match, plays its local player with the keyboard or a pad
+    pub fn netplay(netplay: Netplay) -> Self {
+        let mut app = App::new();
+        // Same as with replays, players and the seed come from it
+        app.insert_resource(netplay);
+        Self::build(app)
+    }
+
+    fn build(mut app: App) -> Self {
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(TransformPlugin::default())
+            .add_plugin(InputPlugin::default())
+            .insert_resource(AssetFolder(game_assets()))
+            .add_plugins(WAGHeadless)
+            .add_combat_system(CombatStage::Inputs, parse_input::<PreWrittenStream>)
+            // Every update is a frame, no need to wait for real time to pass
+            .insert_resource(ClockControl::unpaced());
+
+        // Runs the startup systems, players exist after this
+        app.update();
+        Self { app }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.app.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+
+    pub fn frame(&self) -> usize {
+        self.app.world.resource::<Clock>().frame
+    }
+
+    pub fn in_combat(&self) -> bool {
+        *self.app.world.resource::<State<GameState>>().current() == GameState::Combat
+    }
+
+    pub fn checksums(&self) -> &ChecksumLog {
+        self.app.world.resource::<ChecksumLog>()
+    }
+
+    /// Leave the shop, like pressing start
+    pub fn start_round(&mut self) {
+        self.app
+            .world
+            .resource_mut::<Input<KeyCode>>()
+            .press(KeyCode::Return);
+        self.step();
+        // Held down, the next round would start as soon as this one ends
+        self.app
+            .world
+            .resource_mut::<Input<KeyCode>>()
+            .release(KeyCode::Return);
+    }
+
+    /// Move players and the clock to where the round should start from.
+    /// Meant for right after start_round, which puts everything back to the default spawn,
+    /// and set_inputs, which replaces the input parser the charge is set on
+    pub fn apply_setup(&mut self, setup: &RoundSetup) {
+        setup.apply(&mut self.app.world);
+    }
+
+    /// Player reads these instead of a controller, one entry per frame
+    pub fn set_inputs(
+        &mut self,
+        player: Player,
+        events: Vec<Option<InputEvent>>,
+    ) -> Result<(), InvalidInput> {
+        let entity = self.app.world.resource::<Players>().get(player);
+        let character = self.app.world.get::<Character>(entity).unwrap().clone();
+
+        let bundle = PreWrittenInputBundle::new(events, character.get_inputs())?;
+        self.app.world.entity_mut(entity).insert_bundle(bundle);
+        Ok(())
+    }
+
+    pub fn step(&mut self) {
+        self.app.update();
+    }
+
+    pub fn run(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.step();
+        }
+    }
+
+    /// Run until the round ends, or until the clock reaches max_frames
+    pub fn run_round(&mut self, max_frames: Option<usize>) -> RoundReport {
+        while self.in_combat() && max_frames.map_or(true, |max| self.frame() < max) {
+            self.step();
+        }
+
+        RoundReport {
+            frames: self.frame(),
+            outcome: self.outcome(),
+        }
+    }
+
+    /// Play every round of the replay this runner was made with
+    pub fn run_replay(&mut self) -> Vec<RoundReport> {
+        let mut rounds = vec![];
+        loop {
+            if self.in_combat() {
+                rounds.push(self.run_round(None));
+            } else if self.replay_continues() {
+                // Next round starts on its own
+                self.step();
+            } else {
+                return rounds;
+            }
+        }
+    }
+
+    fn replay_continues(&self) -> bool {
+        self.app
+            .world
+            .get_resource::<ReplayPlayback>()
+            .map_or(false, |playback| playback.has_next_round())
+    }
+
+    fn outcome(&self) -> RoundOutcome {
+        if self.in_combat() {
+            return RoundOutcome::Unfinished;
+        }
+
+        match self.app.world.get_resource::<RoundResult>() {
+            Some(RoundResult {
+                winner: Some(player),
+            }) => RoundOutcome::Win(*player),
+            Some(RoundResult { winner: None }) => RoundOutcome::Draw,
+            // Still in the shop from before the first round
+            None => RoundOutcome::Unfinished,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Instant,
+};
+use whoops_all_grapplers_headless::{HeadlessRunner, RoundOutcome, RoundReport, Scenario};
+use whoops_all_grapplers_lib::ReplayPlayback;
+
+enum Job {
+    Scenario(PathBuf),
+    Replay(PathBuf),
+}
+
+fn main() {
+    // Usage: whoops-all-grapplers-headless [--checksums <dir>] [--scenario <file>]... [--replay <file>]...
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let mut checksum_dir = None;
+    let mut jobs = vec![];
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut path = || {
+            PathBuf::from(
+                iter.next()
+                    .unwrap_or_else(|| panic!("{} requires a path", arg)),
+            )
+        };
+
+        match arg.as_str() {
+            "--checksums" => checksum_dir = Some(path()),
+            "--scenario" => jobs.push(Job::Scenario(path())),
+            "--replay" => jobs.push(Job::Replay(path())),
+            _ => panic!("Unknown argument {}", arg),
+        }
+    }
+
+    let mut failed = false;
+    for job in jobs {
+        let (result, path) = match job {
+            Job::Scenario(path) => (run_scenario(&path), path),
+            Job::Replay(path) => (run_replay(&path), path),
+        };
+
+        match result {
+            Ok(runner) => {
+                if let Some(dir) = &checksum_dir {
+                    let file = dir.join(path.file_name().unwrap_or_default());
+                    if let Err(error) = runner.checksums().save(&file) {
+                        eprintln!("Failed to save checksums to {:?}: {}", file, error);
+                        failed = true;
+                    }
+                }
+            }
+            Err(error) => {
+                eprintln!("Could not run {:?}: {}", path, error);
+                failed = true;
+            }
+        }
+    }
+
+    std::process::exit(if failed { 1 } else { 0 });
+}
+
+fn run_scenario(path: &Path) -> Result<HeadlessRunner, String> {
+    let scenario = Scenario::load(path).map_err(|error| error.to_string())?;
+
+    let start = Instant::now();
+    let (runner, report) = scenario.run().map_err(|error| error.to_string())?;
+    let name = if scenario.name.is_empty() {
+        path.display().to_string()
+    } else {
+        scenario.name
+    };
+    print_reports(&name, &[report], start);
+    Ok(runner)
+}
+
+fn run_replay(path: &Path) -> Result<HeadlessRunner, String> {
+    let playback = ReplayPlayback::load(path).map_err(|error| error.to_string())?;
+
+    let start = Instant::now();
+    let mut runner = HeadlessRunner::replay(playback);
+    let reports = runner.run_replay();
+    print_reports(&path.display().to_string(), &reports, start);
+    Ok(runner)
+}
+
+fn print_reports(name: &str, reports: &[RoundReport], start: Instant) {
+    let frames: usize = reports.iter().map(|report| report.frames).sum();
+    println!(
+        "{}: {} frames in {:.2}s",
+        name,
+        frames,
+        start.elapsed().as_secs_f32()
+    );
+
+    for (index, report) in reports.iter().enumerate() {
+        let outcome = match report.outcome {
+            RoundOutcome::Win(player) => format!("player {} won", player),
+            RoundOutcome::Draw => "draw".to_owned(),
+            RoundOutcome::Unfinished => "unfinished".to_owned(),
+        };
+        println!(
+            "  round {}: {} on frame {}",
+            index + 1,
+            outcome,
+            report.frames
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use input_parsing::{InputEvent, InvalidInput, ParseErrorKind};
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt::Display, fs, io, path::Path};
+use types::Player;
+
+use crate::{HeadlessRunner, RoundReport, RoundSetup};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScenarioInput {
+    /// Input notation, one character per frame
+    Inputs(String),
+    /// Frames without any new inputs
+    Idle(usize),
+}
+
+#[derive(Debug)]
+pub enum ScenarioError {
+    Io(io::Error),
+    Parse(ron::Error),
+    Notation(ParseErrorKind),
+    Character(InvalidInput),
+}
+impl Display for ScenarioError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScenarioError::Io(error) => write!(f, "could not access scenario file: {}", error),
+            ScenarioError::Parse(error) => write!(f, "invalid scenario: {}", error),
+            ScenarioError::Notation(error) => write!(f, "invalid scenario inputs: {}", error),
+            ScenarioError::Character(error) => write!(f, "could not load character: {}", error),
+        }
+    }
+}
+impl Error for ScenarioError {}
+
+/// A round played from the start with scripted inputs
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scenario {
+    pub name: String,
+    pub setup: RoundSetup,
+    pub p1: Vec<ScenarioInput>,
+    pub p2: Vec<ScenarioInput>,
+    /// Frames to run for, by default until both players are out of inputs
+    pub frames: Option<usize>,
+}
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Self, ScenarioError> {
+        let contents = fs::read_to_string(path).map_err(ScenarioError::Io)?;
+        ron::from_str(&contents).map_err(ScenarioError::Parse)
+    }
+
+    /// One entry per frame
+    pub fn events(&self, player: Player) -> Result<Vec<Option<InputEvent>>, ScenarioError> {
+        let inputs = match player {
+            Player::One => &self.p1,
+            Player::Two => &self.p2,
+        };
+
+        let mut events = vec![];
+        for input in inputs {
+            match input {
+                ScenarioInput::Inputs(notation) => {
+                    for ch in notation.chars() {
+                        let event = InputEvent::try_from(ch).map_err(ScenarioError::Notation)?;
+                        events.push(Some(event));
+                    }
+                }
+                ScenarioInput::Idle(frames) => events.extend(vec![None; *frames]),
+            }
+        }
+        Ok(events)
+    }
+
+    /// Start a round with the inputs in place, without running it
+    pub fn setup(&self) -> Result<HeadlessRunner, ScenarioError> {
+        let mut runner = HeadlessRunner::new();
+        runner.start_round();
+        for player in [Player::One, Player::Two] {
+            runner
+                .set_inputs(player, self.events(player)?)
+                .map_err(ScenarioError::Character)?;
+        }
+        runner.apply_setup(&self.setup);
+        Ok(runner)
+    }
+
+    pub fn run(&self) -> Result<(HeadlessRunner, RoundReport), ScenarioError> {
+        let frames = match self.frames {
+            Some(frames) => frames,
+            None => self
+                .events(Player::One)?
+                .len()
+                .max(self.events(Player::Two)?.len()),
+        };
+
+        let mut runner = self.setup()?;
+        // Counted from the start of the round
+        let report = runner.run_round(Some(runner.frame() + frames));
+        Ok((runner, report))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use characters::ItemId;
+
+    #[test]
+    fn events_are_per_frame() {
+        let scenario: Scenario = ron::from_str(
+            r#"(
+                name: "Jump",
+                p1: [Idle(2), Inputs("8f"), Idle(1)],
+            )"#,
+        )
+        .unwrap();
+
+        let events = scenario.events(Player::One).unwrap();
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[0], None);
+        assert_eq!(events[2], Some(InputEvent::try_from('8').unwrap()));
+        assert_eq!(events[3], Some(InputEvent::try_from('f').unwrap()));
+        assert!(scenario.events(Player::Two).unwrap().is_empty());
+    }
+
+    #[test]
+    fn setup_only_needs_what_changes() {
+        let scenario: Scenario = ron::from_str(
+            r#"(
+                setup: (
+                    frame: Some(600),
+                    p2: (health: Some(10), items: [Gun]),
+                ),
+            )"#,
+        )
+        .unwrap();
+
+        assert_eq!(scenario.setup.frame, Some(600));
+        assert_eq!(scenario.setup.p2.health, Some(10));
+        assert_eq!(scenario.setup.p2.items, vec![ItemId::Gun]);
+        assert!(scenario.setup.p1.health.is_none());
+        assert!(scenario.setup.p1.state.is_none());
+    }
+
+    #[test]
+    fn unknown_notation_is_an_error() {
+        let scenario = Scenario {
+            p1: vec![ScenarioInput::Inputs("8x".to_owned())],
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            scenario.events(Player::One),
+            Err(ScenarioError::Notation(ParseErrorKind::UnknownCharacter(
+                'x'
+            )))
+        ));
+    }
+}
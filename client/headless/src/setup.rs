@@ -0,0 +1,115 @@
+use bevy::{ecs::world::EntityMut, prelude::*};
+use characters::{Character, Inventory, ItemId, Resources};
+use input_parsing::InputParser;
+use player_state::PlayerState;
+use serde::{Deserialize, Serialize};
+use time::Clock;
+use types::{Facing, Player, Players};
+use whoops_all_grapplers_lib::{Health, Sides, GROUND_PLANE_HEIGHT};
+
+/// Starting point for one player, anything left out is what a new round starts with
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerSetup {
+    /// Plays with this instead of the definition from the assets, like one with an edited move
+    pub character: Option<Character>,
+    pub position: Option<Vec3>,
+    /// Kept until the players switch sides, like for cross-ups or jumping away
+    pub facing: Option<Facing>,
+    pub health: Option<i32>,
+    pub meter: Option<i32>,
+    /// Frames charged, for every input that starts with a charge
+    pub charge: Option<usize>,
+    pub bullets: Option<i32>,
+    pub items: Vec<ItemId>,
+    /// Like mid-air or in stun, stun ends on a clock frame.
+    /// Grounded states put the player on the ground, as they would be turned into air states otherwise
+    pub state: Option<PlayerState>,
+}
+impl PlayerSetup {
+    fn apply(&self, player: &mut EntityMut, frame: usize) {
+        if let Some(character) = &self.character {
+            player
+                .get_mut::<InputParser>()
+                .unwrap()
+                .reload(character.get_inputs())
+                .expect("Setup has a definition with invalid inputs");
+            player.insert(character.clone());
+        }
+
+        let mut transform = player.get_mut::<Transform>().unwrap();
+        if let Some(position) = self.position {
+            transform.translation = position;
+        }
+        if self.state.as_ref().map_or(false, PlayerState::is_grounded) {
+            transform.translation.y = GROUND_PLANE_HEIGHT;
+        }
+        if let Some(facing) = self.facing {
+            player.insert(facing);
+        }
+
+        if let Some(health) = self.health {
+            player.get_mut::<Health>().unwrap().set(health);
+        }
+
+        let mut resources = player.get_mut::<Resources>().unwrap();
+        if let Some(meter) = self.meter {
+            resources.meter.set(meter);
+        }
+        if let Some(bullets) = self.bullets {
+            resources.bullets.set(bullets);
+        }
+
+        if let Some(charge) = self.charge {
+            // Resources only mirror the parser, which would overwrite the charge on the next frame
+            player
+                .get_mut::<InputParser>()
+                .unwrap()
+                .set_charge(charge, frame);
+        }
+
+        let mut inventory = player.get_mut::<Inventory>().unwrap();
+        for item in &self.items {
+            inventory.add_item(*item);
+        }
+
+        if let Some(state) = &self.state {
+            player.insert(state.clone());
+        }
+    }
+}
+
+/// Where a round starts from, instead of the default spawn
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoundSetup {
+    /// Clock frame the round starts on, the timer runs out sooner if this is set
+    pub frame: Option<usize>,
+    pub p1: PlayerSetup,
+    pub p2: PlayerSetup,
+}
+impl RoundSetup {
+    pub fn get(&self, player: Player) -> &PlayerSetup {
+        match player {
+            Player::One => &self.p1,
+            Player::Two => &self.p2,
+        }
+    }
+
+    pub(crate) fn apply(&self, world: &mut World) {
+        if let Some(frame) = self.frame {
+            world.resource_mut::<Clock>().frame = frame;
+        }
+
+        let frame = world.resource::<Clock>().frame;
+        let players = world.resource::<Players>();
+        let (one, two) = (players.one, players.two);
+        self.p1.apply(&mut world.entity_mut(one), frame);
+        self.p2.apply(&mut world.entity_mut(two), frame);
+
+        // The sides the setup starts from, so players don't turn around without crossing
+        let x = |entity| world.get::<Transform>(entity).unwrap().translation.x;
+        let one_on_right = x(one) > x(two);
+        world.insert_resource(Sides { one_on_right });
+    }
+}
@@ -0,0 +1,76 @@
+use bevy::prelude::*;
+use map_macro::map;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use types::{GameButton, Player};
+
+use crate::input_stream::ParrotAction;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KeyBinding {
+    Up,
+    Down,
+    Left,
+    Right,
+    Button(GameButton),
+    Parrot(ParrotAction),
+}
+
+#[derive(Debug, Clone, Deref, DerefMut, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct KeyboardLayout(pub HashMap<KeyCode, KeyBinding>);
+
+/// Key layouts for two players sharing one keyboard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyboardBindings {
+    pub one: KeyboardLayout,
+    pub two: KeyboardLayout,
+}
+impl KeyboardBindings {
+    pub fn get(&self, player: Player) -> &KeyboardLayout {
+        match player {
+            Player::One => &self.one,
+            Player::Two => &self.two,
+        }
+    }
+}
+impl Default for KeyboardBindings {
+    fn default() -> Self {
+        Self {
+            one: KeyboardLayout(map! {
+                KeyCode::W => KeyBinding::Up,
+                KeyCode::S => KeyBinding::Down,
+                KeyCode::A => KeyBinding::Left,
+                KeyCode::D => KeyBinding::Right,
+                KeyCode::J => KeyBinding::Button(GameButton::Fast),
+                KeyCode::K => KeyBinding::Button(GameButton::Strong),
+                KeyCode::L => KeyBinding::Button(GameButton::Grab),
+                KeyCode::I => KeyBinding::Button(GameButton::Equipment),
+                KeyCode::O => KeyBinding::Button(GameButton::Taunt),
+                KeyCode::Key1 => KeyBinding::Parrot(ParrotAction::CycleMode),
+                KeyCode::Key2 => KeyBinding::Parrot(ParrotAction::NextSlot),
+                KeyCode::Key3 => KeyBinding::Parrot(ParrotAction::CycleSelection),
+                KeyCode::Key4 => KeyBinding::Parrot(ParrotAction::ToggleRelative),
+                KeyCode::Key5 => KeyBinding::Parrot(ParrotAction::Save),
+                KeyCode::Key6 => KeyBinding::Parrot(ParrotAction::Load),
+            }),
+            two: KeyboardLayout(map! {
+                KeyCode::Up => KeyBinding::Up,
+                KeyCode::Down => KeyBinding::Down,
+                KeyCode::Left => KeyBinding::Left,
+                KeyCode::Right => KeyBinding::Right,
+                KeyCode::Numpad1 => KeyBinding::Button(GameButton::Fast),
+                KeyCode::Numpad2 => KeyBinding::Button(GameButton::Strong),
+                KeyCode::Numpad3 => KeyBinding::Button(GameButton::Grab),
+                KeyCode::Numpad5 => KeyBinding::Button(GameButton::Equipment),
+                KeyCode::Numpad6 => KeyBinding::Button(GameButton::Taunt),
+                KeyCode::Numpad7 => KeyBinding::Parrot(ParrotAction::CycleMode),
+                KeyCode::Numpad8 => KeyBinding::Parrot(ParrotAction::NextSlot),
+                KeyCode::Numpad9 => KeyBinding::Parrot(ParrotAction::CycleSelection),
+                KeyCode::Numpad4 => KeyBinding::Parrot(ParrotAction::ToggleRelative),
+                KeyCode::NumpadSubtract => KeyBinding::Parrot(ParrotAction::Save),
+                KeyCode::NumpadAdd => KeyBinding::Parrot(ParrotAction::Load),
+            }),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use bevy::asset::FileAssetIo;
+use ron::ser::PrettyConfig;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::path::PathBuf;
+use types::Player;
+
+mod keyboard;
+mod pad;
+
+pub use keyboard::{KeyBinding, KeyboardBindings, KeyboardLayout};
+pub(crate) use pad::save_pad_layouts;
+pub use pad::{PadBinding, PadLayout, PadLayouts};
+
+const CONTROLS_CONFIG: &str = "assets/config/controls.ron";
+
+/// Which device a player is controlled with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum InputSource {
+    #[default]
+    Gamepad,
+    Keyboard,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct InputSources {
+    pub one: InputSource,
+    pub two: InputSource,
+}
+impl InputSources {
+    pub fn get(&self, player: Player) -> InputSource {
+        match player {
+            Player::One => self.one,
+            Player::Two => self.two,
+        }
+    }
+}
+
+/// Simultaneous opposing cardinal direction resolution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SocdMode {
+    /// Most recently pressed direction is used
+    #[default]
+    LastInputWins,
+    /// Opposite directions cancel out
+    Neutral,
+    /// Up beats down, left and right cancel out
+    UpPriority,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct SocdModes {
+    pub one: SocdMode,
+    pub two: SocdMode,
+}
+impl SocdModes {
+    pub fn get(&self, player: Player) -> SocdMode {
+        match player {
+            Player::One => self.one,
+            Player::Two => self.two,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct ControlsConfig {
+    pub sources: InputSources,
+    pub socd: SocdModes,
+    pub keyboard: KeyboardBindings,
+}
+impl ControlsConfig {
+    pub fn load() -> Self {
+        load_config(CONTROLS_CONFIG)
+    }
+}
+
+fn config_path(file: &str) -> PathBuf {
+    FileAssetIo::get_root_path().join(file)
+}
+
+fn load_config<T: DeserializeOwned + Default>(file: &str) -> T {
+    try_load_config(file).unwrap_or_default()
+}
+
+/// None if the file is missing or invalid
+pub(crate) fn try_load_config<T: DeserializeOwned>(file: &str) -> Option<T> {
+    let path = config_path(file);
+
+    let contents = std::fs::read_to_string(&path).ok()?;
+    match ron::from_str(&contents) {
+        Ok(config) => Some(config),
+        Err(error) => {
+            println!("Failed to parse {:?}: {}", path, error);
+            None
+        }
+    }
+}
+
+pub(crate) fn save_config<T: Serialize>(file: &str, config: &T) {
+    let path = config_path(file);
+
+    let result = ron::ser::to_string_pretty(config, PrettyConfig::default())
+        .map_err(|error| error.to_string())
+        .and_then(|contents| {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+            }
+            std::fs::write(&path, contents).map_err(|error| error.to_string())
+        });
+
+    if let Err(error) = result {
+        println!("Failed to save {:?}: {}", path, error);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn shipped_config_parses() {
+        let config: ControlsConfig =
+            ron::from_str(include_str!("../../../main/assets/config/controls.ron")).unwrap();
+
+        assert_eq!(config.sources.get(Player::One), InputSource::Gamepad);
+        assert_eq!(
+            config.keyboard.one.get(&KeyCode::J),
+            Some(&KeyBinding::Button(types::GameButton::Fast))
+        );
+        assert_eq!(
+            config.keyboard.two.get(&KeyCode::Left),
+            Some(&KeyBinding::Left)
+        );
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let config: ControlsConfig =
+            ron::from_str("(sources: (one: Keyboard, two: Gamepad))").unwrap();
+
+        assert_eq!(config.sources.get(Player::One), InputSource::Keyboard);
+        assert_eq!(
+            config.keyboard.one.len(),
+            KeyboardBindings::default().one.len()
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use map_macro::map;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use types::GameButton;
+
+use crate::input_stream::ParrotAction;
+
+use super::{load_config, save_config};
+
+const PAD_LAYOUTS_CONFIG: &str = "assets/config/pad_layouts.ron";
+
+/// What a physical gamepad button does
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PadBinding {
+    Button(GameButton),
+    /// Presses all of the buttons at once, for example to get a MultiPress
+    Macro(Vec<GameButton>),
+    Parrot(ParrotAction),
+}
+impl PadBinding {
+    pub fn buttons(&self) -> Vec<GameButton> {
+        match self {
+            PadBinding::Button(button) => vec![*button],
+            PadBinding::Macro(buttons) => buttons.clone(),
+            PadBinding::Parrot(_) => vec![],
+        }
+    }
+}
+
+/// Dpad is not remappable, it is handled before the layout
+#[derive(Debug, Clone, PartialEq, Eq, Deref, DerefMut, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PadLayout(pub HashMap<GamepadButtonType, PadBinding>);
+impl Default for PadLayout {
+    fn default() -> Self {
+        Self(map! {
+            GamepadButtonType::South => PadBinding::Button(GameButton::Fast),
+            GamepadButtonType::East => PadBinding::Button(GameButton::Strong),
+            GamepadButtonType::North => PadBinding::Button(GameButton::Grab),
+            GamepadButtonType::West => PadBinding::Button(GameButton::Equipment),
+            GamepadButtonType::LeftTrigger => PadBinding::Button(GameButton::Taunt),
+            GamepadButtonType::Select => PadBinding::Parrot(ParrotAction::CycleMode),
+            GamepadButtonType::Start => PadBinding::Parrot(ParrotAction::NextSlot),
+        })
+    }
+}
+
+/// Layouts of the gamepads that have been remapped, saved to disk on change
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PadLayouts {
+    layouts: HashMap<Gamepad, PadLayout>,
+    #[serde(skip)]
+    fallback: PadLayout,
+}
+impl PadLayouts {
+    pub fn load() -> Self {
+        load_config(PAD_LAYOUTS_CONFIG)
+    }
+
+    pub fn get(&self, pad: Gamepad) -> &PadLayout {
+        self.layouts.get(&pad).unwrap_or(&self.fallback)
+    }
+
+    pub fn set(&mut self, pad: Gamepad, layout: PadLayout) {
+        self.layouts.insert(pad, layout);
+    }
+
+    /// Binding of None unbinds the button
+    pub fn remap(&mut self, pad: Gamepad, button: GamepadButtonType, binding: Option<PadBinding>) {
+        let layout = self
+            .layouts
+            .entry(pad)
+            .or_insert_with(|| self.fallback.clone());
+
+        if let Some(new_binding) = binding {
+            layout.insert(button, new_binding);
+        } else {
+            layout.remove(&button);
+        }
+    }
+}
+
+pub fn save_pad_layouts(layouts: Res<PadLayouts>) {
+    // Added happens when loading, no need to write that back
+    if layouts.is_changed() && !layouts.is_added() {
+        save_config(PAD_LAYOUTS_CONFIG, &*layouts);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn remapping_only_affects_one_pad() {
+        let mut layouts = PadLayouts::default();
+        layouts.remap(
+            Gamepad(0),
+            GamepadButtonType::RightTrigger,
+            Some(PadBinding::Macro(vec![
+                GameButton::Fast,
+                GameButton::Strong,
+            ])),
+        );
+        layouts.remap(Gamepad(0), GamepadButtonType::South, None);
+
+        let remapped = layouts.get(Gamepad(0));
+        assert_eq!(
+            remapped.get(&GamepadButtonType::RightTrigger),
+            Some(&PadBinding::Macro(vec![
+                GameButton::Fast,
+                GameButton::Strong
+            ]))
+        );
+        assert_eq!(remapped.get(&GamepadButtonType::South), None);
+        assert_eq!(
+            remapped.get(&GamepadButtonType::East),
+            Some(&PadBinding::Button(GameButton::Strong))
+        );
+
+        assert_eq!(layouts.get(Gamepad(1)), &PadLayout::default());
+    }
+
+    #[test]
+    fn layouts_survive_serialization() {
+        let mut layouts = PadLayouts::default();
+        layouts.remap(
+            Gamepad(1),
+            GamepadButtonType::RightThumb,
+            Some(PadBinding::Button(GameButton::Grab)),
+        );
+
+        let serialized = ron::to_string(&layouts).unwrap();
+        let deserialized: PadLayouts = ron::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.get(Gamepad(1)), layouts.get(Gamepad(1)));
+    }
+}
@@ -1,9 +1,10 @@
 use map_macro::set;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
 use types::{GameButton, StickPosition};
 
-#[derive(Clone, Eq, PartialEq, Debug, Default)]
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
 /// Frame is a situation, diff is a change
 pub struct Frame {
     pub stick_position: StickPosition,
@@ -25,7 +26,7 @@ impl Frame {
     }
 }
 
-#[derive(Default, Clone, PartialEq, Eq, Debug)]
+#[derive(Default, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 /// A single update in input state
 pub struct Diff {
     pub stick_move: Option<StickPosition>,
@@ -40,10 +41,22 @@ impl Diff {
             InputEvent::Release(button) => self.released = Some(add_or_init(self.released, button)),
             InputEvent::MultiPress(_) => panic!("Applying multipress to diff"),
             InputEvent::Range(_) => panic!("Applying range to diff"),
+            InputEvent::AllPoints(_) => panic!("Applying all points to diff"),
+            InputEvent::Charge(_, _, _) => panic!("Applying charge to diff"),
+            InputEvent::Optional(_) => panic!("Applying optional to diff"),
         }
 
         self
     }
+    /// Combines two diffs that happened on the same frame, later stick position wins
+    pub fn merge(self, later: Diff) -> Self {
+        Self {
+            stick_move: later.stick_move.or(self.stick_move),
+            pressed: merge_sets(self.pressed, later.pressed),
+            released: merge_sets(self.released, later.released),
+        }
+    }
+
     pub fn pressed_contains(&self, button: &GameButton) -> bool {
         if let Some(pressed) = &self.pressed {
             pressed.contains(button)
@@ -60,6 +73,15 @@ impl Diff {
         }
     }
 }
+fn merge_sets(
+    a: Option<HashSet<GameButton>>,
+    b: Option<HashSet<GameButton>>,
+) -> Option<HashSet<GameButton>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.union(&b).copied().collect()),
+        (a, b) => a.or(b),
+    }
+}
 fn add_or_init(base: Option<HashSet<GameButton>>, button: GameButton) -> HashSet<GameButton> {
     if let Some(mut pressed) = base {
         pressed.insert(button);
@@ -70,7 +92,7 @@ fn add_or_init(base: Option<HashSet<GameButton>>, button: GameButton) -> HashSet
 }
 
 /// Enum used to define move inputs.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum InputEvent {
     /// Stick must visit a point
     Point(StickPosition),
@@ -82,25 +104,11 @@ pub enum InputEvent {
     MultiPress(Vec<GameButton>),
     /// Release a button
     Release(GameButton),
-}
-impl From<char> for InputEvent {
-    fn from(ch: char) -> InputEvent {
-        if let Ok(number_token) = ch.to_string().parse::<i32>() {
-            InputEvent::Point(number_token.into())
-        } else {
-            match ch {
-                'f' => InputEvent::Press(GameButton::Fast),
-                'F' => InputEvent::Release(GameButton::Fast),
-                's' => InputEvent::Press(GameButton::Strong),
-                'S' => InputEvent::Release(GameButton::Strong),
-                'g' => InputEvent::Press(GameButton::Grab),
-                'G' => InputEvent::Release(GameButton::Grab),
-                'e' => InputEvent::Press(GameButton::Equipment),
-                'E' => InputEvent::Release(GameButton::Equipment),
-                't' => InputEvent::Press(GameButton::Taunt),
-                'T' => InputEvent::Release(GameButton::Taunt),
-                _ => panic!("Invalid character {}", ch),
-            }
-        }
-    }
+    /// Stick must visit all of the following points, in any order
+    AllPoints(Vec<StickPosition>),
+    /// Stick must stay in the points for at least this many frames and then leave.
+    /// Coming back within the last amount of frames keeps what was charged
+    Charge(Vec<StickPosition>, usize, usize),
+    /// Step can be skipped
+    Optional(Box<InputEvent>),
 }
@@ -1,17 +1,66 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Display,
+};
 
 use crate::{
     helper_types::{Diff, Frame},
     input_stream::InputStream,
-    motion_input::MotionInput,
+    motion_input::{ChargeProgress, MotionInput, ParserHead},
+    notation::ParseError,
+    replay::ReplayRecorder,
 };
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use types::{Facing, GameButton, StickPosition};
+use time::Clock;
+use types::{Facing, GameButton, Player, StickPosition};
 
 use characters::MoveId;
 
+/// Move with an input definition that could not be parsed
+#[derive(Debug, Clone)]
+pub struct InvalidInput {
+    pub id: MoveId,
+    pub input: String,
+    pub error: ParseError,
+}
+impl Display for InvalidInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} has invalid input \"{}\": {}",
+            self.id, self.input, self.error
+        )
+    }
+}
+impl std::error::Error for InvalidInput {}
+
+const HISTORY_LENGTH: usize = 20;
+
+/// Input change that the parser has read, kept around for debugging motions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub frame: usize,
+    /// Relative to facing, like in the motions
+    pub stick: StickPosition,
+    pub pressed: HashSet<GameButton>,
+    /// Moves that were recognized on this frame
+    pub events: Vec<MoveId>,
+}
+
+/// Progress of an InputParser, without the input definitions that never change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParserState {
+    events: Vec<MoveId>,
+    /// Sorted by move so the same progress always looks the same
+    heads: Vec<(MoveId, Vec<ParserHead>)>,
+    head: Frame,
+    relative_stick: StickPosition,
+    history: VecDeque<HistoryEntry>,
+}
+
 /// This is a component and used as an interface
 /// Main tells this what Actions to send what events from
 #[derive(Debug, Default, Component)]
@@ -21,22 +70,38 @@ pub struct InputParser {
     registered_inputs: HashMap<MoveId, MotionInput>,
     head: Frame,
     relative_stick: StickPosition,
+    history: VecDeque<HistoryEntry>,
 }
 impl InputParser {
-    pub fn load(inputs: HashMap<MoveId, &str>) -> Self {
-        Self {
+    pub fn load(inputs: HashMap<MoveId, &str>) -> Result<Self, InvalidInput> {
+        Ok(Self {
             registered_inputs: inputs
                 .into_iter()
-                .map(|(id, definition)| (id, definition.into()))
-                .collect(),
+                .map(|(id, definition)| {
+                    MotionInput::try_from(definition)
+                        .map(|motion| (id, motion))
+                        .map_err(|error| InvalidInput {
+                            id,
+                            input: definition.to_owned(),
+                            error,
+                        })
+                })
+                .collect::<Result<_, _>>()?,
             ..default()
-        }
+        })
     }
 
     pub fn register_input(&mut self, id: MoveId, input: MotionInput) {
         self.registered_inputs.insert(id, input);
     }
 
+    /// Swaps every input definition for new ones, what is held down stays.
+    /// If any of the new ones can't be parsed, the old ones are kept
+    pub fn reload(&mut self, inputs: HashMap<MoveId, &str>) -> Result<(), InvalidInput> {
+        self.registered_inputs = Self::load(inputs)?.registered_inputs;
+        Ok(())
+    }
+
     pub fn get_pressed(&self) -> HashSet<GameButton> {
         self.head.pressed.clone()
     }
@@ -49,6 +114,26 @@ impl InputParser {
         self.relative_stick
     }
 
+    /// Most complete charge out of all the registered inputs
+    pub fn get_charge_progress(&self, frame: usize) -> Option<ChargeProgress> {
+        self.registered_inputs
+            .values()
+            .filter_map(|input| input.charge_progress(frame))
+            .max_by(|a, b| a.ratio().total_cmp(&b.ratio()))
+    }
+
+    /// Every input that starts with a charge gets that many frames of it, as if the stick had been held there
+    pub fn set_charge(&mut self, charged: usize, frame: usize) {
+        for input in self.registered_inputs.values_mut() {
+            input.set_charge(charged, frame);
+        }
+    }
+
+    /// Newest entry first
+    pub fn get_history(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.history.iter()
+    }
+
     pub fn drain_events(&mut self) -> Vec<MoveId> {
         self.events.drain(..).collect()
     }
@@ -57,7 +142,11 @@ impl InputParser {
         self.head.stick_position == StickPosition::Neutral && self.head.pressed.is_empty()
     }
 
-    fn add_frame(&mut self, diff: Diff, facing: &Facing) {
+    fn add_frame(&mut self, diff: Diff, facing: &Facing, frame: usize) {
+        let events_before = self.events.len();
+        let pressed = diff.pressed.clone().unwrap_or_default();
+        let changed = diff != Diff::default();
+
         // This needs to happen before relative_stick is set to enable inputs that permit holding a direction as the first requirement
         self.parse_inputs(
             Diff {
@@ -65,53 +154,111 @@ impl InputParser {
                 ..diff.clone()
             },
             self.relative_stick,
+            frame,
         );
 
         self.head.apply(diff);
         self.relative_stick = facing.mirror_stick(self.head.stick_position);
+
+        if changed {
+            self.history.push_front(HistoryEntry {
+                frame,
+                stick: self.relative_stick,
+                pressed,
+                events: self.events[events_before..].to_vec(),
+            });
+            self.history.truncate(HISTORY_LENGTH);
+        }
     }
 
-    fn parse_inputs(&mut self, diff: Diff, old_stick: StickPosition) {
-        self.events
-            .extend(self.registered_inputs.iter_mut().filter_map(|(id, input)| {
-                input.advance(&diff, old_stick);
+    fn parse_inputs(&mut self, diff: Diff, old_stick: StickPosition, frame: usize) {
+        let mut completed: Vec<MoveId> = self
+            .registered_inputs
+            .iter_mut()
+            .filter_map(|(id, input)| {
+                input.advance(&diff, old_stick, frame);
                 if input.is_done() {
                     input.clear();
                     return Some(*id);
                 }
                 None
-            }));
+            })
+            .collect();
+        // Map iteration order differs between runs, events must not
+        completed.sort();
+        self.events.extend(completed);
     }
 
     pub fn clear(&mut self) {
         self.events.clear();
     }
+
+    pub fn save_state(&self) -> ParserState {
+        let mut heads: Vec<_> = self
+            .registered_inputs
+            .iter()
+            .map(|(id, input)| (*id, input.save_heads()))
+            .collect();
+        heads.sort_by_key(|(id, _)| *id);
+
+        ParserState {
+            events: self.events.clone(),
+            heads,
+            head: self.head.clone(),
+            relative_stick: self.relative_stick,
+            history: self.history.clone(),
+        }
+    }
+
+    /// Inputs that are not registered on this parser are ignored
+    pub fn load_state(&mut self, state: &ParserState) {
+        for input in self.registered_inputs.values_mut() {
+            input.clear();
+        }
+        for (id, heads) in &state.heads {
+            if let Some(input) = self.registered_inputs.get_mut(id) {
+                input.load_heads(heads.clone());
+            }
+        }
+
+        self.events = state.events.clone();
+        self.head = state.head.clone();
+        self.relative_stick = state.relative_stick;
+        self.history = state.history.clone();
+    }
 }
 
 pub fn parse_input<T: InputStream + Component>(
-    mut characters: Query<(&mut InputParser, &mut T, &Facing)>,
+    clock: Res<Clock>,
+    mut recorder: Option<ResMut<ReplayRecorder>>,
+    mut characters: Query<(&mut InputParser, &mut T, &Facing, Option<&Player>)>,
 ) {
-    for (mut parser, mut reader, facing) in characters.iter_mut() {
+    for (mut parser, mut reader, facing, player) in characters.iter_mut() {
         if let Some(diff) = reader.read() {
-            parser.add_frame(diff, facing);
+            if let (Some(recorder), Some(player)) = (recorder.as_mut(), player) {
+                recorder.record(*player, clock.frame, diff.clone());
+            }
+            parser.add_frame(diff, facing, clock.frame);
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use std::thread::sleep;
-    use std::time::Duration;
+    use map_macro::set;
     use types::GameButton;
 
     use crate::{
         helper_types::InputEvent,
         testing::{TestInputBundle, TestStream},
-        MAX_SECONDS_BETWEEN_SUBSEQUENT_MOTIONS,
+        MAX_FRAMES_BETWEEN_SUBSEQUENT_MOTIONS,
     };
 
     use super::*;
 
+    const TEST_MOVE: MoveId = MoveId::new("TestMove");
+    const SECOND_TEST_MOVE: MoveId = MoveId::new("SecondTestMove");
+
     #[test]
     fn hadouken_recognized() {
         let mut interface = TestInterface::with_input("236f");
@@ -134,10 +281,46 @@ mod test {
         interface.add_stick_and_tick(StickPosition::E);
         interface.assert_no_events();
 
-        interface.sleep(MAX_SECONDS_BETWEEN_SUBSEQUENT_MOTIONS);
+        interface.wait(MAX_FRAMES_BETWEEN_SUBSEQUENT_MOTIONS);
+
+        interface.add_button_and_tick(GameButton::Fast);
+        interface.assert_no_events();
+    }
+
+    #[test]
+    fn inputs_within_window_recognized() {
+        let mut interface = TestInterface::with_input("236f");
+
+        interface.add_stick_and_tick(StickPosition::S);
+        interface.add_stick_and_tick(StickPosition::SE);
+        interface.add_stick_and_tick(StickPosition::E);
+        interface.assert_no_events();
+
+        interface.wait(MAX_FRAMES_BETWEEN_SUBSEQUENT_MOTIONS - 2);
 
         interface.add_button_and_tick(GameButton::Fast);
+        interface.assert_test_event_is_present();
+    }
+
+    #[test]
+    fn custom_window_recognized() {
+        let mut interface = TestInterface::new(vec![]);
+        interface.register_input(
+            TEST_MOVE,
+            MotionInput::try_from("236f")
+                .unwrap()
+                .with_window(3 * MAX_FRAMES_BETWEEN_SUBSEQUENT_MOTIONS),
+        );
+
+        interface.add_stick_and_tick(StickPosition::S);
+        interface.add_stick_and_tick(StickPosition::SE);
+        interface.add_stick_and_tick(StickPosition::E);
         interface.assert_no_events();
+
+        interface.wait(2 * MAX_FRAMES_BETWEEN_SUBSEQUENT_MOTIONS);
+
+        interface.add_button_and_tick(GameButton::Fast);
+        interface.assert_test_event_is_present();
     }
 
     #[test]
@@ -166,7 +349,7 @@ mod test {
         interface.add_stick_and_tick(StickPosition::S);
         interface.assert_no_events();
 
-        interface.sleep(MAX_SECONDS_BETWEEN_SUBSEQUENT_MOTIONS);
+        interface.wait(MAX_FRAMES_BETWEEN_SUBSEQUENT_MOTIONS);
 
         interface.add_button_and_tick(GameButton::Fast);
         interface.assert_test_event_is_present();
@@ -179,7 +362,7 @@ mod test {
         interface.add_stick_and_tick(StickPosition::S);
         interface.assert_no_events();
 
-        interface.sleep(MAX_SECONDS_BETWEEN_SUBSEQUENT_MOTIONS);
+        interface.wait(MAX_FRAMES_BETWEEN_SUBSEQUENT_MOTIONS);
 
         interface.add_stick_and_tick(StickPosition::N);
         interface.assert_test_event_is_present();
@@ -226,31 +409,269 @@ mod test {
         interface.assert_both_test_events_are_present();
     }
 
+    #[test]
+    fn charge_recognized() {
+        let mut interface = TestInterface::with_input("[4]:30 6f");
+
+        interface.add_stick_and_tick(StickPosition::W);
+        // Charge can go way past the normal window
+        interface.wait(2 * MAX_FRAMES_BETWEEN_SUBSEQUENT_MOTIONS.max(30));
+        interface.add_stick_and_tick(StickPosition::E);
+        interface.assert_no_events();
+        interface.add_button_and_tick(GameButton::Fast);
+        interface.assert_test_event_is_present();
+    }
+
+    #[test]
+    fn short_charge_not_recognized() {
+        let mut interface = TestInterface::with_input("[4]:30 6f");
+
+        interface.add_stick_and_tick(StickPosition::W);
+        interface.wait(10);
+        interface.add_stick_and_tick(StickPosition::E);
+        interface.add_button_and_tick(GameButton::Fast);
+        interface.assert_no_events();
+    }
+
+    #[test]
+    fn decaying_charge_survives_letting_go() {
+        let mut interface = TestInterface::with_inputs("[4]:30~12 6f", "[4]:30 6s");
+
+        // Let go for a moment halfway through
+        interface.add_stick_and_tick(StickPosition::W);
+        interface.wait(19);
+        interface.add_stick_and_tick(StickPosition::Neutral);
+        interface.wait(5);
+        interface.add_stick_and_tick(StickPosition::W);
+        interface.wait(19);
+        interface.add_stick_and_tick(StickPosition::E);
+        interface.add_button_and_tick(GameButton::Fast);
+        interface.add_button_and_tick(GameButton::Strong);
+
+        interface.assert_test_event_is_present();
+        interface.assert_event_is_not_present(SECOND_TEST_MOVE);
+    }
+
+    #[test]
+    fn decayed_charge_is_lost() {
+        let mut interface = TestInterface::with_input("[4]:30~12 6f");
+
+        interface.add_stick_and_tick(StickPosition::W);
+        interface.wait(19);
+        interface.add_stick_and_tick(StickPosition::Neutral);
+        interface.wait(15);
+        interface.add_stick_and_tick(StickPosition::W);
+        interface.wait(19);
+        interface.add_stick_and_tick(StickPosition::E);
+        interface.add_button_and_tick(GameButton::Fast);
+
+        interface.assert_no_events();
+    }
+
+    #[test]
+    fn charges_are_partitioned() {
+        let mut interface = TestInterface::with_inputs("[41]:30 6f", "[123]:30 8s");
+
+        // Down back charges both
+        interface.add_stick_and_tick(StickPosition::SW);
+        interface.wait(20);
+        // Down keeps the down charge but drops the back charge
+        interface.add_stick_and_tick(StickPosition::S);
+        interface.wait(20);
+        interface.add_stick_and_tick(StickPosition::N);
+        interface.add_button_and_tick(GameButton::Strong);
+        interface.add_stick_and_tick(StickPosition::E);
+        interface.add_button_and_tick(GameButton::Fast);
+
+        interface.assert_event_is_present(SECOND_TEST_MOVE);
+        interface.assert_event_is_not_present(TEST_MOVE);
+    }
+
+    #[test]
+    fn charge_progress_reported() {
+        let mut interface = TestInterface::with_inputs("[41]:30 6f", "[2]:60 8s");
+
+        interface.add_stick_and_tick(StickPosition::SW);
+        interface.wait(14);
+        assert_eq!(
+            interface.charge_progress(),
+            Some(ChargeProgress {
+                progress: 15,
+                required: 30
+            })
+        );
+
+        interface.add_stick_and_tick(StickPosition::S);
+        interface.wait(29);
+        // Back charge was lost when moving to down
+        assert_eq!(
+            interface.charge_progress(),
+            Some(ChargeProgress {
+                progress: 30,
+                required: 60
+            })
+        );
+    }
+
+    #[test]
+    fn history_records_inputs_and_events() {
+        let mut interface = TestInterface::with_input("2f");
+
+        interface.add_stick_and_tick(StickPosition::S);
+        interface.wait(3);
+        interface.add_button_and_tick(GameButton::Fast);
+
+        let history = interface.history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].pressed, set! {GameButton::Fast});
+        assert_eq!(history[0].events, vec![TEST_MOVE]);
+        assert_eq!(history[1].stick, StickPosition::S);
+        assert!(history[1].events.is_empty());
+        assert_eq!(history[0].frame - history[1].frame, 5);
+    }
+
+    #[test]
+    fn saved_state_continues_motion() {
+        let mut interface = TestInterface::with_input("236f");
+
+        interface.add_stick_and_tick(StickPosition::S);
+        interface.add_stick_and_tick(StickPosition::SE);
+        let saved = interface.parser().save_state();
+
+        interface.add_stick_and_tick(StickPosition::N);
+        interface.add_button_and_tick(GameButton::Fast);
+        interface.assert_no_events();
+
+        interface.parser_mut().load_state(&saved);
+        assert_eq!(interface.parser().save_state(), saved);
+        interface.add_stick_and_tick(StickPosition::E);
+        interface.add_button_and_tick(GameButton::Fast);
+        interface.assert_test_event_is_present();
+    }
+
+    #[test]
+    fn simultaneous_events_are_ordered() {
+        let mut interface = TestInterface::with_inputs("f", "f");
+
+        interface.add_button_and_tick(GameButton::Fast);
+        // In name order
+        assert_eq!(
+            interface.parser_mut().drain_events(),
+            vec![SECOND_TEST_MOVE, TEST_MOVE]
+        );
+    }
+
+    #[test]
+    fn optional_step_can_be_skipped() {
+        let mut interface = TestInterface::with_inputs("23?6f", "23?6s");
+
+        interface.add_stick_and_tick(StickPosition::S);
+        interface.add_stick_and_tick(StickPosition::E);
+        interface.add_button_and_tick(GameButton::Fast);
+        interface.assert_test_event_is_present();
+
+        interface.add_stick_and_tick(StickPosition::S);
+        interface.add_stick_and_tick(StickPosition::SE);
+        interface.add_stick_and_tick(StickPosition::E);
+        interface.add_button_and_tick(GameButton::Strong);
+        interface.assert_event_is_present(SECOND_TEST_MOVE);
+    }
+
+    #[test]
+    fn circle_recognized_from_any_start() {
+        let mut interface = TestInterface::with_input("360g");
+
+        interface.add_stick_and_tick(StickPosition::W);
+        interface.add_stick_and_tick(StickPosition::N);
+        interface.add_stick_and_tick(StickPosition::E);
+        interface.add_stick_and_tick(StickPosition::S);
+        interface.assert_no_events();
+        interface.add_button_and_tick(GameButton::Grab);
+        interface.assert_test_event_is_present();
+    }
+
+    #[test]
+    fn slow_circle_not_recognized() {
+        let mut interface = TestInterface::with_input("360g");
+
+        // Walk back, jump, walk forward and crouch, with time in between
+        interface.add_stick_and_tick(StickPosition::W);
+        for stick in [StickPosition::N, StickPosition::E, StickPosition::S] {
+            interface.wait(MAX_FRAMES_BETWEEN_SUBSEQUENT_MOTIONS);
+            interface.add_stick_and_tick(stick);
+        }
+        interface.add_button_and_tick(GameButton::Grab);
+        interface.assert_no_events();
+    }
+
+    #[test]
+    fn invalid_input_reported_on_load() {
+        let error = InputParser::load(vec![(TEST_MOVE, "2x")].into_iter().collect()).unwrap_err();
+
+        assert_eq!(error.id, TEST_MOVE);
+        assert_eq!(error.error.position, 1);
+    }
+
+    #[test]
+    fn reload_replaces_inputs() {
+        let mut interface = TestInterface::with_input("f");
+
+        interface
+            .parser_mut()
+            .reload(vec![(TEST_MOVE, "s")].into_iter().collect())
+            .unwrap();
+        interface.add_button_and_tick(GameButton::Fast);
+        interface.assert_no_events();
+        interface.add_button_and_tick(GameButton::Strong);
+        interface.assert_test_event_is_present();
+    }
+
+    #[test]
+    fn invalid_reload_keeps_old_inputs() {
+        let mut interface = TestInterface::with_input("f");
+
+        let result = interface
+            .parser_mut()
+            .reload(vec![(TEST_MOVE, "2x")].into_iter().collect());
+        assert!(result.is_err());
+        interface.add_button_and_tick(GameButton::Fast);
+        interface.assert_test_event_is_present();
+    }
+
+    #[test]
+    fn character_inputs_are_valid() {
+        let assets = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../main/assets");
+        for id in characters::CharacterId::ALL {
+            if let Err(error) = InputParser::load(id.load(&assets).unwrap().get_inputs()) {
+                panic!("{}", error);
+            }
+        }
+    }
+
     struct TestInterface {
         world: World,
         stage: SystemStage,
     }
     impl TestInterface {
         fn with_input(input: &str) -> TestInterface {
-            TestInterface::new(vec![(MoveId::TestMove, input)])
+            TestInterface::new(vec![(TEST_MOVE, input)])
         }
 
         fn with_inputs(input: &str, second_input: &str) -> TestInterface {
-            TestInterface::new(vec![
-                (MoveId::TestMove, input),
-                (MoveId::SecondTestMove, second_input),
-            ])
+            TestInterface::new(vec![(TEST_MOVE, input), (SECOND_TEST_MOVE, second_input)])
         }
 
         fn new(moves: Vec<(MoveId, &str)>) -> TestInterface {
             let mut world = World::default();
 
+            world.insert_resource(Clock::default());
+
             let mut stage = SystemStage::parallel();
             stage.add_system(parse_input::<TestStream>);
 
             world
                 .spawn()
-                .insert_bundle(TestInputBundle::new(moves.into_iter().collect()))
+                .insert_bundle(TestInputBundle::new(moves.into_iter().collect()).unwrap())
                 .insert(Facing::Right);
 
             let mut tester = TestInterface { world, stage };
@@ -260,9 +681,36 @@ mod test {
         }
 
         fn tick(&mut self) {
+            self.world.resource_mut::<Clock>().frame += 1;
             self.stage.run(&mut self.world);
         }
 
+        fn register_input(&mut self, id: MoveId, input: MotionInput) {
+            for mut parser in self
+                .world
+                .query::<&mut InputParser>()
+                .iter_mut(&mut self.world)
+            {
+                parser.register_input(id, input.clone());
+            }
+        }
+
+        fn parser(&mut self) -> &InputParser {
+            self.world
+                .query::<&InputParser>()
+                .iter(&self.world)
+                .next()
+                .unwrap()
+        }
+
+        fn parser_mut(&mut self) -> Mut<'_, InputParser> {
+            self.world
+                .query::<&mut InputParser>()
+                .iter_mut(&mut self.world)
+                .next()
+                .unwrap()
+        }
+
         fn add_button_and_tick(&mut self, button: GameButton) {
             self.add_input(InputEvent::Press(button));
             self.tick();
@@ -283,18 +731,18 @@ mod test {
             }
         }
 
-        fn sleep(&mut self, seconds: f32) {
-            sleep(Duration::from_secs_f32(seconds + 0.1));
+        fn wait(&mut self, frames: usize) {
+            self.world.resource_mut::<Clock>().frame += frames;
             self.tick();
         }
 
         fn assert_test_event_is_present(&mut self) {
-            self.assert_event_is_present(MoveId::TestMove);
+            self.assert_event_is_present(TEST_MOVE);
         }
 
         fn assert_both_test_events_are_present(&mut self) {
-            self.assert_event_is_present(MoveId::TestMove);
-            self.assert_event_is_present(MoveId::SecondTestMove);
+            self.assert_event_is_present(TEST_MOVE);
+            self.assert_event_is_present(SECOND_TEST_MOVE);
         }
 
         fn assert_event_is_present(&mut self, id: MoveId) {
@@ -312,6 +760,38 @@ mod test {
             );
         }
 
+        fn assert_event_is_not_present(&mut self, id: MoveId) {
+            let parser = self
+                .world
+                .query::<&InputParser>()
+                .iter(&self.world)
+                .next()
+                .unwrap();
+
+            assert!(!parser.events.contains(&id), "Event {:?} was present", id);
+        }
+
+        fn history(&mut self) -> Vec<HistoryEntry> {
+            self.world
+                .query::<&InputParser>()
+                .iter(&self.world)
+                .next()
+                .unwrap()
+                .get_history()
+                .cloned()
+                .collect()
+        }
+
+        fn charge_progress(&mut self) -> Option<ChargeProgress> {
+            let frame = self.world.resource::<Clock>().frame;
+            self.world
+                .query::<&InputParser>()
+                .iter(&self.world)
+                .next()
+                .unwrap()
+                .get_charge_progress(frame)
+        }
+
         fn assert_no_events(&mut self) {
             let parser = self
                 .world
@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+use types::Player;
+
+use crate::{
+    bindings::{KeyBinding, KeyboardBindings, SocdMode},
+    helper_types::Diff,
+};
+
+use super::{raw_inputs::RawInputs, InputStream, ParrotStream};
+
+#[derive(Component)]
+pub struct KeyboardStream {
+    player: Player,
+    inputs: RawInputs,
+}
+
+impl KeyboardStream {
+    pub fn new(player: Player) -> Self {
+        Self {
+            player,
+            inputs: RawInputs::default(),
+        }
+    }
+
+    pub fn set_socd(&mut self, socd: SocdMode) {
+        self.inputs.socd = socd;
+    }
+
+    fn handle_binding(&mut self, parrot: &mut ParrotStream, binding: KeyBinding, pressed: bool) {
+        match binding {
+            KeyBinding::Up => self.inputs.update_dpad(pressed, None, Some(1)),
+            KeyBinding::Down => self.inputs.update_dpad(pressed, None, Some(-1)),
+            KeyBinding::Left => self.inputs.update_dpad(pressed, Some(-1), None),
+            KeyBinding::Right => self.inputs.update_dpad(pressed, Some(1), None),
+            KeyBinding::Button(button) => {
+                if pressed {
+                    self.inputs.press_button(button)
+                } else {
+                    self.inputs.release_button(button)
+                }
+            }
+            KeyBinding::Parrot(action) => {
+                if pressed {
+                    parrot.handle(action)
+                }
+            }
+        }
+    }
+}
+
+impl InputStream for KeyboardStream {
+    fn read(&mut self) -> Option<Diff> {
+        self.inputs.read()
+    }
+}
+
+pub fn update_keyboards(
+    keys: Res<Input<KeyCode>>,
+    bindings: Res<KeyboardBindings>,
+    mut readers: Query<(&mut KeyboardStream, &mut ParrotStream)>,
+) {
+    for (mut reader, mut parrot) in readers.iter_mut() {
+        let layout = bindings.get(reader.player);
+
+        for key in keys.get_just_pressed() {
+            if let Some(binding) = layout.get(key) {
+                reader.handle_binding(&mut parrot, *binding, true);
+            }
+        }
+
+        for key in keys.get_just_released() {
+            if let Some(binding) = layout.get(key) {
+                reader.handle_binding(&mut parrot, *binding, false);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use types::{GameButton, StickPosition};
+
+    use crate::input_stream::ParrotMode;
+
+    use super::*;
+
+    fn read_after(app: &mut App, press: &[KeyCode], release: &[KeyCode]) -> Option<Diff> {
+        let mut keys = app.world.resource_mut::<Input<KeyCode>>();
+        keys.clear();
+        for key in press {
+            keys.press(*key);
+        }
+        for key in release {
+            keys.release(*key);
+        }
+        app.update();
+
+        app.world
+            .query::<&mut KeyboardStream>()
+            .iter_mut(&mut app.world)
+            .next()
+            .unwrap()
+            .read()
+    }
+
+    fn test_app(player: Player) -> App {
+        let mut app = App::new();
+        app.insert_resource(Input::<KeyCode>::default())
+            .insert_resource(KeyboardBindings::default())
+            .add_system(update_keyboards);
+        app.world
+            .spawn()
+            .insert(KeyboardStream::new(player))
+            .insert(ParrotStream::default());
+        app
+    }
+
+    #[test]
+    fn keys_produce_diffs() {
+        let mut app = test_app(Player::One);
+
+        assert!(read_after(&mut app, &[], &[]).is_none());
+
+        let diff = read_after(&mut app, &[KeyCode::D, KeyCode::J], &[]).unwrap();
+        assert_eq!(diff.stick_move, Some(StickPosition::E));
+        assert!(diff.pressed_contains(&GameButton::Fast));
+
+        let diff = read_after(&mut app, &[KeyCode::S], &[KeyCode::J]).unwrap();
+        assert_eq!(diff.stick_move, Some(StickPosition::SE));
+        assert!(diff.released_contains(&GameButton::Fast));
+
+        let diff = read_after(&mut app, &[], &[KeyCode::D, KeyCode::S]).unwrap();
+        assert_eq!(diff.stick_move, Some(StickPosition::Neutral));
+    }
+
+    #[test]
+    fn other_players_keys_are_ignored() {
+        let mut app = test_app(Player::Two);
+
+        assert!(read_after(&mut app, &[KeyCode::D, KeyCode::J], &[]).is_none());
+        let diff = read_after(&mut app, &[KeyCode::Left], &[]).unwrap();
+        assert_eq!(diff.stick_move, Some(StickPosition::W));
+    }
+
+    #[test]
+    fn parrot_keys_control_parrot() {
+        let mut app = test_app(Player::One);
+
+        assert!(read_after(&mut app, &[KeyCode::Key1], &[]).is_none());
+        let status = app
+            .world
+            .query::<&ParrotStream>()
+            .iter(&app.world)
+            .next()
+            .unwrap()
+            .status();
+        assert_eq!(status.mode, ParrotMode::Listening);
+    }
+}
@@ -1,15 +1,41 @@
+mod keyboard_stream;
+mod network_stream;
 mod pad_stream;
 mod parrot_stream;
 mod prewritten_stream;
+mod raw_inputs;
 mod test_stream;
 
+pub use keyboard_stream::{update_keyboards, KeyboardStream};
+pub use network_stream::NetworkStream;
 pub use pad_stream::{update_pads, PadStream};
-pub use parrot_stream::{update_parrots, ParrotStream};
+pub use parrot_stream::{
+    update_parrot_files, update_parrots, ParrotAction, ParrotMode, ParrotStatus, ParrotStream,
+    SlotSelection, PARROT_SLOTS,
+};
 pub use prewritten_stream::PreWrittenStream;
 pub use test_stream::TestStream;
 
-use crate::helper_types::Diff;
+use bevy::prelude::*;
+use types::Player;
+
+use crate::{bindings::SocdModes, helper_types::Diff};
 
 pub trait InputStream {
     fn read(&mut self) -> Option<Diff>;
 }
+
+pub fn update_socd_modes(
+    modes: Res<SocdModes>,
+    mut pads: Query<(&Player, &mut PadStream)>,
+    mut keyboards: Query<(&Player, &mut KeyboardStream)>,
+) {
+    if modes.is_changed() {
+        for (player, mut pad) in pads.iter_mut() {
+            pad.set_socd(modes.get(*player));
+        }
+        for (player, mut keyboard) in keyboards.iter_mut() {
+            keyboard.set_socd(modes.get(*player));
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use bevy::prelude::Component;
+
+use super::InputStream;
+use crate::helper_types::Diff;
+
+/// Input that is decided outside the game loop, like by a rollback session
+#[derive(Debug, Component, Default)]
+pub struct NetworkStream {
+    next_read: Option<Diff>,
+}
+impl NetworkStream {
+    /// Input for the next frame, replaces anything that was not read yet
+    pub fn set(&mut self, input: Option<Diff>) {
+        self.next_read = input;
+    }
+}
+impl InputStream for NetworkStream {
+    fn read(&mut self) -> Option<Diff> {
+        self.next_read.take()
+    }
+}
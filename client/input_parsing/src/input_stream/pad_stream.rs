@@ -1,93 +1,33 @@
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 
 use bevy::prelude::*;
-use types::{GameButton, StickPosition};
 
 use crate::{
-    helper_types::{Diff, InputEvent},
+    bindings::{PadBinding, PadLayout, PadLayouts, SocdMode},
+    helper_types::Diff,
     STICK_DEAD_ZONE,
 };
 
-use super::{InputStream, ParrotStream};
+use super::{raw_inputs::RawInputs, InputStream, ParrotStream};
 
 #[derive(Default, Component)]
 pub struct PadStream {
     pub pad_id: Option<Gamepad>,
-    next_read: Vec<InputEvent>,
-    stick_position: IVec2,
-    stick_position_last_read: StickPosition,
+    inputs: RawInputs,
+    // Analog buttons send multiple change events per press
+    held_buttons: HashSet<GamepadButtonType>,
 }
 
 impl PadStream {
-    fn update_next_diff_stick(&mut self) {
-        let discrete_stick = self.stick_position.into();
-        self.next_read.push(InputEvent::Point(discrete_stick));
-    }
-
-    fn update_stick(&mut self, new_x: Option<i32>, new_y: Option<i32>) {
-        if let Some(x) = new_x {
-            self.stick_position.x = x;
-        }
-        if let Some(y) = new_y {
-            self.stick_position.y = y;
-        }
-
-        self.update_next_diff_stick();
-    }
-
-    fn press_button(&mut self, button: GameButton) {
-        self.next_read.push(InputEvent::Press(button));
-    }
-
-    fn release_button(&mut self, button: GameButton) {
-        self.next_read.push(InputEvent::Release(button));
-    }
-
-    fn update_dpad(&mut self, pressed: bool, new_x: Option<i32>, new_y: Option<i32>) {
-        // Plan was for opposite presses to override, to make hitbox gaming easier
-        // So on release we can't just reset to zero, because the other direction may be held
-        // Hopefully this works.
-        if pressed {
-            if let Some(x) = new_x {
-                self.stick_position.x = x;
-            }
-            if let Some(y) = new_y {
-                self.stick_position.y = y;
-            }
-        } else {
-            if let Some(x) = new_x {
-                if self.stick_position.x == x {
-                    self.stick_position.x = 0;
-                }
-            }
-            if let Some(y) = new_y {
-                if self.stick_position.y == y {
-                    self.stick_position.y = 0;
-                }
-            }
-        }
-
-        self.update_next_diff_stick();
+    pub fn set_socd(&mut self, socd: SocdMode) {
+        self.inputs.socd = socd;
     }
 }
 
 impl InputStream for PadStream {
     fn read(&mut self) -> Option<Diff> {
-        let readable = self.pad_id.is_some() && !self.next_read.is_empty();
-        if readable {
-            let temp = self.next_read.clone();
-            self.next_read.clear();
-            let mut diff = temp
-                .into_iter()
-                .fold(Diff::default(), |acc, new| acc.apply(new));
-
-            if let Some(new_stick) = diff.stick_move {
-                if new_stick == self.stick_position_last_read {
-                    diff.stick_move = None
-                }
-                self.stick_position_last_read = new_stick;
-            }
-            Some(diff)
+        if self.pad_id.is_some() {
+            self.inputs.read()
         } else {
             None
         }
@@ -97,6 +37,7 @@ impl InputStream for PadStream {
 pub fn update_pads(
     mut gamepad_events: EventReader<GamepadEvent>,
     mut unused_pads: ResMut<VecDeque<Gamepad>>,
+    layouts: Res<PadLayouts>,
     mut readers: Query<(&mut PadStream, &mut ParrotStream)>,
 ) {
     for GamepadEvent(pad_id, event_type) in gamepad_events.iter() {
@@ -116,7 +57,8 @@ pub fn update_pads(
             }
             GamepadEventType::ButtonChanged(button, new_value) => {
                 let (mut reader, mut parrot) = matching_components.unwrap();
-                button_change(&mut reader, &mut parrot, *button, *new_value);
+                let layout = layouts.get(*pad_id);
+                button_change(&mut reader, &mut parrot, layout, *button, *new_value);
             }
         };
     }
@@ -143,6 +85,9 @@ fn pad_connection(
 fn pad_disconnection(reader: &mut Mut<PadStream>, unused_pads: &mut VecDeque<Gamepad>) {
     println!("Gamepad disconnected with ID: {:?}", reader.pad_id);
 
+    reader.held_buttons.clear();
+    reader.inputs.release_all();
+
     reader.pad_id = unused_pads.pop_front();
 }
 
@@ -150,7 +95,7 @@ fn axis_change(reader: &mut Mut<PadStream>, axis: GamepadAxisType, new_value: f3
     match axis {
         // Even though DPad axis are on the list, they don't fire
         GamepadAxisType::LeftStickX | GamepadAxisType::RightStickX | GamepadAxisType::DPadX => {
-            reader.update_stick(
+            reader.inputs.update_stick(
                 Some(if new_value.abs() > STICK_DEAD_ZONE {
                     new_value.signum() as i32
                 } else {
@@ -160,7 +105,7 @@ fn axis_change(reader: &mut Mut<PadStream>, axis: GamepadAxisType, new_value: f3
             )
         }
         GamepadAxisType::LeftStickY | GamepadAxisType::RightStickY | GamepadAxisType::DPadY => {
-            reader.update_stick(
+            reader.inputs.update_stick(
                 None,
                 Some(if new_value.abs() > STICK_DEAD_ZONE {
                     new_value.signum() as i32
@@ -176,37 +121,250 @@ fn axis_change(reader: &mut Mut<PadStream>, axis: GamepadAxisType, new_value: f3
 fn button_change(
     reader: &mut Mut<PadStream>,
     parrot: &mut Mut<ParrotStream>,
+    layout: &PadLayout,
     button: GamepadButtonType,
     new_value: f32,
 ) {
-    // TODO: real button mappings
-
     let press = new_value > 0.1;
-    let handle_gamebutton = move |reader: &mut Mut<PadStream>, button: GameButton| {
-        if press {
-            reader.press_button(button)
-        } else {
-            reader.release_button(button)
-        }
-    };
+    if press == reader.held_buttons.contains(&button) {
+        // No change
+        return;
+    }
+    if press {
+        reader.held_buttons.insert(button);
+    } else {
+        reader.held_buttons.remove(&button);
+    }
 
     match button {
-        GamepadButtonType::South => handle_gamebutton(reader, GameButton::Fast),
-        GamepadButtonType::East => handle_gamebutton(reader, GameButton::Strong),
-        GamepadButtonType::North => handle_gamebutton(reader, GameButton::Grab),
-        GamepadButtonType::West => handle_gamebutton(reader, GameButton::Equipment),
-        GamepadButtonType::LeftTrigger => handle_gamebutton(reader, GameButton::Taunt),
-
-        GamepadButtonType::DPadUp => reader.update_dpad(press, None, Some(1)),
-        GamepadButtonType::DPadDown => reader.update_dpad(press, None, Some(-1)),
-        GamepadButtonType::DPadLeft => reader.update_dpad(press, Some(-1), None),
-        GamepadButtonType::DPadRight => reader.update_dpad(press, Some(1), None),
-
-        GamepadButtonType::Select => {
-            if press {
-                parrot.cycle()
+        GamepadButtonType::DPadUp => reader.inputs.update_dpad(press, None, Some(1)),
+        GamepadButtonType::DPadDown => reader.inputs.update_dpad(press, None, Some(-1)),
+        GamepadButtonType::DPadLeft => reader.inputs.update_dpad(press, Some(-1), None),
+        GamepadButtonType::DPadRight => reader.inputs.update_dpad(press, Some(1), None),
+
+        _ => match layout.get(&button) {
+            Some(PadBinding::Parrot(action)) => {
+                if press {
+                    parrot.handle(*action)
+                }
             }
-        }
-        _ => {}
+            Some(binding) => {
+                for game_button in binding.buttons() {
+                    if press {
+                        reader.inputs.press_button(game_button)
+                    } else {
+                        reader.inputs.release_button(game_button)
+                    }
+                }
+            }
+            None => {}
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::ecs::event::Events;
+    use types::{GameButton, StickPosition};
+
+    use super::*;
+
+    fn test_app(layouts: PadLayouts, socd: SocdMode) -> App {
+        let mut app = App::new();
+        app.insert_resource(VecDeque::<Gamepad>::default())
+            .insert_resource(layouts)
+            .add_event::<GamepadEvent>()
+            .add_system(update_pads);
+
+        let mut stream = PadStream::default();
+        stream.set_socd(socd);
+        app.world
+            .spawn()
+            .insert(stream)
+            .insert(ParrotStream::default());
+
+        send(&mut app, GamepadEventType::Connected);
+        app
+    }
+
+    fn send(app: &mut App, event_type: GamepadEventType) {
+        app.world
+            .resource_mut::<Events<GamepadEvent>>()
+            .send(GamepadEvent(Gamepad(0), event_type));
+        app.update();
+    }
+
+    fn dpad(app: &mut App, button: GamepadButtonType, pressed: bool) -> Option<StickPosition> {
+        send(
+            app,
+            GamepadEventType::ButtonChanged(button, if pressed { 1.0 } else { 0.0 }),
+        );
+        read(app).and_then(|diff| diff.stick_move)
+    }
+
+    fn read(app: &mut App) -> Option<Diff> {
+        app.world
+            .query::<&mut PadStream>()
+            .iter_mut(&mut app.world)
+            .next()
+            .unwrap()
+            .read()
+    }
+
+    #[test]
+    fn macro_presses_all_buttons() {
+        let mut layouts = PadLayouts::default();
+        layouts.remap(
+            Gamepad(0),
+            GamepadButtonType::RightTrigger,
+            Some(PadBinding::Macro(vec![
+                GameButton::Fast,
+                GameButton::Strong,
+            ])),
+        );
+        let mut app = test_app(layouts, SocdMode::default());
+
+        send(
+            &mut app,
+            GamepadEventType::ButtonChanged(GamepadButtonType::RightTrigger, 1.0),
+        );
+        let diff = read(&mut app).unwrap();
+        assert!(diff.pressed_contains(&GameButton::Fast));
+        assert!(diff.pressed_contains(&GameButton::Strong));
+
+        send(
+            &mut app,
+            GamepadEventType::ButtonChanged(GamepadButtonType::RightTrigger, 0.0),
+        );
+        let diff = read(&mut app).unwrap();
+        assert!(diff.released_contains(&GameButton::Fast));
+        assert!(diff.released_contains(&GameButton::Strong));
+    }
+
+    #[test]
+    fn button_held_until_all_bindings_released() {
+        let mut layouts = PadLayouts::default();
+        layouts.remap(
+            Gamepad(0),
+            GamepadButtonType::RightTrigger,
+            Some(PadBinding::Button(GameButton::Fast)),
+        );
+        let mut app = test_app(layouts, SocdMode::default());
+
+        send(
+            &mut app,
+            GamepadEventType::ButtonChanged(GamepadButtonType::South, 1.0),
+        );
+        send(
+            &mut app,
+            GamepadEventType::ButtonChanged(GamepadButtonType::RightTrigger, 0.5),
+        );
+        // Analog buttons send more events while held
+        send(
+            &mut app,
+            GamepadEventType::ButtonChanged(GamepadButtonType::RightTrigger, 1.0),
+        );
+        assert!(read(&mut app).unwrap().pressed_contains(&GameButton::Fast));
+
+        send(
+            &mut app,
+            GamepadEventType::ButtonChanged(GamepadButtonType::South, 0.0),
+        );
+        assert!(read(&mut app).is_none());
+
+        send(
+            &mut app,
+            GamepadEventType::ButtonChanged(GamepadButtonType::RightTrigger, 0.0),
+        );
+        assert!(read(&mut app).unwrap().released_contains(&GameButton::Fast));
+    }
+
+    #[test]
+    fn socd_last_input_wins() {
+        let mut app = test_app(PadLayouts::default(), SocdMode::LastInputWins);
+
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadLeft, true),
+            Some(StickPosition::W)
+        );
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadRight, true),
+            Some(StickPosition::E)
+        );
+        // Left is still held
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadRight, false),
+            Some(StickPosition::W)
+        );
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadLeft, false),
+            Some(StickPosition::Neutral)
+        );
+
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadUp, true),
+            Some(StickPosition::N)
+        );
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadDown, true),
+            Some(StickPosition::S)
+        );
+    }
+
+    #[test]
+    fn socd_neutral() {
+        let mut app = test_app(PadLayouts::default(), SocdMode::Neutral);
+
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadLeft, true),
+            Some(StickPosition::W)
+        );
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadRight, true),
+            Some(StickPosition::Neutral)
+        );
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadLeft, false),
+            Some(StickPosition::E)
+        );
+
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadDown, true),
+            Some(StickPosition::SE)
+        );
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadUp, true),
+            Some(StickPosition::E)
+        );
+    }
+
+    #[test]
+    fn socd_up_priority() {
+        let mut app = test_app(PadLayouts::default(), SocdMode::UpPriority);
+
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadUp, true),
+            Some(StickPosition::N)
+        );
+        // Up wins regardless of order
+        assert_eq!(dpad(&mut app, GamepadButtonType::DPadDown, true), None);
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadUp, false),
+            Some(StickPosition::S)
+        );
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadUp, true),
+            Some(StickPosition::N)
+        );
+
+        // Horizontals cancel
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadRight, true),
+            Some(StickPosition::NE)
+        );
+        assert_eq!(
+            dpad(&mut app, GamepadButtonType::DPadLeft, true),
+            Some(StickPosition::N)
+        );
     }
 }
@@ -1,62 +1,251 @@
 use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use types::{Facing, Player};
 
-use crate::helper_types::Diff;
+use crate::{
+    bindings::{save_config, try_load_config},
+    helper_types::Diff,
+};
 
 use super::InputStream;
 
-#[derive(PartialEq, Eq, Default)]
-enum ParrotMode {
+pub const PARROT_SLOTS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParrotMode {
     Listening,
     Repeating,
     #[default]
     Noop,
 }
 
-#[derive(Component, Default)]
+/// How the next slot is chosen once a recording has been played through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SlotSelection {
+    #[default]
+    Sequential,
+    Random,
+}
+
+/// Parrot controls that can be bound to keys or buttons
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParrotAction {
+    /// Noop -> Listening -> Repeating -> Noop
+    CycleMode,
+    NextSlot,
+    CycleSelection,
+    ToggleRelative,
+    Save,
+    Load,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Recording {
+    /// Which way the character was facing when recording started
+    facing: Facing,
+    frames: Vec<Option<Diff>>,
+}
+
+/// Everything that gets written to a recording file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct ParrotSetup {
+    slots: [Recording; PARROT_SLOTS],
+    selection: SlotSelection,
+    /// Mirror recordings if the character is facing the other way than while recording
+    relative_to_facing: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParrotStatus {
+    pub mode: ParrotMode,
+    pub slot: usize,
+    pub selection: SlotSelection,
+    pub relative_to_facing: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileRequest {
+    Save,
+    Load,
+}
+
+#[derive(Component)]
 pub struct ParrotStream {
     mode: ParrotMode,
-    buffer: Vec<Option<Diff>>,
+    setup: ParrotSetup,
+    selected_slot: usize,
+    playing_slot: usize,
     buffer_index: usize,
+    current_facing: Facing,
+    file_request: Option<FileRequest>,
+    /// Picks random slots, seeded so a match seed always plays the slots in the same order
+    rng: StdRng,
+}
+impl Default for ParrotStream {
+    fn default() -> Self {
+        Self {
+            mode: default(),
+            setup: default(),
+            selected_slot: default(),
+            playing_slot: default(),
+            buffer_index: default(),
+            current_facing: default(),
+            file_request: default(),
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
 }
 
 impl ParrotStream {
-    fn listen(&mut self, input: Option<Diff>) {
-        self.buffer.push(input);
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn status(&self) -> ParrotStatus {
+        ParrotStatus {
+            mode: self.mode,
+            slot: self.selected_slot,
+            selection: self.setup.selection,
+            relative_to_facing: self.setup.relative_to_facing,
+        }
+    }
+
+    pub fn handle(&mut self, action: ParrotAction) {
+        match action {
+            ParrotAction::CycleMode => self.cycle(),
+            ParrotAction::NextSlot => {
+                // Switching in the middle of a recording would leave it half done
+                if self.mode != ParrotMode::Listening {
+                    self.selected_slot = (self.selected_slot + 1) % PARROT_SLOTS;
+                    self.start_playback();
+                }
+            }
+            ParrotAction::CycleSelection => {
+                self.setup.selection = match self.setup.selection {
+                    SlotSelection::Sequential => SlotSelection::Random,
+                    SlotSelection::Random => SlotSelection::Sequential,
+                }
+            }
+            ParrotAction::ToggleRelative => {
+                self.setup.relative_to_facing = !self.setup.relative_to_facing
+            }
+            ParrotAction::Save => self.file_request = Some(FileRequest::Save),
+            ParrotAction::Load => self.file_request = Some(FileRequest::Load),
+        }
     }
 
     pub fn cycle(&mut self) {
         self.mode = match self.mode {
             ParrotMode::Listening => {
-                dbg!("Starting playback.");
+                self.start_playback();
                 ParrotMode::Repeating
             }
-            ParrotMode::Repeating => {
-                dbg!("Entered direct control mode.");
-                ParrotMode::Noop
-            }
+            ParrotMode::Repeating => ParrotMode::Noop,
             ParrotMode::Noop => {
-                dbg!("Starting recording.");
-                self.buffer = vec![];
-                self.buffer_index = 0;
+                self.setup.slots[self.selected_slot] = Recording {
+                    facing: self.current_facing,
+                    frames: vec![],
+                };
                 ParrotMode::Listening
             }
         }
     }
+
+    fn listen(&mut self, input: Option<Diff>) {
+        self.setup.slots[self.selected_slot].frames.push(input);
+    }
+
+    fn start_playback(&mut self) {
+        self.playing_slot = self.selected_slot;
+        self.buffer_index = 0;
+    }
+
+    fn recorded_slots(&self) -> Vec<usize> {
+        (0..PARROT_SLOTS)
+            .filter(|slot| !self.setup.slots[*slot].frames.is_empty())
+            .collect()
+    }
+
+    fn next_slot(&mut self) -> Option<usize> {
+        let recorded = self.recorded_slots();
+        if recorded.is_empty() {
+            return None;
+        }
+
+        Some(match self.setup.selection {
+            SlotSelection::Sequential => recorded
+                .iter()
+                .copied()
+                .find(|slot| *slot > self.playing_slot)
+                .unwrap_or(recorded[0]),
+            SlotSelection::Random => recorded[self.rng.gen_range(0..recorded.len())],
+        })
+    }
+
+    fn mirror(&self, diff: Diff, recorded_facing: Facing) -> Diff {
+        if !self.setup.relative_to_facing || recorded_facing == self.current_facing {
+            return diff;
+        }
+
+        Diff {
+            stick_move: diff
+                .stick_move
+                .map(|stick| Facing::Left.mirror_stick(stick)),
+            ..diff
+        }
+    }
+
+    fn save(&self, player: Player) {
+        save_config(&recording_file(player), &self.setup);
+    }
+
+    fn load(&mut self, player: Player) {
+        if let Some(setup) = try_load_config(&recording_file(player)) {
+            self.setup = setup;
+            self.mode = ParrotMode::Noop;
+            self.start_playback();
+        }
+    }
 }
 
 impl InputStream for ParrotStream {
     fn read(&mut self) -> Option<Diff> {
-        if self.mode == ParrotMode::Repeating {
-            self.buffer_index = (self.buffer_index + 1) % self.buffer.len();
-            self.buffer[self.buffer_index].to_owned()
-        } else {
-            None
+        if self.mode != ParrotMode::Repeating {
+            return None;
+        }
+
+        if self.buffer_index >= self.setup.slots[self.playing_slot].frames.len() {
+            // Played through or started on an empty slot
+            self.playing_slot = self.next_slot()?;
+            self.buffer_index = 0;
         }
+
+        let recording = &self.setup.slots[self.playing_slot];
+        let diff = recording.frames[self.buffer_index].to_owned();
+        let facing = recording.facing;
+        self.buffer_index += 1;
+
+        diff.map(|diff| self.mirror(diff, facing))
     }
 }
 
-pub fn update_parrots<T: InputStream + Component>(mut readers: Query<(&mut ParrotStream, &mut T)>) {
-    for (mut parrot, mut stream) in readers.iter_mut() {
+fn recording_file(player: Player) -> String {
+    let name = match player {
+        Player::One => "one",
+        Player::Two => "two",
+    };
+    format!("assets/recordings/player_{}.ron", name)
+}
+
+pub fn update_parrots<T: InputStream + Component>(
+    mut readers: Query<(&mut ParrotStream, &mut T, Option<&Facing>)>,
+) {
+    for (mut parrot, mut stream, facing) in readers.iter_mut() {
+        if let Some(facing) = facing {
+            parrot.current_facing = *facing;
+        }
+
         if parrot.mode == ParrotMode::Listening {
             parrot.listen(stream.read());
         } else if parrot.mode == ParrotMode::Repeating {
@@ -65,3 +254,141 @@ pub fn update_parrots<T: InputStream + Component>(mut readers: Query<(&mut Parro
         }
     }
 }
+
+pub fn update_parrot_files(mut parrots: Query<(&Player, &mut ParrotStream)>) {
+    for (player, mut parrot) in parrots.iter_mut() {
+        match parrot.file_request.take() {
+            Some(FileRequest::Save) => parrot.save(*player),
+            Some(FileRequest::Load) => parrot.load(*player),
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use types::{GameButton, StickPosition};
+
+    use super::*;
+
+    fn stick(position: StickPosition) -> Option<Diff> {
+        Some(Diff {
+            stick_move: Some(position),
+            ..default()
+        })
+    }
+
+    fn record(parrot: &mut ParrotStream, frames: Vec<Option<Diff>>) {
+        parrot.cycle();
+        for frame in frames {
+            parrot.listen(frame);
+        }
+        // Back to noop through playback
+        parrot.cycle();
+        parrot.cycle();
+    }
+
+    fn play(parrot: &mut ParrotStream) {
+        parrot.mode = ParrotMode::Repeating;
+        parrot.start_playback();
+    }
+
+    #[test]
+    fn empty_recording_reads_nothing() {
+        let mut parrot = ParrotStream::default();
+        parrot.cycle();
+        parrot.cycle();
+
+        assert_eq!(parrot.status().mode, ParrotMode::Repeating);
+        assert!(parrot.read().is_none());
+        assert!(parrot.read().is_none());
+    }
+
+    #[test]
+    fn playback_loops_recording() {
+        let mut parrot = ParrotStream::default();
+        record(&mut parrot, vec![stick(StickPosition::E), None]);
+        play(&mut parrot);
+
+        assert_eq!(parrot.read(), stick(StickPosition::E));
+        assert_eq!(parrot.read(), None);
+        assert_eq!(parrot.read(), stick(StickPosition::E));
+    }
+
+    #[test]
+    fn sequential_slots() {
+        let mut parrot = ParrotStream::default();
+        record(&mut parrot, vec![stick(StickPosition::E)]);
+        parrot.handle(ParrotAction::NextSlot);
+        parrot.handle(ParrotAction::NextSlot);
+        record(
+            &mut parrot,
+            vec![Some(
+                Diff::default().apply(crate::InputEvent::Press(GameButton::Fast)),
+            )],
+        );
+        assert_eq!(parrot.status().slot, 2);
+
+        parrot.handle(ParrotAction::NextSlot);
+        play(&mut parrot);
+        // Slot 3 is empty, wraps around to the first recorded slot
+        assert_eq!(parrot.read(), stick(StickPosition::E));
+        assert!(parrot.read().unwrap().pressed_contains(&GameButton::Fast));
+        assert_eq!(parrot.read(), stick(StickPosition::E));
+    }
+
+    #[test]
+    fn random_selection_only_plays_recorded_slots() {
+        let mut parrot = ParrotStream::default();
+        parrot.handle(ParrotAction::NextSlot);
+        record(&mut parrot, vec![stick(StickPosition::S)]);
+        parrot.handle(ParrotAction::CycleSelection);
+        assert_eq!(parrot.status().selection, SlotSelection::Random);
+
+        play(&mut parrot);
+        for _ in 0..10 {
+            assert_eq!(parrot.read(), stick(StickPosition::S));
+        }
+    }
+
+    #[test]
+    fn random_selection_follows_the_seed() {
+        let random_playback = |seed: u64| {
+            let mut parrot = ParrotStream::default();
+            parrot.seed(seed);
+            for stick_position in [StickPosition::N, StickPosition::S, StickPosition::E] {
+                record(&mut parrot, vec![stick(stick_position)]);
+                parrot.handle(ParrotAction::NextSlot);
+            }
+            parrot.handle(ParrotAction::CycleSelection);
+
+            play(&mut parrot);
+            (0..20).map(|_| parrot.read()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(random_playback(7), random_playback(7));
+    }
+
+    #[test]
+    fn relative_playback_mirrors() {
+        let mut parrot = ParrotStream::default();
+        record(&mut parrot, vec![stick(StickPosition::SE)]);
+        parrot.current_facing = Facing::Left;
+        play(&mut parrot);
+
+        assert_eq!(parrot.read(), stick(StickPosition::SE));
+        parrot.handle(ParrotAction::ToggleRelative);
+        assert_eq!(parrot.read(), stick(StickPosition::SW));
+    }
+
+    #[test]
+    fn setup_survives_serialization() {
+        let mut parrot = ParrotStream::default();
+        parrot.handle(ParrotAction::ToggleRelative);
+        record(&mut parrot, vec![stick(StickPosition::N), None]);
+
+        let serialized = ron::to_string(&parrot.setup).unwrap();
+        let deserialized: ParrotSetup = ron::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, parrot.setup);
+    }
+}
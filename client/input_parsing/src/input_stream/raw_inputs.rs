@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+use types::{GameButton, StickPosition};
+
+use crate::{
+    bindings::SocdMode,
+    helper_types::{Diff, InputEvent},
+};
+
+/// Digital inputs for one direction axis, like left and right on a dpad
+#[derive(Debug, Default)]
+struct DigitalAxis {
+    negative: bool,
+    positive: bool,
+    last_pressed: i32,
+}
+impl DigitalAxis {
+    fn update(&mut self, pressed: bool, direction: i32) {
+        if direction > 0 {
+            self.positive = pressed;
+        } else {
+            self.negative = pressed;
+        }
+
+        if pressed {
+            self.last_pressed = direction;
+        }
+    }
+
+    fn resolve(&self, socd: SocdMode, vertical: bool) -> i32 {
+        match (self.negative, self.positive) {
+            (false, false) => 0,
+            (true, false) => -1,
+            (false, true) => 1,
+            (true, true) => match socd {
+                SocdMode::LastInputWins => self.last_pressed,
+                SocdMode::Neutral => 0,
+                SocdMode::UpPriority => {
+                    if vertical {
+                        1
+                    } else {
+                        0
+                    }
+                }
+            },
+        }
+    }
+}
+
+/// Device state shared by the streams that read physical hardware
+#[derive(Debug, Default)]
+pub(super) struct RawInputs {
+    next_read: Vec<InputEvent>,
+    stick_position: IVec2,
+    stick_position_last_read: StickPosition,
+    pub(super) socd: SocdMode,
+    dpad_x: DigitalAxis,
+    dpad_y: DigitalAxis,
+    // Multiple physical buttons can be bound to one game button
+    held: HashMap<GameButton, usize>,
+}
+
+impl RawInputs {
+    fn update_next_diff_stick(&mut self) {
+        let discrete_stick = self.stick_position.into();
+        self.next_read.push(InputEvent::Point(discrete_stick));
+    }
+
+    pub(super) fn update_stick(&mut self, new_x: Option<i32>, new_y: Option<i32>) {
+        if let Some(x) = new_x {
+            self.stick_position.x = x;
+        }
+        if let Some(y) = new_y {
+            self.stick_position.y = y;
+        }
+
+        self.update_next_diff_stick();
+    }
+
+    pub(super) fn press_button(&mut self, button: GameButton) {
+        *self.held.entry(button).or_default() += 1;
+        self.next_read.push(InputEvent::Press(button));
+    }
+
+    pub(super) fn release_button(&mut self, button: GameButton) {
+        if let Some(count) = self.held.get_mut(&button) {
+            *count -= 1;
+            if *count == 0 {
+                self.held.remove(&button);
+                self.next_read.push(InputEvent::Release(button));
+            }
+        }
+    }
+
+    pub(super) fn release_all(&mut self) {
+        for (button, _) in self.held.drain() {
+            self.next_read.push(InputEvent::Release(button));
+        }
+    }
+
+    pub(super) fn update_dpad(&mut self, pressed: bool, new_x: Option<i32>, new_y: Option<i32>) {
+        // Opposite directions are resolved with the SOCD mode
+        // Hitbox style controllers can hold both at once
+        if let Some(x) = new_x {
+            self.dpad_x.update(pressed, x);
+            self.stick_position.x = self.dpad_x.resolve(self.socd, false);
+        }
+        if let Some(y) = new_y {
+            self.dpad_y.update(pressed, y);
+            self.stick_position.y = self.dpad_y.resolve(self.socd, true);
+        }
+
+        self.update_next_diff_stick();
+    }
+
+    pub(super) fn read(&mut self) -> Option<Diff> {
+        if self.next_read.is_empty() {
+            return None;
+        }
+
+        let mut diff = self
+            .next_read
+            .drain(..)
+            .fold(Diff::default(), |acc, new| acc.apply(new));
+
+        if let Some(new_stick) = diff.stick_move {
+            if new_stick == self.stick_position_last_read {
+                diff.stick_move = None
+            }
+            self.stick_position_last_read = new_stick;
+        }
+        Some(diff)
+    }
+}
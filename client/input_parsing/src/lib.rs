@@ -1,57 +1,134 @@
 use bevy::prelude::*;
 use characters::MoveId;
 use std::collections::{HashMap, VecDeque};
-use time::WAGStage;
+use time::{CombatApp, CombatStage, WAGStage};
+use types::Player;
 
+mod bindings;
 mod helper_types;
 mod input_parser;
 mod input_stream;
 mod motion_input;
+mod notation;
+mod replay;
 
-pub use helper_types::InputEvent;
-pub use input_parser::InputParser;
+pub use bindings::{
+    InputSource, InputSources, KeyBinding, KeyboardBindings, KeyboardLayout, PadBinding, PadLayout,
+    PadLayouts, SocdMode, SocdModes,
+};
+pub use helper_types::{Diff, InputEvent};
+pub use input_parser::{HistoryEntry, InputParser, InvalidInput, ParserState};
+pub use input_stream::{
+    InputStream, KeyboardStream, NetworkStream, PadStream, ParrotAction, ParrotMode, ParrotStatus,
+    ParrotStream, SlotSelection, PARROT_SLOTS,
+};
+pub use motion_input::{ChargeProgress, MotionInput};
+pub use notation::{ParseError, ParseErrorKind};
+pub use replay::{
+    MatchSettings, PlayerRecording, Replay, ReplayCharacters, ReplayDefinitions, ReplayError,
+    ReplayRecorder, ReplayStream, RoundRecording, REPLAY_VERSION,
+};
 
-use input_stream::{update_pads, update_parrots, PadStream, ParrotStream};
+use bindings::{save_pad_layouts, ControlsConfig};
+use input_stream::{
+    update_keyboards, update_pads, update_parrot_files, update_parrots, update_socd_modes,
+};
+use replay::update_replay_streams;
 
-const MAX_SECONDS_BETWEEN_SUBSEQUENT_MOTIONS: f32 = 0.2; // In seconds
+const MAX_FRAMES_BETWEEN_SUBSEQUENT_MOTIONS: usize = (0.2 * constants::FPS) as usize;
 const STICK_DEAD_ZONE: f32 = 0.2;
 
 pub struct InputParsingPlugin;
 
 impl Plugin for InputParsingPlugin {
     fn build(&self, app: &mut App) {
+        let controls = ControlsConfig::load();
+
         app.insert_resource(VecDeque::<Gamepad>::default())
+            .insert_resource(controls.sources)
+            .insert_resource(controls.socd)
+            .insert_resource(controls.keyboard)
+            .insert_resource(PadLayouts::load())
+            .add_system(save_pad_layouts)
+            .add_system(update_parrot_files)
             .add_system_set_to_stage(
                 WAGStage::Inputs,
                 SystemSet::new()
-                    .with_system(update_pads)
-                    .with_system(update_parrots::<PadStream>.after(update_pads))
+                    .with_system(update_socd_modes)
+                    .with_system(update_pads.after(update_socd_modes))
+                    .with_system(update_keyboards.after(update_socd_modes)),
+            )
+            // Controllers fill their streams every update, parsing empties them once per frame
+            .add_combat_system_set(
+                CombatStage::Inputs,
+                SystemSet::new()
+                    .with_system(update_parrots::<PadStream>)
                     .with_system(
                         // Very important for this to happen after we've updated parrots
                         // If an entity has a parrot stream, it will drain the basic pad stream
                         input_parser::parse_input::<PadStream>.after(update_parrots::<PadStream>),
                     )
+                    .with_system(update_parrots::<KeyboardStream>)
+                    .with_system(
+                        input_parser::parse_input::<KeyboardStream>
+                            .after(update_parrots::<KeyboardStream>),
+                    )
+                    .with_system(input_parser::parse_input::<NetworkStream>)
+                    .with_system(update_replay_streams)
+                    .with_system(
+                        input_parser::parse_input::<ReplayStream>.after(update_replay_streams),
+                    )
                     .with_system(
                         input_parser::parse_input::<ParrotStream>
-                            .after(update_parrots::<PadStream>),
+                            .after(update_parrots::<PadStream>)
+                            .after(update_parrots::<KeyboardStream>),
                     ),
             );
     }
 }
 
 #[derive(Bundle)]
-pub struct PadBundle {
-    reader: PadStream,
+pub struct PadBundle<T: Component> {
+    reader: T,
     parser: InputParser,
     parrot: ParrotStream,
 }
-impl PadBundle {
-    pub fn new(inputs: HashMap<MoveId, &str>) -> Self {
-        Self {
+impl PadBundle<PadStream> {
+    pub fn new(inputs: HashMap<MoveId, &str>) -> Result<Self, InvalidInput> {
+        Ok(Self {
             reader: PadStream::default(),
-            parser: InputParser::load(inputs),
+            parser: InputParser::load(inputs)?,
             parrot: ParrotStream::default(),
-        }
+        })
+    }
+}
+impl PadBundle<KeyboardStream> {
+    pub fn keyboard(inputs: HashMap<MoveId, &str>, player: Player) -> Result<Self, InvalidInput> {
+        Ok(Self {
+            reader: KeyboardStream::new(player),
+            parser: InputParser::load(inputs)?,
+            parrot: ParrotStream::default(),
+        })
+    }
+}
+
+impl PadBundle<ReplayStream> {
+    pub fn replay(inputs: HashMap<MoveId, &str>, player: Player) -> Result<Self, InvalidInput> {
+        Ok(Self {
+            reader: ReplayStream::new(player),
+            parser: InputParser::load(inputs)?,
+            parrot: ParrotStream::default(),
+        })
+    }
+}
+
+impl PadBundle<NetworkStream> {
+    pub fn network(inputs: HashMap<MoveId, &str>) -> Result<Self, InvalidInput> {
+        Ok(Self {
+            reader: NetworkStream::default(),
+            parser: InputParser::load(inputs)?,
+            parrot: ParrotStream::default(),
+        })
     }
 }
 
@@ -68,12 +145,15 @@ pub mod testing {
         parrot: ParrotStream,
     }
     impl PreWrittenInputBundle {
-        pub fn new(events: Vec<Option<InputEvent>>, inputs: HashMap<MoveId, &str>) -> Self {
-            Self {
+        pub fn new(
+            events: Vec<Option<InputEvent>>,
+            inputs: HashMap<MoveId, &str>,
+        ) -> Result<Self, InvalidInput> {
+            Ok(Self {
                 reader: PreWrittenStream::new(events),
-                parser: InputParser::load(inputs),
+                parser: InputParser::load(inputs)?,
                 parrot: ParrotStream::default(),
-            }
+            })
         }
     }
 
@@ -84,12 +164,12 @@ pub mod testing {
         parrot: ParrotStream,
     }
     impl TestInputBundle {
-        pub fn new(inputs: HashMap<MoveId, &str>) -> Self {
-            Self {
+        pub fn new(inputs: HashMap<MoveId, &str>) -> Result<Self, InvalidInput> {
+            Ok(Self {
                 reader: TestStream::default(),
-                parser: InputParser::load(inputs),
+                parser: InputParser::load(inputs)?,
                 parrot: ParrotStream::default(),
-            }
+            })
         }
     }
 }
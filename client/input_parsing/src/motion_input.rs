@@ -1,48 +1,73 @@
 use bevy::prelude::*;
-use bevy::utils::Instant;
+use serde::{Deserialize, Serialize};
 
 use types::{GameButton, StickPosition};
 
 use crate::{
     helper_types::{Diff, InputEvent},
-    MAX_SECONDS_BETWEEN_SUBSEQUENT_MOTIONS,
+    notation::{self, ParseError},
+    MAX_FRAMES_BETWEEN_SUBSEQUENT_MOTIONS,
 };
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-struct ParserHead {
+/// How far along a charge input is, in frames
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ChargeProgress {
+    pub progress: usize,
+    pub required: usize,
+}
+impl ChargeProgress {
+    pub fn ratio(&self) -> f32 {
+        self.progress.min(self.required) as f32 / self.required as f32
+    }
+}
+
+fn charge_frames(requirement: &InputEvent) -> Option<usize> {
+    match requirement {
+        InputEvent::Charge(_, frames, _) => Some(*frames),
+        InputEvent::Optional(inner) => charge_frames(inner),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+pub(crate) struct ParserHead {
     index: usize,
-    last_update: Instant,
+    /// Frame on which the head was last bumped or refreshed
+    last_update: usize,
     /// None if complete
     requirement: Option<InputEvent>,
     multipresses_received: Vec<GameButton>,
+    points_visited: Vec<StickPosition>,
+    /// Frame on which the stick entered the charge points
+    charge_start: Option<usize>,
+    /// Frames that were already charged when charge_start was set
+    precharged: usize,
+    /// Frame on which the stick left the charge points before the charge was done
+    charge_left: Option<usize>,
 }
 
-impl Default for ParserHead {
-    fn default() -> Self {
-        Self {
-            index: default(),
-            last_update: Instant::now(),
-            requirement: default(),
-            multipresses_received: default(),
-        }
-    }
-}
 impl ParserHead {
-    fn from_old_stick(requirements: &[InputEvent], old_stick: StickPosition) -> ParserHead {
-        let mut new = ParserHead::new(requirements.get(0).cloned());
+    fn from_old_stick(
+        requirements: &[InputEvent],
+        old_stick: StickPosition,
+        frame: usize,
+    ) -> ParserHead {
+        let mut new = ParserHead::new(requirements.get(0).cloned(), frame);
         new.advance(
             requirements,
             &Diff {
                 stick_move: Some(old_stick),
                 ..default()
             },
+            frame,
         );
         new
     }
 
-    fn new(requirement: Option<InputEvent>) -> ParserHead {
+    fn new(requirement: Option<InputEvent>, frame: usize) -> ParserHead {
         ParserHead {
             requirement,
+            last_update: frame,
             ..default()
         }
     }
@@ -51,25 +76,84 @@ impl ParserHead {
         self.requirement.is_none()
     }
 
-    fn expired(&self) -> bool {
-        let time_from_previous_event = Instant::now()
-            .duration_since(self.last_update)
-            .as_secs_f32();
+    /// Same step, with the same progress on it
+    fn same_progress(&self, other: &ParserHead) -> bool {
+        *self
+            == ParserHead {
+                last_update: self.last_update,
+                ..other.clone()
+            }
+    }
 
-        time_from_previous_event > MAX_SECONDS_BETWEEN_SUBSEQUENT_MOTIONS
+    fn expired(&self, frame: usize, max_frames_between: usize) -> bool {
+        // Clock going backwards means the round was reset, old heads are stale in that case too
+        // Charging heads are waiting on purpose, so they don't expire, decaying ones wait for the stick
+        let (since, window) = match self.charge_left {
+            Some(left) => (left, self.charge_decay()),
+            None => (self.last_update, max_frames_between),
+        };
+        frame < self.last_update || (self.charge_start.is_none() && frame - since > window)
     }
 
-    fn bump(&mut self, requirement: Option<InputEvent>) {
+    fn charge_decay(&self) -> usize {
+        match self.requirement {
+            Some(InputEvent::Charge(_, _, decay)) => decay,
+            Some(InputEvent::Optional(ref inner)) => match **inner {
+                InputEvent::Charge(_, _, decay) => decay,
+                _ => 0,
+            },
+            _ => 0,
+        }
+    }
+
+    fn bump(&mut self, requirement: Option<InputEvent>, frame: usize) {
         *self = ParserHead {
             requirement,
             index: self.index + 1,
+            last_update: frame,
             ..default()
         }
     }
 
-    fn advance(&mut self, requirements: &[InputEvent], diff: &Diff) {
-        while !self.is_done() && self.requirement_met(diff) {
-            self.bump(self.get_next_requirement(requirements));
+    fn advance(&mut self, requirements: &[InputEvent], diff: &Diff, frame: usize) {
+        while !self.is_done()
+            && (self.requirement_met(diff, frame) || self.can_skip(requirements, diff, frame))
+        {
+            self.bump(self.get_next_requirement(requirements), frame);
+        }
+    }
+
+    fn can_skip(&self, requirements: &[InputEvent], diff: &Diff, frame: usize) -> bool {
+        // Optional steps are skipped when the diff already meets the step after
+        if let Some(InputEvent::Optional(_)) = self.requirement {
+            ParserHead::new(self.get_next_requirement(requirements), frame)
+                .requirement_met(diff, frame)
+        } else {
+            false
+        }
+    }
+
+    fn charge_progress(&self, requirements: &[InputEvent], frame: usize) -> Option<ChargeProgress> {
+        if let Some(required) = self.requirement.as_ref().and_then(charge_frames) {
+            let progress = if let Some(start) = self.charge_start {
+                frame.saturating_sub(start) + self.precharged
+            } else if self.charge_left.is_some() {
+                // Still there if the stick comes back in time
+                self.precharged
+            } else {
+                return None;
+            };
+            Some(ChargeProgress { progress, required })
+        } else {
+            // A charge that was completed earlier in the motion stays full until the head expires
+            requirements[..self.index]
+                .iter()
+                .rev()
+                .find_map(charge_frames)
+                .map(|required| ChargeProgress {
+                    progress: required,
+                    required,
+                })
         }
     }
 
@@ -77,71 +161,167 @@ impl ParserHead {
         requirements.get(self.index + 1).cloned()
     }
 
-    fn requirement_met(&mut self, diff: &Diff) -> bool {
+    fn requirement_met(&mut self, diff: &Diff, frame: usize) -> bool {
         if let Some(requirement) = self.requirement.clone() {
-            match requirement {
-                InputEvent::Point(required_stick) => {
-                    diff.stick_move.is_some() && diff.stick_move.unwrap() == required_stick
+            self.event_met(requirement, diff, frame)
+        } else {
+            false
+        }
+    }
+
+    fn event_met(&mut self, requirement: InputEvent, diff: &Diff, frame: usize) -> bool {
+        match requirement {
+            InputEvent::Point(required_stick) => {
+                diff.stick_move.is_some() && diff.stick_move.unwrap() == required_stick
+            }
+            InputEvent::Range(required_sticks) => {
+                diff.stick_move.is_some() && required_sticks.contains(&diff.stick_move.unwrap())
+            }
+            InputEvent::Press(required_button) => diff.pressed_contains(&required_button),
+            InputEvent::MultiPress(required_buttons) => {
+                if let Some(pressed) = diff.pressed.clone() {
+                    let mut new_buttons = pressed.into_iter().collect();
+                    self.multipresses_received.append(&mut new_buttons);
+
+                    if required_buttons
+                        .into_iter()
+                        .filter(|button| !self.multipresses_received.contains(button))
+                        .peekable()
+                        .peek()
+                        .is_none()
+                    {
+                        return true;
+                    }
                 }
-                InputEvent::Range(required_sticks) => {
-                    diff.stick_move.is_some() && required_sticks.contains(&diff.stick_move.unwrap())
+                false
+            }
+            InputEvent::Release(required_button) => diff.released_contains(&required_button),
+            InputEvent::AllPoints(required_sticks) => {
+                if let Some(stick) = diff.stick_move {
+                    self.points_visited.push(stick);
                 }
-                InputEvent::Press(required_button) => diff.pressed_contains(&required_button),
-                InputEvent::MultiPress(required_buttons) => {
-                    if let Some(pressed) = diff.pressed.clone() {
-                        let mut new_buttons = pressed.into_iter().collect();
-                        self.multipresses_received.append(&mut new_buttons);
-
-                        if required_buttons
-                            .into_iter()
-                            .filter(|button| !self.multipresses_received.contains(button))
-                            .peekable()
-                            .peek()
-                            .is_none()
-                        {
-                            return true;
+
+                required_sticks
+                    .iter()
+                    .all(|stick| self.points_visited.contains(stick))
+            }
+            InputEvent::Charge(required_sticks, frames, decay) => {
+                if let Some(stick) = diff.stick_move {
+                    if required_sticks.contains(&stick) {
+                        if self.charge_start.is_none() {
+                            // Coming back before the charge decays picks up where it left off
+                            let decayed = self
+                                .charge_left
+                                .take()
+                                .map_or(true, |left| frame.saturating_sub(left) > decay);
+                            if decayed {
+                                self.precharged = 0;
+                            }
                         }
+                        // Moving between the charge points doesn't reset the charge
+                        self.charge_start.get_or_insert(frame);
+                        false
+                    } else if let Some(start) = self.charge_start.take() {
+                        let charged = frame.saturating_sub(start) + self.precharged;
+                        if charged >= frames {
+                            true
+                        } else {
+                            // Leaving an unfinished charge loses it, unless it decays
+                            self.precharged = if decay > 0 { charged } else { 0 };
+                            self.charge_left = Some(frame).filter(|_| decay > 0);
+                            false
+                        }
+                    } else {
+                        false
                     }
+                } else {
                     false
                 }
-                InputEvent::Release(required_button) => diff.released_contains(&required_button),
             }
-        } else {
-            false
+            InputEvent::Optional(inner) => self.event_met(*inner, diff, frame),
         }
     }
 }
 
-#[derive(Default, Debug, Clone)]
+#[derive(Debug, Clone)]
 pub struct MotionInput {
     heads: Vec<ParserHead>,
     requirements: Vec<InputEvent>,
+    /// How many frames may pass between two subsequent requirements before the motion is dropped
+    max_frames_between: usize,
 }
 impl MotionInput {
+    /// Override the leniency window, for example to give a 360 more time than a quarter circle
+    #[must_use]
+    pub fn with_window(self, max_frames_between: usize) -> Self {
+        Self {
+            max_frames_between,
+            ..self
+        }
+    }
+
     pub fn clear(&mut self) {
         self.heads.clear();
     }
 
+    pub(crate) fn save_heads(&self) -> Vec<ParserHead> {
+        self.heads.clone()
+    }
+
+    pub(crate) fn load_heads(&mut self, heads: Vec<ParserHead>) {
+        self.heads = heads;
+    }
+
+    /// Start the motion as if its opening charge had been held for a while already.
+    /// Does nothing if the motion doesn't start with a charge
+    pub fn set_charge(&mut self, charged: usize, frame: usize) {
+        if let Some(requirement) = self
+            .requirements
+            .get(0)
+            .filter(|requirement| charge_frames(requirement).is_some())
+        {
+            self.heads.retain(|head| head.index != 0);
+            self.heads.push(ParserHead {
+                charge_start: Some(frame),
+                precharged: charged,
+                ..ParserHead::new(Some(requirement.clone()), frame)
+            });
+        }
+    }
+
     pub fn is_done(&self) -> bool {
         self.heads.iter().any(|head| head.requirement.is_none())
     }
 
-    pub fn advance(&mut self, diff: &Diff, old_stick: StickPosition) {
+    /// Progress of the head that is closest to completing a charge
+    pub fn charge_progress(&self, frame: usize) -> Option<ChargeProgress> {
+        self.heads
+            .iter()
+            .filter_map(|head| head.charge_progress(&self.requirements, frame))
+            .max_by(|a, b| a.ratio().total_cmp(&b.ratio()))
+    }
+
+    pub fn advance(&mut self, diff: &Diff, old_stick: StickPosition, frame: usize) {
         if self.is_done() {
             return;
         }
 
-        let new_head = ParserHead::from_old_stick(&self.requirements, old_stick);
+        let new_head = ParserHead::from_old_stick(&self.requirements, old_stick, frame);
 
-        if let Some(ref mut existing_head) = self
+        if let Some(existing_head) = self
             .heads
             .iter_mut()
-            .find(|head| head.index == new_head.index)
+            .find(|head| head.same_progress(&new_head))
         {
-            // There is an existing head with the same index
-            existing_head.last_update = Instant::now();
-        } else {
-            // No existing head
+            // Just as far along as a new head, so it may as well be one
+            existing_head.last_update = frame;
+        } else if !self
+            .heads
+            .iter()
+            .any(|head| head.index == new_head.index && head.charge_start.is_some())
+        {
+            // Heads that got further, like with some of a circle or a multipress, expire on their own
+            // and the new head makes a fresh attempt. A charge that started earlier is always ahead
             self.heads.push(new_head);
         }
 
@@ -150,10 +330,10 @@ impl MotionInput {
             .clone()
             .into_iter()
             .filter_map(|mut head| {
-                if head.expired() {
+                if head.expired(frame, self.max_frames_between) {
                     None
                 } else {
-                    head.advance(&self.requirements, diff);
+                    head.advance(&self.requirements, diff, frame);
                     Some(head)
                 }
             })
@@ -161,75 +341,15 @@ impl MotionInput {
     }
 }
 
-impl From<&str> for MotionInput {
-    fn from(input: &str) -> Self {
-        let mut tokens = vec![];
-        let mut multichar = None;
+impl TryFrom<&str> for MotionInput {
+    type Error = ParseError;
 
-        for ch in input.chars() {
-            match ch {
-                '[' => {
-                    assert!(multichar.is_none(), "Nested '['");
-                    multichar = Some(String::default());
-                }
-                ']' => {
-                    assert!(multichar.is_some(), "Closing ']' before opener");
-                    tokens.push(multichar.unwrap());
-                    multichar = None;
-                }
-                _ => {
-                    if let Some(mut temp) = multichar {
-                        temp.push(ch);
-                        multichar = Some(temp);
-                    } else {
-                        tokens.push(ch.to_string());
-                    }
-                }
-            }
-        }
-
-        assert!(!tokens.is_empty(), "No tokens");
-
-        let requirements: Vec<InputEvent> = tokens
-            .into_iter()
-            .map(|token| {
-                let gts: Vec<InputEvent> = token.chars().map(|char| char.into()).collect();
-                if gts.len() == 1 {
-                    gts[0].clone()
-                } else {
-                    match gts[0] {
-                        InputEvent::Point(_) => InputEvent::Range(
-                            gts.into_iter()
-                                .map(|requirement| {
-                                    if let InputEvent::Point(stick) = requirement {
-                                        stick
-                                    } else {
-                                        panic!("Mismatched requirements")
-                                    }
-                                })
-                                .collect(),
-                        ),
-                        InputEvent::Press(_) => InputEvent::MultiPress(
-                            gts.into_iter()
-                                .map(|requirement| {
-                                    if let InputEvent::Press(button) = requirement {
-                                        button
-                                    } else {
-                                        panic!("Mismatched requirements")
-                                    }
-                                })
-                                .collect(),
-                        ),
-                        _ => panic!("Multiple non multipleable requirements"),
-                    }
-                }
-            })
-            .collect();
-
-        Self {
-            requirements,
-            ..default()
-        }
+    fn try_from(input: &str) -> Result<Self, Self::Error> {
+        Ok(Self {
+            requirements: notation::parse(input)?,
+            heads: vec![],
+            max_frames_between: MAX_FRAMES_BETWEEN_SUBSEQUENT_MOTIONS,
+        })
     }
 }
 
@@ -242,7 +362,7 @@ mod test {
 
     #[test]
     fn hadouken() {
-        let parsed: MotionInput = "236f".into();
+        let parsed = MotionInput::try_from("236f").unwrap();
         assert_eq!(
             parsed.requirements,
             vec![
@@ -256,17 +376,70 @@ mod test {
 
     #[test]
     fn head_advancement() {
-        let motion: MotionInput = "6f".into();
+        let motion = MotionInput::try_from("6f").unwrap();
 
         let diff = Diff {
             pressed: Some(set! {GameButton::Fast}),
             ..default()
         };
 
-        let mut ph = ParserHead::from_old_stick(&motion.requirements, StickPosition::E);
+        let mut ph = ParserHead::from_old_stick(&motion.requirements, StickPosition::E, 0);
         assert!(ph.index == 1);
 
-        ph.advance(&motion.requirements, &diff);
+        ph.advance(&motion.requirements, &diff, 1);
         assert!(ph.is_done());
     }
+
+    #[test]
+    fn head_expiration() {
+        let head = ParserHead::new(None, 10);
+
+        assert!(!head.expired(10, 5));
+        assert!(!head.expired(15, 5));
+        assert!(head.expired(16, 5));
+        // Clock was reset
+        assert!(head.expired(0, 5));
+    }
+
+    #[test]
+    fn charging_head_does_not_expire() {
+        let head = ParserHead {
+            charge_start: Some(10),
+            ..ParserHead::new(None, 10)
+        };
+
+        assert!(!head.expired(100, 5));
+        assert!(head.expired(0, 5));
+    }
+
+    #[test]
+    fn set_charge_counts_towards_the_charge() {
+        let mut motion = MotionInput::try_from("[4]:30 6f").unwrap();
+        motion.set_charge(30, 5);
+        assert_eq!(
+            motion.charge_progress(10),
+            Some(ChargeProgress {
+                progress: 35,
+                required: 30
+            })
+        );
+
+        motion.advance(
+            &Diff {
+                stick_move: Some(StickPosition::E),
+                ..default()
+            },
+            StickPosition::Neutral,
+            10,
+        );
+        motion.advance(
+            &Diff {
+                pressed: Some(set! {GameButton::Fast}),
+                ..default()
+            },
+            StickPosition::E,
+            11,
+        );
+        assert!(motion.is_done());
+    }
 }
@@ -0,0 +1,477 @@
+//! Parser for the move input notation
+//!
+//! Numbers are numpad directions, lowercase letters are presses and uppercase letters are releases.
+//! `[..]` is either a range of directions or a multipress. A range followed by `:` and a frame count
+//! is a charge, so `[4]:30 6f` is hold back for 30 frames, then forward and fast.
+//! Charges are lost as soon as the stick leaves, unless they decay, like `[4]:30~12` which keeps the
+//! charge if the stick comes back within 12 frames.
+//! `360` and `720` are full circles, `?` makes the previous step optional.
+
+use std::fmt::Display;
+
+use types::{GameButton, StickPosition};
+
+use crate::helper_types::InputEvent;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    Empty,
+    UnknownCharacter(char),
+    NestedBracket,
+    UnclosedBracket,
+    UnopenedBracket,
+    EmptyBracket,
+    MixedBracket,
+    MultipleReleases,
+    ChargedButtons,
+    ZeroCharge,
+    MissingChargeFrames,
+    MissingDecayFrames,
+    AmbiguousCharge,
+    MisplacedOptional,
+    TrailingOptional,
+}
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseErrorKind::Empty => write!(f, "input is empty"),
+            ParseErrorKind::UnknownCharacter(ch) => write!(f, "unknown character '{}'", ch),
+            ParseErrorKind::NestedBracket => write!(f, "brackets can't be nested"),
+            ParseErrorKind::UnclosedBracket => write!(f, "'[' is never closed"),
+            ParseErrorKind::UnopenedBracket => write!(f, "']' without an opening '['"),
+            ParseErrorKind::EmptyBracket => write!(f, "brackets are empty"),
+            ParseErrorKind::MixedBracket => {
+                write!(f, "brackets mix directions and buttons")
+            }
+            ParseErrorKind::MultipleReleases => {
+                write!(f, "brackets can only contain one release")
+            }
+            ParseErrorKind::ChargedButtons => write!(f, "only directions can be charged"),
+            ParseErrorKind::ZeroCharge => write!(f, "charge must last at least one frame"),
+            ParseErrorKind::MissingChargeFrames => write!(f, "':' must be followed by frames"),
+            ParseErrorKind::MissingDecayFrames => write!(f, "'~' must be followed by frames"),
+            ParseErrorKind::AmbiguousCharge => write!(
+                f,
+                "directions followed by whitespace, write charges as [..]:frames"
+            ),
+            ParseErrorKind::MisplacedOptional => write!(f, "'?' must follow a required step"),
+            ParseErrorKind::TrailingOptional => write!(f, "last step can't be optional"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    /// Index of the offending character
+    pub position: usize,
+    pub kind: ParseErrorKind,
+}
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at position {}", self.kind, self.position)
+    }
+}
+impl std::error::Error for ParseError {}
+
+impl TryFrom<char> for InputEvent {
+    type Error = ParseErrorKind;
+
+    fn try_from(ch: char) -> Result<InputEvent, ParseErrorKind> {
+        match ch {
+            '1'..='9' => Ok(InputEvent::Point((ch as i32 - '0' as i32).into())),
+            'f' => Ok(InputEvent::Press(GameButton::Fast)),
+            'F' => Ok(InputEvent::Release(GameButton::Fast)),
+            's' => Ok(InputEvent::Press(GameButton::Strong)),
+            'S' => Ok(InputEvent::Release(GameButton::Strong)),
+            'g' => Ok(InputEvent::Press(GameButton::Grab)),
+            'G' => Ok(InputEvent::Release(GameButton::Grab)),
+            'e' => Ok(InputEvent::Press(GameButton::Equipment)),
+            'E' => Ok(InputEvent::Release(GameButton::Equipment)),
+            't' => Ok(InputEvent::Press(GameButton::Taunt)),
+            'T' => Ok(InputEvent::Release(GameButton::Taunt)),
+            _ => Err(ParseErrorKind::UnknownCharacter(ch)),
+        }
+    }
+}
+
+pub fn parse(input: &str) -> Result<Vec<InputEvent>, ParseError> {
+    Parser {
+        chars: input.chars().collect(),
+        position: 0,
+        requirements: vec![],
+    }
+    .parse()
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    requirements: Vec<InputEvent>,
+}
+impl Parser {
+    fn parse(mut self) -> Result<Vec<InputEvent>, ParseError> {
+        while let Some(ch) = self.peek() {
+            match ch {
+                '[' => self.bracket()?,
+                ']' => return Err(self.error(ParseErrorKind::UnopenedBracket)),
+                '?' => self.optional()?,
+                _ if ch.is_whitespace() => self.position += 1,
+                _ if self.circle("360") => self.push_circles(1),
+                _ if self.circle("720") => self.push_circles(2),
+                _ => {
+                    let event = InputEvent::try_from(ch).map_err(|kind| self.error(kind))?;
+                    self.requirements.push(event);
+                    self.position += 1;
+                }
+            }
+        }
+
+        match self.requirements.last() {
+            None => Err(ParseError {
+                position: 0,
+                kind: ParseErrorKind::Empty,
+            }),
+            Some(InputEvent::Optional(_)) => Err(ParseError {
+                position: self.chars.len() - 1,
+                kind: ParseErrorKind::TrailingOptional,
+            }),
+            Some(_) => Ok(self.requirements),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn error(&self, kind: ParseErrorKind) -> ParseError {
+        ParseError {
+            position: self.position,
+            kind,
+        }
+    }
+
+    fn circle(&mut self, keyword: &str) -> bool {
+        let matches = keyword
+            .chars()
+            .enumerate()
+            .all(|(offset, ch)| self.chars.get(self.position + offset) == Some(&ch));
+
+        if matches {
+            self.position += keyword.len();
+        }
+        matches
+    }
+
+    fn push_circles(&mut self, amount: usize) {
+        for _ in 0..amount {
+            self.requirements.push(InputEvent::AllPoints(vec![
+                StickPosition::E,
+                StickPosition::S,
+                StickPosition::W,
+                StickPosition::N,
+            ]));
+        }
+    }
+
+    fn optional(&mut self) -> Result<(), ParseError> {
+        match self.requirements.pop() {
+            Some(InputEvent::Optional(_)) | None => {
+                Err(self.error(ParseErrorKind::MisplacedOptional))
+            }
+            Some(previous) => {
+                self.requirements
+                    .push(InputEvent::Optional(Box::new(previous)));
+                self.position += 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn bracket(&mut self) -> Result<(), ParseError> {
+        let opener = self.position;
+        self.position += 1;
+
+        let mut events = vec![];
+        loop {
+            match self.peek() {
+                None => {
+                    return Err(ParseError {
+                        position: opener,
+                        kind: ParseErrorKind::UnclosedBracket,
+                    })
+                }
+                Some('[') => return Err(self.error(ParseErrorKind::NestedBracket)),
+                Some(']') => break,
+                Some(ch) => {
+                    events.push(InputEvent::try_from(ch).map_err(|kind| self.error(kind))?);
+                    self.position += 1;
+                }
+            }
+        }
+
+        let closer = self.position;
+        self.position += 1;
+
+        let event = Self::combine(events).map_err(|kind| ParseError {
+            position: opener,
+            kind,
+        })?;
+
+        if self.peek() == Some(':') {
+            let marker = self.position;
+            self.position += 1;
+            let frames = self.charge_frames().ok_or(ParseError {
+                position: marker,
+                kind: ParseErrorKind::MissingChargeFrames,
+            })?;
+            if frames == 0 {
+                return Err(ParseError {
+                    position: marker + 1,
+                    kind: ParseErrorKind::ZeroCharge,
+                });
+            }
+            let decay = if self.peek() == Some('~') {
+                let marker = self.position;
+                self.position += 1;
+                self.charge_frames().ok_or(ParseError {
+                    position: marker,
+                    kind: ParseErrorKind::MissingDecayFrames,
+                })?
+            } else {
+                0
+            };
+
+            let sticks = match event {
+                InputEvent::Point(stick) => vec![stick],
+                InputEvent::Range(sticks) => sticks,
+                _ => {
+                    return Err(ParseError {
+                        position: opener,
+                        kind: ParseErrorKind::ChargedButtons,
+                    })
+                }
+            };
+            self.requirements
+                .push(InputEvent::Charge(sticks, frames, decay));
+        } else if self.old_charge() {
+            // Used to be a charge, now it would quietly become directions
+            return Err(ParseError {
+                position: closer + 1,
+                kind: ParseErrorKind::AmbiguousCharge,
+            });
+        } else {
+            self.requirements.push(event);
+        }
+
+        Ok(())
+    }
+
+    fn digits(&self) -> usize {
+        self.chars[self.position..]
+            .iter()
+            .take_while(|ch| ch.is_ascii_digit())
+            .count()
+    }
+
+    /// Frames of a charge or its decay, they run until the first character that is not a digit
+    fn charge_frames(&mut self) -> Option<usize> {
+        let digits = self.digits();
+        let frames = self.chars[self.position..self.position + digits]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()?;
+
+        self.position += digits;
+        Some(frames)
+    }
+
+    /// Digits and whitespace right after a bracket, like the `30 ` in `[4]30 6f`
+    fn old_charge(&self) -> bool {
+        let digits = self.digits();
+        digits > 0
+            && self
+                .chars
+                .get(self.position + digits)
+                .map_or(false, |ch| ch.is_whitespace())
+    }
+
+    fn combine(mut events: Vec<InputEvent>) -> Result<InputEvent, ParseErrorKind> {
+        if events.len() <= 1 {
+            return events.pop().ok_or(ParseErrorKind::EmptyBracket);
+        }
+
+        if events
+            .iter()
+            .all(|event| matches!(event, InputEvent::Point(_)))
+        {
+            Ok(InputEvent::Range(
+                events
+                    .into_iter()
+                    .filter_map(|event| match event {
+                        InputEvent::Point(stick) => Some(stick),
+                        _ => None,
+                    })
+                    .collect(),
+            ))
+        } else if events
+            .iter()
+            .all(|event| matches!(event, InputEvent::Press(_)))
+        {
+            Ok(InputEvent::MultiPress(
+                events
+                    .into_iter()
+                    .filter_map(|event| match event {
+                        InputEvent::Press(button) => Some(button),
+                        _ => None,
+                    })
+                    .collect(),
+            ))
+        } else if events
+            .iter()
+            .all(|event| matches!(event, InputEvent::Release(_)))
+        {
+            Err(ParseErrorKind::MultipleReleases)
+        } else {
+            Err(ParseErrorKind::MixedBracket)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse_error(input: &str) -> ParseError {
+        parse(input).unwrap_err()
+    }
+
+    #[test]
+    fn existing_notation() {
+        assert_eq!(
+            parse("[41]6f").unwrap(),
+            vec![
+                InputEvent::Range(vec![StickPosition::W, StickPosition::SW]),
+                InputEvent::Point(StickPosition::E),
+                InputEvent::Press(GameButton::Fast),
+            ]
+        );
+        assert_eq!(
+            parse("[fs]F").unwrap(),
+            vec![
+                InputEvent::MultiPress(vec![GameButton::Fast, GameButton::Strong]),
+                InputEvent::Release(GameButton::Fast),
+            ]
+        );
+        assert_eq!(
+            parse("[123]8").unwrap(),
+            vec![
+                InputEvent::Range(vec![StickPosition::SW, StickPosition::S, StickPosition::SE]),
+                InputEvent::Point(StickPosition::N),
+            ]
+        );
+    }
+
+    #[test]
+    fn charge() {
+        assert_eq!(
+            parse("[4]:30 6f").unwrap(),
+            vec![
+                InputEvent::Charge(vec![StickPosition::W], 30, 0),
+                InputEvent::Point(StickPosition::E),
+                InputEvent::Press(GameButton::Fast),
+            ]
+        );
+        assert_eq!(
+            parse("[123]:45 8s").unwrap()[0],
+            InputEvent::Charge(
+                vec![StickPosition::SW, StickPosition::S, StickPosition::SE],
+                45,
+                0
+            )
+        );
+        assert_eq!(
+            parse("[4]:30~12 6f").unwrap()[0],
+            InputEvent::Charge(vec![StickPosition::W], 30, 12)
+        );
+    }
+
+    #[test]
+    fn charge_needs_a_marker() {
+        // Both read like a charge of a few frames, but could be meant as directions
+        for input in ["[41]6 f", "[4]30 6f"] {
+            assert_eq!(
+                parse_error(input),
+                ParseError {
+                    position: input.find(']').unwrap() + 1,
+                    kind: ParseErrorKind::AmbiguousCharge
+                }
+            );
+        }
+        // Without whitespace the digits are directions, the way they always were
+        assert_eq!(parse("[41]6f").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn circles() {
+        let parsed = parse("360g").unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert!(matches!(parsed[0], InputEvent::AllPoints(_)));
+
+        let parsed = parse("720g").unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[0], parsed[1]);
+    }
+
+    #[test]
+    fn optional_steps() {
+        assert_eq!(
+            parse("23?6f").unwrap(),
+            vec![
+                InputEvent::Point(StickPosition::S),
+                InputEvent::Optional(Box::new(InputEvent::Point(StickPosition::SE))),
+                InputEvent::Point(StickPosition::E),
+                InputEvent::Press(GameButton::Fast),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_report_position() {
+        assert_eq!(
+            parse_error("236x"),
+            ParseError {
+                position: 3,
+                kind: ParseErrorKind::UnknownCharacter('x')
+            }
+        );
+        assert_eq!(
+            parse_error("20f").kind,
+            ParseErrorKind::UnknownCharacter('0')
+        );
+        assert_eq!(parse_error("").kind, ParseErrorKind::Empty);
+        assert_eq!(parse_error("6[f").position, 1);
+        assert_eq!(parse_error("6[f").kind, ParseErrorKind::UnclosedBracket);
+        assert_eq!(parse_error("[[f]]").position, 1);
+        assert_eq!(parse_error("6]").kind, ParseErrorKind::UnopenedBracket);
+        assert_eq!(parse_error("[]f").kind, ParseErrorKind::EmptyBracket);
+        assert_eq!(parse_error("[6f]").kind, ParseErrorKind::MixedBracket);
+        assert_eq!(parse_error("[FS]").kind, ParseErrorKind::MultipleReleases);
+        assert_eq!(parse_error("[f]:30 6").kind, ParseErrorKind::ChargedButtons);
+        assert_eq!(parse_error("[4]:0 6f").kind, ParseErrorKind::ZeroCharge);
+        assert_eq!(
+            parse_error("[4]: 6f"),
+            ParseError {
+                position: 3,
+                kind: ParseErrorKind::MissingChargeFrames
+            }
+        );
+        assert_eq!(
+            parse_error("[4]:30~f").kind,
+            ParseErrorKind::MissingDecayFrames
+        );
+        assert_eq!(parse_error("?6f").kind, ParseErrorKind::MisplacedOptional);
+        assert_eq!(parse_error("2??6f").position, 2);
+        assert_eq!(parse_error("6f?").kind, ParseErrorKind::TrailingOptional);
+    }
+}
@@ -0,0 +1,482 @@
+use bevy::prelude::*;
+use characters::{Character, CharacterId, Inventory};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt::{Display, Formatter},
+    path::Path,
+};
+use time::Clock;
+use types::Player;
+
+use crate::{helper_types::Diff, input_stream::InputStream};
+
+/// Bump when the format changes, old replays will refuse to load
+pub const REPLAY_VERSION: u32 = 3;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Parse(ron::Error),
+    UnsupportedVersion(u32),
+    /// Recorded with a different frame rate or round length, playback would diverge
+    SettingsMismatch(MatchSettings),
+}
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::Io(error) => write!(f, "could not access replay file: {}", error),
+            ReplayError::Parse(error) => write!(f, "invalid replay: {}", error),
+            ReplayError::UnsupportedVersion(version) => write!(
+                f,
+                "replay version {} is not supported, expected {}",
+                version, REPLAY_VERSION
+            ),
+            ReplayError::SettingsMismatch(settings) => write!(
+                f,
+                "replay was recorded at {} fps with {} second rounds, expected {} fps and {} seconds",
+                settings.fps,
+                settings.round_time,
+                constants::FPS,
+                time::ROUND_TIME
+            ),
+        }
+    }
+}
+impl Error for ReplayError {}
+
+/// Settings the match was played with, replays from a build with other settings refuse to load
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchSettings {
+    pub fps: f32,
+    pub round_time: f32,
+    /// Gameplay randomness is seeded with this
+    pub seed: u64,
+}
+impl MatchSettings {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            fps: constants::FPS,
+            round_time: time::ROUND_TIME,
+            seed,
+        }
+    }
+
+    /// Whether this build plays with the same frame rate and round length, any seed goes
+    pub fn is_current(&self) -> bool {
+        *self == Self::new(self.seed)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayCharacters {
+    pub one: CharacterId,
+    pub two: CharacterId,
+}
+impl ReplayCharacters {
+    pub fn get(&self, player: Player) -> CharacterId {
+        match player {
+            Player::One => self.one,
+            Player::Two => self.two,
+        }
+    }
+}
+
+/// What the definition files held when the match started.
+/// Stored so replays play out the same after the files are edited
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReplayDefinitions {
+    pub one: Character,
+    pub two: Character,
+}
+impl ReplayDefinitions {
+    pub fn get(&self, player: Player) -> &Character {
+        match player {
+            Player::One => &self.one,
+            Player::Two => &self.two,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayerRecording {
+    /// Inventory at the start of the round
+    pub inventory: Inventory,
+    /// Inputs keyed by the frame of the round they happened on, at most one per frame
+    pub frames: Vec<(usize, Diff)>,
+    /// Definitions that were reloaded during the round, keyed by the frame they took over on
+    pub reloads: Vec<(usize, Character)>,
+}
+impl PlayerRecording {
+    pub fn new(inventory: Inventory) -> Self {
+        Self {
+            inventory,
+            ..default()
+        }
+    }
+
+    fn record(&mut self, frame: usize, diff: Diff) {
+        match self.frames.last_mut() {
+            Some((last_frame, last_diff)) if *last_frame == frame => {
+                *last_diff = std::mem::take(last_diff).merge(diff);
+            }
+            _ => self.frames.push((frame, diff)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RoundRecording {
+    pub one: PlayerRecording,
+    pub two: PlayerRecording,
+}
+impl RoundRecording {
+    pub fn get(&self, player: Player) -> &PlayerRecording {
+        match player {
+            Player::One => &self.one,
+            Player::Two => &self.two,
+        }
+    }
+
+    fn get_mut(&mut self, player: Player) -> &mut PlayerRecording {
+        match player {
+            Player::One => &mut self.one,
+            Player::Two => &mut self.two,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplayHeader {
+    version: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub settings: MatchSettings,
+    pub characters: ReplayCharacters,
+    pub definitions: ReplayDefinitions,
+    pub rounds: Vec<RoundRecording>,
+}
+impl Replay {
+    pub fn new(characters: ReplayCharacters, definitions: ReplayDefinitions, seed: u64) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            settings: MatchSettings::new(seed),
+            characters,
+            definitions,
+            rounds: vec![],
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        let contents = std::fs::read_to_string(path).map_err(ReplayError::Io)?;
+        Self::parse(&contents)
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, ReplayError> {
+        // Check the version first, an old replay would most likely fail to parse
+        let header: ReplayHeader = ron::from_str(contents).map_err(ReplayError::Parse)?;
+        if header.version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(header.version));
+        }
+
+        let replay: Self = ron::from_str(contents).map_err(ReplayError::Parse)?;
+        if !replay.settings.is_current() {
+            return Err(ReplayError::SettingsMismatch(replay.settings));
+        }
+
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        // Replays get long, no point in indenting every frame
+        let config = PrettyConfig::default().depth_limit(3);
+        let contents = ron::ser::to_string_pretty(self, config).map_err(ReplayError::Parse)?;
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(ReplayError::Io)?;
+        }
+        std::fs::write(path, contents).map_err(ReplayError::Io)
+    }
+}
+
+/// Collects the inputs of an ongoing match
+#[derive(Debug)]
+pub struct ReplayRecorder {
+    pub replay: Replay,
+    recording: bool,
+}
+impl ReplayRecorder {
+    pub fn new(characters: ReplayCharacters, definitions: ReplayDefinitions, seed: u64) -> Self {
+        Self {
+            replay: Replay::new(characters, definitions, seed),
+            recording: false,
+        }
+    }
+
+    pub fn start_round(&mut self, one: Inventory, two: Inventory) {
+        self.replay.rounds.push(RoundRecording {
+            one: PlayerRecording::new(one),
+            two: PlayerRecording::new(two),
+        });
+        self.recording = true;
+    }
+
+    pub fn end_round(&mut self) {
+        self.recording = false;
+    }
+
+    pub fn record(&mut self, player: Player, frame: usize, diff: Diff) {
+        if !self.recording {
+            return;
+        }
+
+        if let Some(round) = self.replay.rounds.last_mut() {
+            round.get_mut(player).record(frame, diff);
+        }
+    }
+
+    pub fn record_reload(&mut self, player: Player, frame: usize, character: Character) {
+        if !self.recording {
+            return;
+        }
+
+        if let Some(round) = self.replay.rounds.last_mut() {
+            round.get_mut(player).reloads.push((frame, character));
+        }
+    }
+
+    /// Forgets everything after the frame, the round was rewound to it and plays out again
+    pub fn rewind(&mut self, frame: usize) {
+        if !self.recording {
+            return;
+        }
+
+        if let Some(round) = self.replay.rounds.last_mut() {
+            for recording in [&mut round.one, &mut round.two] {
+                recording.frames.retain(|(recorded, _)| *recorded <= frame);
+                recording.reloads.retain(|(recorded, _)| *recorded <= frame);
+            }
+        }
+    }
+}
+
+/// Feeds recorded inputs back in on the frames they originally happened on
+#[derive(Debug, Component)]
+pub struct ReplayStream {
+    player: Player,
+    frames: VecDeque<(usize, Diff)>,
+    reloads: VecDeque<(usize, Character)>,
+    current_frame: usize,
+}
+impl ReplayStream {
+    pub fn new(player: Player) -> Self {
+        Self {
+            player,
+            frames: VecDeque::default(),
+            reloads: VecDeque::default(),
+            current_frame: 0,
+        }
+    }
+
+    pub fn start_round(&mut self, round: &RoundRecording) {
+        let recording = round.get(self.player);
+        self.frames = recording.frames.iter().cloned().collect();
+        self.reloads = recording.reloads.iter().cloned().collect();
+        self.current_frame = 0;
+    }
+
+    /// Definition that was reloaded on this frame, or the latest one if frames were skipped
+    pub fn take_reload(&mut self, frame: usize) -> Option<Character> {
+        let mut result = None;
+        while let Some((reload_frame, _)) = self.reloads.front() {
+            if *reload_frame > frame {
+                break;
+            }
+
+            result = self.reloads.pop_front().map(|(_, character)| character);
+        }
+
+        result
+    }
+}
+impl InputStream for ReplayStream {
+    fn read(&mut self) -> Option<Diff> {
+        let mut result: Option<Diff> = None;
+
+        // If the clock skipped frames, everything that was missed is applied at once
+        while let Some((frame, _)) = self.frames.front() {
+            if *frame > self.current_frame {
+                break;
+            }
+
+            let (_, diff) = self.frames.pop_front().unwrap();
+            result = Some(match result {
+                Some(earlier) => earlier.merge(diff),
+                None => diff,
+            });
+        }
+
+        result
+    }
+}
+
+pub fn update_replay_streams(clock: Res<Clock>, mut streams: Query<&mut ReplayStream>) {
+    for mut stream in streams.iter_mut() {
+        stream.current_frame = clock.frame;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use types::{GameButton, StickPosition};
+
+    use crate::helper_types::InputEvent;
+
+    use super::*;
+
+    fn stick(position: StickPosition) -> Diff {
+        Diff::default().apply(InputEvent::Point(position))
+    }
+
+    fn press(button: GameButton) -> Diff {
+        Diff::default().apply(InputEvent::Press(button))
+    }
+
+    fn recorded_match() -> Replay {
+        let mut recorder =
+            ReplayRecorder::new(ReplayCharacters::default(), ReplayDefinitions::default(), 7);
+        // Before the round starts
+        recorder.record(Player::One, 1, stick(StickPosition::N));
+
+        recorder.start_round(Inventory::default(), Inventory::default());
+        recorder.record(Player::One, 3, stick(StickPosition::S));
+        recorder.record_reload(Player::Two, 4, Character::default());
+        recorder.record(Player::One, 3, press(GameButton::Fast));
+        recorder.record(Player::Two, 5, stick(StickPosition::E));
+        recorder.end_round();
+
+        recorder.record(Player::One, 10, stick(StickPosition::W));
+        recorder.replay
+    }
+
+    #[test]
+    fn records_only_during_rounds() {
+        let replay = recorded_match();
+
+        assert_eq!(replay.rounds.len(), 1);
+        assert_eq!(replay.rounds[0].one.frames.len(), 1);
+        assert_eq!(replay.rounds[0].two.frames.len(), 1);
+    }
+
+    #[test]
+    fn same_frame_inputs_are_merged() {
+        let replay = recorded_match();
+        let (frame, diff) = &replay.rounds[0].one.frames[0];
+
+        assert_eq!(*frame, 3);
+        assert_eq!(diff.stick_move, Some(StickPosition::S));
+        assert!(diff.pressed_contains(&GameButton::Fast));
+    }
+
+    #[test]
+    fn rewinding_drops_later_frames() {
+        let mut recorder =
+            ReplayRecorder::new(ReplayCharacters::default(), ReplayDefinitions::default(), 7);
+        recorder.start_round(Inventory::default(), Inventory::default());
+        recorder.record(Player::One, 3, stick(StickPosition::S));
+        recorder.record(Player::One, 5, stick(StickPosition::N));
+        recorder.record_reload(Player::Two, 4, Character::default());
+
+        recorder.rewind(3);
+        // Played out again with a different input
+        recorder.record(Player::One, 5, press(GameButton::Fast));
+
+        let round = &recorder.replay.rounds[0];
+        assert_eq!(
+            round.one.frames,
+            vec![(3, stick(StickPosition::S)), (5, press(GameButton::Fast))]
+        );
+        assert!(round.two.reloads.is_empty());
+    }
+
+    #[test]
+    fn stream_plays_inputs_on_their_frames() {
+        let replay = recorded_match();
+        let mut stream = ReplayStream::new(Player::Two);
+        stream.start_round(&replay.rounds[0]);
+
+        for frame in 0..5 {
+            stream.current_frame = frame;
+            assert!(stream.read().is_none());
+        }
+        stream.current_frame = 5;
+        assert_eq!(stream.read(), Some(stick(StickPosition::E)));
+        assert!(stream.read().is_none());
+    }
+
+    #[test]
+    fn stream_catches_up_on_skipped_frames() {
+        let mut stream = ReplayStream::new(Player::One);
+        stream.start_round(&RoundRecording {
+            one: PlayerRecording {
+                frames: vec![(1, stick(StickPosition::S)), (2, press(GameButton::Fast))],
+                ..default()
+            },
+            two: PlayerRecording::default(),
+        });
+
+        stream.current_frame = 4;
+        let diff = stream.read().unwrap();
+        assert_eq!(diff.stick_move, Some(StickPosition::S));
+        assert!(diff.pressed_contains(&GameButton::Fast));
+    }
+
+    #[test]
+    fn stream_hands_out_reloads_on_their_frames() {
+        let replay = recorded_match();
+        assert_eq!(replay.rounds[0].one.reloads.len(), 0);
+
+        let mut stream = ReplayStream::new(Player::Two);
+        stream.start_round(&replay.rounds[0]);
+        assert!(stream.take_reload(3).is_none());
+        assert!(stream.take_reload(4).is_some());
+        assert!(stream.take_reload(5).is_none());
+    }
+
+    #[test]
+    fn replay_round_trip() {
+        let replay = recorded_match();
+        let serialized = ron::to_string(&replay).unwrap();
+
+        assert_eq!(Replay::parse(&serialized).unwrap(), replay);
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let mut replay = recorded_match();
+        replay.version = REPLAY_VERSION + 1;
+        let serialized = ron::to_string(&replay).unwrap();
+
+        assert!(matches!(
+            Replay::parse(&serialized),
+            Err(ReplayError::UnsupportedVersion(version)) if version == REPLAY_VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn other_settings_are_rejected() {
+        let mut replay = recorded_match();
+        replay.settings.round_time += 10.0;
+        let serialized = ron::to_string(&replay).unwrap();
+
+        assert!(matches!(
+            Replay::parse(&serialized),
+            Err(ReplayError::SettingsMismatch(settings)) if settings == replay.settings
+        ));
+    }
+}
@@ -1,19 +1,26 @@
 use bevy::{ecs::query::WorldQuery, prelude::*};
 use types::Players;
+use whoops_all_grapplers_headless::HeadlessRunner;
 
 pub struct AppWrapper {
-    app: App,
+    runner: HeadlessRunner,
 }
 impl AppWrapper {
-    pub fn new(app: App) -> Self {
-        Self { app }
+    pub fn new(runner: HeadlessRunner) -> Self {
+        Self { runner }
     }
 
     pub fn query<Q: WorldQuery>(&mut self) -> QueryState<Q> {
-        self.app.world.query::<Q>()
+        self.runner.world_mut().query::<Q>()
     }
     pub fn world(&self) -> &World {
-        &self.app.world
+        self.runner.world()
+    }
+    pub fn world_mut(&mut self) -> &mut World {
+        self.runner.world_mut()
+    }
+    pub fn tick(&mut self, frames: usize) {
+        self.runner.run(frames);
     }
     pub fn get_players(&self) -> (Entity, Entity) {
         let players = self.world().resource::<Players>();
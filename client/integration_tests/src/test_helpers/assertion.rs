@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+use characters::{Hitbox, MoveId, Resources};
+use player_state::PlayerState;
+use std::fmt::Display;
+use types::{Owner, Player, Players};
+use whoops_all_grapplers_lib::Health;
+
+/// Something that should be true on the frame the assertion is reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assertion {
+    Stunned(Player),
+    NotStunned(Player),
+    Health(Player, i32),
+    Meter(Player, i32),
+    /// Player has at least one hitbox out
+    HitboxExists(Player),
+    /// Player is in the middle of the move
+    Move(Player, MoveId),
+}
+impl Display for Assertion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Assertion::Stunned(player) => write!(f, "player {} is in stun", player),
+            Assertion::NotStunned(player) => write!(f, "player {} is not in stun", player),
+            Assertion::Health(player, value) => write!(f, "player {} health is {}", player, value),
+            Assertion::Meter(player, value) => write!(f, "player {} meter is {}", player, value),
+            Assertion::HitboxExists(player) => write!(f, "player {} has a hitbox", player),
+            Assertion::Move(player, id) => write!(f, "player {} is doing {}", player, id),
+        }
+    }
+}
+impl Assertion {
+    /// On failure, returns what the state actually was
+    pub fn check(&self, world: &mut World) -> Result<(), String> {
+        match *self {
+            Assertion::Stunned(player) | Assertion::NotStunned(player) => {
+                let state = get::<PlayerState>(world, player);
+                if state.stunned() == matches!(self, Assertion::Stunned(_)) {
+                    Ok(())
+                } else {
+                    Err(format!("state was {:?}", state))
+                }
+            }
+            Assertion::Health(player, value) => {
+                let health = get::<Health>(world, player).get();
+                if health == value {
+                    Ok(())
+                } else {
+                    Err(format!("health was {}", health))
+                }
+            }
+            Assertion::Meter(player, value) => {
+                let meter = get::<Resources>(world, player).meter.get();
+                if meter == value {
+                    Ok(())
+                } else {
+                    Err(format!("meter was {}", meter))
+                }
+            }
+            Assertion::HitboxExists(player) => {
+                let owners: Vec<Player> = world
+                    .query_filtered::<&Owner, With<Hitbox>>()
+                    .iter(world)
+                    .map(|owner| **owner)
+                    .collect();
+                if owners.contains(&player) {
+                    Ok(())
+                } else {
+                    Err(format!("hitbox owners were {:?}", owners))
+                }
+            }
+            Assertion::Move(player, id) => {
+                let state = get::<PlayerState>(world, player);
+                if state.get_move_state().map(|situation| situation.move_id) == Some(id) {
+                    Ok(())
+                } else {
+                    Err(format!("state was {:?}", state))
+                }
+            }
+        }
+    }
+}
+
+fn get<T: Component + Clone>(world: &World, player: Player) -> T {
+    let entity = world.resource::<Players>().get(player);
+    world
+        .get::<T>(entity)
+        .expect("Player is missing a component")
+        .clone()
+}
@@ -1,6 +1,9 @@
 mod app_wrapper;
 pub use app_wrapper::AppWrapper;
 
+mod assertion;
+pub use assertion::Assertion;
+
 mod test_runner;
 pub use test_runner::TestRunner;
 
@@ -1,26 +1,22 @@
-use std::{
-    thread::sleep,
-    time::{Duration, Instant},
-};
-
-use bevy::{app::ScheduleRunnerSettings, asset::AssetPlugin, input::InputPlugin, prelude::*};
-use bevy_hanabi::HanabiPlugin;
-use input_parsing::testing::{parse_input, PreWrittenStream};
+use input_parsing::testing::PreWrittenInputBundle;
 use types::Players;
-use whoops_all_grapplers_lib::{DevPlugin, WAGLib};
+use whoops_all_grapplers_headless::{HeadlessRunner, RoundSetup};
 
 use super::{AppWrapper, TestSpec};
 
 /// A framework that runs through a list of specs from a common starting position.
 pub struct TestRunner {
-    // TODO: Starting scenario
-    // TODO: Several tests per runner
+    setup: RoundSetup,
 }
 impl TestRunner {
-    /// Setup the game env for a test case
+    /// Specs start from the default spawn
     pub fn new() -> Self {
-        // TODO: Scenario data for setup
-        Self {}
+        Self::from_setup(RoundSetup::default())
+    }
+
+    /// Every spec starts over from the setup
+    pub fn from_setup(setup: RoundSetup) -> Self {
+        Self { setup }
     }
 
     /// Run a spec, return the world
@@ -28,56 +24,44 @@ impl TestRunner {
         let ticks = spec.len;
         println!("Starting test case '{}' ({} ticks)", case_name, ticks);
 
-        let mut app = self.setup(spec);
-        self.simulate(&mut app, ticks);
-        AppWrapper::new(app)
+        let mut runner = self.setup(spec.p1_bundle, spec.p2_bundle);
+        let mut assertions = spec.assertions.into_iter().peekable();
+        for frame in 0..=ticks {
+            // Assertions on a frame see the state after that many frames of inputs
+            while let Some((_, assertion)) = assertions.next_if(|(at, _)| *at == frame) {
+                if let Err(actual) = assertion.check(runner.world_mut()) {
+                    panic!(
+                        "Test case '{}' failed on frame {}: expected {}, but {}",
+                        case_name, frame, assertion, actual
+                    );
+                }
+            }
+
+            if frame < ticks {
+                runner.step();
+            }
+        }
+        AppWrapper::new(runner)
     }
 
     /// Setup the game env for a test case
-    fn setup(&self, spec: TestSpec) -> App {
-        let mut app = App::new();
-        app.add_plugins(MinimalPlugins);
-        app.insert_resource(ScheduleRunnerSettings {
-            run_mode: bevy::app::RunMode::Loop { wait: None },
-        });
-        app.add_plugin(AssetPlugin::default());
-        app.add_plugin(InputPlugin::default());
-
-        app.add_plugins_with(WAGLib, |group| {
-            group.disable::<HanabiPlugin>();
-            group.disable::<DevPlugin>();
-            group
-        });
-        app.add_system(parse_input::<PreWrittenStream>);
-        app.update();
+    fn setup(
+        &self,
+        p1_bundle: PreWrittenInputBundle,
+        p2_bundle: PreWrittenInputBundle,
+    ) -> HeadlessRunner {
+        let mut runner = HeadlessRunner::new();
 
         // Go to combat (skip buy phase)
-        app.world
-            .resource_mut::<Input<KeyCode>>()
-            .press(KeyCode::Return);
-        app.update();
-
-        let players = app.world.resource::<Players>();
-        let p1 = players.one;
-        let p2 = players.two;
-        drop(players); // Needs to drop because couldn't figure out how to get the Players resource without by value.
+        runner.start_round();
 
-        app.world.entity_mut(p1).insert_bundle(spec.p1_bundle);
-        app.world.entity_mut(p2).insert_bundle(spec.p2_bundle);
+        let players = runner.world().resource::<Players>();
+        let (p1, p2) = (players.one, players.two);
+        runner.world_mut().entity_mut(p1).insert_bundle(p1_bundle);
+        runner.world_mut().entity_mut(p2).insert_bundle(p2_bundle);
 
-        app.update();
-        app
-    }
-
-    /// Run the spec inputs
-    fn simulate(&self, app: &mut App, ticks: usize) {
-        for _ in 0..ticks {
-            let pre_update = Instant::now();
-            app.update();
-            // Must sleep real time here, as bevy clock doesn't care about our fake time
-            sleep(Duration::from_secs_f32(
-                (1.0 / constants::FPS - pre_update.elapsed().as_secs_f32()).max(0.0),
-            ))
-        }
+        // After the bundles, the setup charges the parser in them
+        runner.apply_setup(&self.setup);
+        runner
     }
 }
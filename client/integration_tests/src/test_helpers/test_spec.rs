@@ -1,46 +1,72 @@
 use std::time::Duration;
 
-use characters::dummy;
+use characters::CharacterId;
 use input_parsing::{testing::PreWrittenInputBundle, InputEvent};
+use whoops_all_grapplers_headless::game_assets;
+
+use super::Assertion;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputClump {
     InputStream(&'static str),
     Idle(Duration),
-    // Plan was to have a "wait" and an "assert" type here
+    /// Idle for an exact amount of frames
+    Wait(usize),
+    /// Checked after the frames before it in the same list, takes no time
+    Assert(Assertion),
 }
 
 pub struct TestSpec {
     pub p1_bundle: PreWrittenInputBundle,
     pub p2_bundle: PreWrittenInputBundle,
+    /// Frame to check on, in order
+    pub assertions: Vec<(usize, Assertion)>,
     pub len: usize,
 }
 impl TestSpec {
     pub fn new(p1: Vec<InputClump>, p2: Vec<InputClump>) -> Self {
-        let character = dummy();
-        let p1_events = Self::flatten_events(p1);
-        let p2_events = Self::flatten_events(p2);
+        let character = CharacterId::Dummy.load(&game_assets()).unwrap();
+        let (p1_events, p1_assertions) = Self::flatten_events(p1);
+        let (p2_events, p2_assertions) = Self::flatten_events(p2);
         let inputs = character.get_inputs();
 
+        let mut assertions: Vec<_> = p1_assertions.into_iter().chain(p2_assertions).collect();
+        // Stable, so assertions on the same frame stay in the order they were written
+        assertions.sort_by_key(|(frame, _)| *frame);
+        let last_assertion = assertions.last().map_or(0, |(frame, _)| *frame);
+
         Self {
-            len: p1_events.len().max(p2_events.len()),
-            p1_bundle: PreWrittenInputBundle::new(p1_events, inputs.clone()),
-            p2_bundle: PreWrittenInputBundle::new(p2_events, inputs.clone()),
+            len: p1_events.len().max(p2_events.len()).max(last_assertion),
+            p1_bundle: PreWrittenInputBundle::new(p1_events, inputs.clone()).unwrap(),
+            p2_bundle: PreWrittenInputBundle::new(p2_events, inputs).unwrap(),
+            assertions,
         }
     }
 
-    fn flatten_events(events: Vec<InputClump>) -> Vec<Option<InputEvent>> {
-        events
-            .into_iter()
-            .flat_map(|clump| match clump {
-                InputClump::InputStream(inputs) => inputs
-                    .chars()
-                    .map(|char| Some(InputEvent::from(char)))
-                    .collect(),
-                InputClump::Idle(frames) => {
-                    vec![None; (frames.as_secs_f32() * constants::FPS) as usize]
-                }
-            })
-            .collect()
+    #[allow(clippy::type_complexity)]
+    fn flatten_events(
+        clumps: Vec<InputClump>,
+    ) -> (Vec<Option<InputEvent>>, Vec<(usize, Assertion)>) {
+        let mut events = vec![];
+        let mut assertions = vec![];
+
+        for clump in clumps {
+            match clump {
+                InputClump::InputStream(inputs) => events.extend(
+                    inputs
+                        .chars()
+                        .map(|char| Some(InputEvent::try_from(char).unwrap())),
+                ),
+                InputClump::Idle(duration) => events.extend(vec![
+                    None;
+                    (duration.as_secs_f32() * constants::FPS)
+                        as usize
+                ]),
+                InputClump::Wait(frames) => events.extend(vec![None; frames]),
+                InputClump::Assert(assertion) => assertions.push((events.len(), assertion)),
+            }
+        }
+
+        (events, assertions)
     }
 }
@@ -1,7 +1,25 @@
-use bevy::prelude::*;
-use std::time::Duration;
+use bevy::{
+    ecs::event::Events,
+    input::{keyboard::KeyboardInput, ElementState},
+    prelude::*,
+};
+use characters::{
+    Character, CharacterId, CounterType, Hitbox, Inventory, ItemId, MoveId, MoveSituation,
+    Resources,
+};
+use input_parsing::{InputEvent, KeyboardBindings, PadBundle, ReplayRecorder};
+use netcode::{LinkConditions, LoopbackTransport};
+use player_state::PlayerState;
+use std::{fs, time::Duration};
+use time::{Clock, ClockControl, RoundResult};
+use types::{Facing, GameButton, Owner, Player, Players, StickPosition};
+use whoops_all_grapplers_headless::{game_assets, HeadlessRunner, PlayerSetup, RoundSetup};
+use whoops_all_grapplers_lib::{
+    ChecksumLog, ChecksumPart, GameRng, Health, Netplay, PendingDefinition, ReplayPlayback,
+    Snapshot,
+};
 
-use crate::test_helpers::{InputClump, TestRunner, TestSpec};
+use crate::test_helpers::{AppWrapper, Assertion, InputClump, TestRunner, TestSpec};
 
 #[test]
 fn round_start() {
@@ -36,3 +54,917 @@ fn round_start() {
 
     assert!(p1_height > p2_height);
 }
+
+#[test]
+fn snapshot_restores_round() {
+    let mut test_runner = TestRunner::new();
+
+    let mut wrapper = test_runner.run(
+        "Snapshot",
+        TestSpec::new(
+            vec![
+                InputClump::Idle(Duration::from_secs_f32(0.1)),
+                InputClump::InputStream("8"),
+                InputClump::Idle(Duration::from_secs_f32(0.1)),
+            ],
+            // Hitbox is out when the snapshot is taken
+            vec![InputClump::Wait(4), InputClump::InputStream("f")],
+        ),
+    );
+
+    let (p1, _) = wrapper.get_players();
+    let height = |wrapper: &mut AppWrapper| {
+        wrapper
+            .query::<&Transform>()
+            .get(wrapper.world(), p1)
+            .unwrap()
+            .translation
+            .y
+    };
+
+    let airborne_height = height(&mut wrapper);
+    let frame = wrapper.world().resource::<Clock>().frame;
+    // Goes through bytes like a save state would
+    let snapshot =
+        Snapshot::from_bytes(&Snapshot::capture(wrapper.world_mut()).to_bytes()).unwrap();
+
+    // Swapped in while in the air, there is no move to wait for
+    let original_definition = wrapper.world().get::<Character>(p1).unwrap().clone();
+    let edited = dummy_with_punch_on("s");
+    let swap = |wrapper: &mut AppWrapper| {
+        wrapper
+            .world_mut()
+            .entity_mut(p1)
+            .insert(PendingDefinition::new(edited.clone()).unwrap());
+    };
+    swap(&mut wrapper);
+    wrapper.tick(60);
+    assert_ne!(height(&mut wrapper), airborne_height);
+    assert_eq!(wrapper.world().get::<Character>(p1), Some(&edited));
+    let original = wrapper.world().resource::<ChecksumLog>().clone();
+
+    snapshot.restore(wrapper.world_mut());
+    assert_eq!(height(&mut wrapper), airborne_height);
+    assert_eq!(wrapper.world().resource::<Clock>().frame, frame);
+    assert_eq!(
+        wrapper.world().get::<Character>(p1),
+        Some(&original_definition)
+    );
+
+    // Every frame after the restore has to be simulated again, and come out the same
+    wrapper.world_mut().resource_mut::<ChecksumLog>().rounds[0]
+        .retain(|checksum| checksum.frame <= frame);
+    swap(&mut wrapper);
+    wrapper.tick(60);
+    let resimulated = wrapper.world().resource::<ChecksumLog>();
+    assert_eq!(original.first_desync(resimulated), None);
+}
+
+#[test]
+fn identical_runs_have_identical_checksums() {
+    let spec = || {
+        TestSpec::new(
+            vec![
+                InputClump::Idle(Duration::from_secs_f32(0.1)),
+                InputClump::InputStream("6f"),
+                InputClump::Idle(Duration::from_secs_f32(0.5)),
+            ],
+            vec![InputClump::InputStream("8")],
+        )
+    };
+
+    let checksums = |wrapper: AppWrapper| wrapper.world().resource::<ChecksumLog>().clone();
+    let first = checksums(TestRunner::new().run("Checksums 1", spec()));
+    let mut second = checksums(TestRunner::new().run("Checksums 2", spec()));
+
+    assert!(!first.rounds[0].is_empty());
+    assert_eq!(first.first_desync(&second), None);
+
+    let desynced_frame = second.rounds[0][5].frame;
+    second.rounds[0][5].two.velocity += 1;
+    let desync = first.first_desync(&second).unwrap();
+    assert_eq!(desync.frame, desynced_frame);
+    assert_eq!(desync.player, Some(Player::Two));
+    assert_eq!(desync.part, Some(ChecksumPart::Velocity));
+
+    // Running out of frames or rounds early is a desync too, whichever log is the shorter one
+    let mut truncated = first.clone();
+    let missing_frame = truncated.rounds[0].pop().unwrap().frame;
+    for (a, b) in [(&first, &truncated), (&truncated, &first)] {
+        let desync = a.first_desync(b).unwrap();
+        assert_eq!((desync.round, desync.frame), (0, missing_frame));
+        assert_eq!(desync.part, None);
+    }
+
+    let mut extra_round = first.clone();
+    extra_round.start_round();
+    let desync = first.first_desync(&extra_round).unwrap();
+    assert_eq!((desync.round, desync.part), (1, None));
+}
+
+#[test]
+fn back_to_back_frames_match_one_frame_per_update() {
+    // Walking moves the camera, and the stage walls with it
+    let walk = vec![None, None, Some(InputEvent::Point(StickPosition::E))];
+    // Command punch moves the player and the hitbox along with them
+    let mut punch = walk.clone();
+    punch.extend(vec![None; 60]);
+    punch.extend([
+        Some(InputEvent::Press(GameButton::Fast)),
+        Some(InputEvent::Release(GameButton::Fast)),
+    ]);
+
+    let runner = || {
+        let mut runner = HeadlessRunner::new();
+        runner.start_round();
+        runner.set_inputs(Player::One, punch.clone()).unwrap();
+        runner.set_inputs(Player::Two, walk.clone()).unwrap();
+        runner
+    };
+
+    let mut stepped = runner();
+    stepped.run(121);
+
+    // Nothing happens between the frames, like when fast forwarding or resimulating
+    let mut batched = runner();
+    // First frame of the round comes with entering combat
+    batched.step();
+    batched.world_mut().resource_mut::<ClockControl>().paused = true;
+    for _ in 0..40 {
+        for _ in 0..3 {
+            time::run_combat_frame(batched.world_mut());
+        }
+        batched.step();
+    }
+
+    assert_eq!(stepped.checksums().first_desync(batched.checksums()), None);
+}
+
+fn send_key(runner: &mut HeadlessRunner, key: KeyCode, state: ElementState) {
+    runner
+        .world_mut()
+        .resource_mut::<Events<KeyboardInput>>()
+        .send(KeyboardInput {
+            scan_code: 0,
+            key_code: Some(key),
+            state,
+        });
+}
+
+#[test]
+fn replay_matches_play_at_uneven_update_rate() {
+    let mut live = HeadlessRunner::new();
+    live.start_round();
+
+    // Played on the keyboard, with the bindings a fresh install has
+    live.world_mut()
+        .insert_resource(KeyboardBindings::default());
+    for player in [Player::One, Player::Two] {
+        let entity = live.world().resource::<Players>().get(player);
+        let character = live.world().get::<Character>(entity).unwrap().clone();
+        let bundle = PadBundle::keyboard(character.get_inputs(), player).unwrap();
+        live.world_mut().entity_mut(entity).insert_bundle(bundle);
+    }
+
+    // Every third update has no frame due, keys still come in on those
+    let mut control = ClockControl::default();
+    control.paused = true;
+    live.world_mut().insert_resource(control);
+
+    let keys = [
+        (16, KeyCode::Left, ElementState::Pressed),
+        // Dash, with the last two directions coming in before the same frame
+        (30, KeyCode::D, ElementState::Pressed),
+        (31, KeyCode::D, ElementState::Released),
+        (32, KeyCode::D, ElementState::Pressed),
+        (40, KeyCode::Left, ElementState::Released),
+        (50, KeyCode::D, ElementState::Released),
+        (61, KeyCode::Numpad1, ElementState::Pressed),
+        (66, KeyCode::Numpad1, ElementState::Released),
+    ];
+    for update in 0..200 {
+        for (_, key, state) in keys.iter().filter(|(at, _, _)| *at == update) {
+            send_key(&mut live, *key, *state);
+        }
+        if update % 3 != 1 {
+            live.world_mut().resource_mut::<ClockControl>().step();
+        }
+        live.step();
+    }
+    assert!(live.in_combat());
+
+    let replay = live.world().resource::<ReplayRecorder>().replay.clone();
+    assert!(!replay.rounds[0].one.frames.is_empty());
+    assert!(!replay.rounds[0].two.frames.is_empty());
+
+    let mut playback = HeadlessRunner::replay(ReplayPlayback::new(replay));
+    while !playback.in_combat() {
+        playback.step();
+    }
+    playback.run_round(Some(live.frame()));
+
+    assert_eq!(live.checksums().first_desync(playback.checksums()), None);
+}
+
+#[test]
+fn netplay_peers_agree_over_a_bad_connection() {
+    const FRAMES: usize = 180;
+
+    let (one, two) = LoopbackTransport::pair(LinkConditions {
+        latency: 3,
+        packet_loss: 0.2,
+        seed: 7,
+    });
+    let mut peers = [
+        HeadlessRunner::netplay(Netplay::new(one, Player::One, 3)),
+        HeadlessRunner::netplay(Netplay::new(two, Player::Two, 3)),
+    ];
+    for peer in peers.iter_mut() {
+        peer.world_mut()
+            .insert_resource(KeyboardBindings::default());
+    }
+    start_netplay_round(&mut peers, &RoundSetup::default());
+
+    // Each side only presses the keys of its own player, the other side has to guess them
+    let keys = [
+        (10, 0, KeyCode::D, ElementState::Pressed),
+        (25, 1, KeyCode::Left, ElementState::Pressed),
+        (40, 0, KeyCode::D, ElementState::Released),
+        (45, 0, KeyCode::J, ElementState::Pressed),
+        (48, 0, KeyCode::J, ElementState::Released),
+        (60, 1, KeyCode::Left, ElementState::Released),
+        (62, 1, KeyCode::Numpad1, ElementState::Pressed),
+        (66, 1, KeyCode::Numpad1, ElementState::Released),
+        (90, 0, KeyCode::W, ElementState::Pressed),
+        (92, 0, KeyCode::W, ElementState::Released),
+        (95, 1, KeyCode::Numpad1, ElementState::Pressed),
+        (97, 1, KeyCode::Numpad1, ElementState::Released),
+    ];
+    let netplay = |peer: &HeadlessRunner| {
+        let netplay = peer.world().resource::<Netplay>();
+        (netplay.frame(), netplay.rollbacks())
+    };
+
+    let mut update = 0;
+    while peers.iter().any(|peer| netplay(peer).0 < FRAMES) {
+        for (_, index, key, state) in keys.iter().filter(|(at, _, _, _)| *at == update) {
+            send_key(&mut peers[*index], *key, *state);
+        }
+        for peer in peers.iter_mut() {
+            peer.step();
+            // Keeps exchanging inputs and rolling back, but doesn't go past the last frame
+            if netplay(peer).0 == FRAMES {
+                peer.world_mut().resource_mut::<ClockControl>().paused = true;
+            }
+        }
+
+        update += 1;
+        assert!(update < 10 * FRAMES, "Peers stopped advancing");
+    }
+    // Lost inputs are resent until the last guesses are corrected
+    for _ in 0..60 {
+        for peer in peers.iter_mut() {
+            peer.step();
+        }
+    }
+
+    for peer in &peers {
+        assert!(peer.in_combat());
+    }
+    assert!(peers.iter().any(|peer| netplay(peer).1 > 0));
+    let [one, two] = &peers;
+    assert_eq!(one.checksums().rounds[0].len(), FRAMES);
+    assert_eq!(one.checksums().first_desync(two.checksums()), None);
+}
+
+/// Both peers press start, the setup is applied before either simulates the round
+fn start_netplay_round(peers: &mut [HeadlessRunner; 2], setup: &RoundSetup) {
+    for peer in peers.iter_mut() {
+        peer.start_round();
+    }
+
+    let mut updates = 0;
+    while peers.iter().any(|peer| !peer.in_combat()) {
+        for peer in peers.iter_mut() {
+            let waiting = !peer.in_combat();
+            peer.step();
+            if waiting && peer.in_combat() {
+                peer.apply_setup(setup);
+            }
+        }
+
+        updates += 1;
+        assert!(updates < 100, "Peers never agreed to start the round");
+    }
+}
+
+#[test]
+fn netplay_rounds_end_and_start_together() {
+    let (one, two) = LoopbackTransport::pair(LinkConditions {
+        latency: 3,
+        packet_loss: 0.2,
+        seed: 11,
+    });
+    let mut peers = [
+        HeadlessRunner::netplay(Netplay::new(one, Player::One, 5)),
+        HeadlessRunner::netplay(Netplay::new(two, Player::Two, 5)),
+    ];
+    for peer in peers.iter_mut() {
+        peer.world_mut()
+            .insert_resource(KeyboardBindings::default());
+    }
+
+    // The loser only has enough health for one hit. In the first round, player one's side
+    // sees the first punch hit before it learns that it was blocked
+    let rounds = [
+        (
+            Player::One,
+            vec![
+                (0, 0, KeyCode::J, ElementState::Pressed),
+                (3, 0, KeyCode::J, ElementState::Released),
+                (4, 1, KeyCode::Right, ElementState::Pressed),
+                (30, 1, KeyCode::Right, ElementState::Released),
+                (30, 0, KeyCode::D, ElementState::Pressed),
+                (50, 0, KeyCode::D, ElementState::Released),
+                (60, 0, KeyCode::J, ElementState::Pressed),
+                (63, 0, KeyCode::J, ElementState::Released),
+            ],
+        ),
+        (
+            Player::Two,
+            vec![
+                (0, 1, KeyCode::Numpad1, ElementState::Pressed),
+                (3, 1, KeyCode::Numpad1, ElementState::Released),
+            ],
+        ),
+    ];
+    for (round, (winner, keys)) in rounds.into_iter().enumerate() {
+        let (mut p1, mut p2) = (PlayerSetup::default(), PlayerSetup::default());
+        p1.position = Some(Vec3::new(-0.4, 0.0, 0.0));
+        p2.position = Some(Vec3::new(0.4, 0.0, 0.0));
+        match winner {
+            Player::One => p2.health = Some(10),
+            Player::Two => p1.health = Some(10),
+        }
+        start_netplay_round(
+            &mut peers,
+            &RoundSetup {
+                frame: None,
+                p1,
+                p2,
+            },
+        );
+        for peer in &peers {
+            assert_eq!(peer.world().resource::<Netplay>().round(), round + 1);
+            assert_eq!(peer.world().get_resource::<RoundResult>(), None);
+        }
+
+        let mut update = 0;
+        while peers.iter().any(HeadlessRunner::in_combat) {
+            for (_, index, key, state) in keys.iter().filter(|(at, _, _, _)| *at == update) {
+                send_key(&mut peers[*index], *key, *state);
+            }
+            for peer in peers.iter_mut() {
+                peer.step();
+            }
+
+            update += 1;
+            assert!(update < 300, "Round {} never ended", round + 1);
+        }
+
+        for peer in &peers {
+            assert_eq!(
+                peer.world().get_resource::<RoundResult>(),
+                Some(&RoundResult {
+                    winner: Some(winner)
+                })
+            );
+        }
+    }
+
+    let [one, two] = &peers;
+    assert_eq!(one.checksums().rounds.len(), 2);
+    assert_eq!(one.checksums().first_desync(two.checksums()), None);
+}
+
+#[test]
+fn netplay_peers_with_different_seeds_dont_start() {
+    let (one, two) = LoopbackTransport::pair(LinkConditions::default());
+    let mut peers = [
+        HeadlessRunner::netplay(Netplay::new(one, Player::One, 5)),
+        HeadlessRunner::netplay(Netplay::new(two, Player::Two, 6)),
+    ];
+    for peer in peers.iter_mut() {
+        peer.start_round();
+    }
+    for _ in 0..10 {
+        for peer in peers.iter_mut() {
+            peer.step();
+        }
+    }
+
+    for peer in &peers {
+        assert!(!peer.in_combat());
+        assert!(peer.world().resource::<Netplay>().mismatched());
+    }
+}
+
+#[test]
+fn same_seed_rolls_the_same_items() {
+    let dummy = CharacterId::Dummy.load(&game_assets()).unwrap();
+    let roll = |seed: u64| -> Vec<ItemId> {
+        dummy
+            .roll_items(3, &Inventory::default(), &mut GameRng::new(seed))
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
+    };
+
+    assert_eq!(roll(3), roll(3));
+}
+
+#[test]
+fn setup_replaces_default_spawn() {
+    let mut airborne = PlayerState::default();
+    airborne.jump();
+    let mut stunned = PlayerState::default();
+    stunned.stun(630);
+
+    let mut test_runner = TestRunner::from_setup(RoundSetup {
+        frame: Some(600),
+        p1: PlayerSetup {
+            character: None,
+            position: Some(Vec3::new(-1.0, 3.0, 0.0)),
+            // Jumping away, without crossing over
+            facing: Some(Facing::Left),
+            health: Some(10),
+            meter: Some(20),
+            charge: Some(40),
+            bullets: Some(0),
+            items: vec![ItemId::Gun],
+            state: Some(airborne),
+        },
+        p2: PlayerSetup {
+            state: Some(stunned),
+            ..default()
+        },
+    });
+
+    // Both runs start from the setup, not from where the first one left off
+    for case_name in ["Setup 1", "Setup 2"] {
+        let mut wrapper = test_runner.run(
+            case_name,
+            TestSpec::new(vec![InputClump::Idle(Duration::from_secs_f32(0.2))], vec![]),
+        );
+        let (p1, p2) = wrapper.get_players();
+
+        let frame = wrapper.world().resource::<Clock>().frame;
+        assert!(frame > 600 && frame < 630);
+
+        let (tf, facing, health, resources, inventory, state) = wrapper
+            .query::<(
+                &Transform,
+                &Facing,
+                &Health,
+                &Resources,
+                &Inventory,
+                &PlayerState,
+            )>()
+            .get(wrapper.world(), p1)
+            .unwrap();
+        assert!(tf.translation.y < 3.0);
+        assert_eq!(*facing, Facing::Left);
+        assert!(!state.is_grounded());
+        assert_eq!(health.get_percentage(), 10.0);
+        assert_eq!(resources.meter.get_percentage(), 20.0);
+        // Still there after the frames that rebuild it from the input parser
+        assert!(resources.charge.progress >= 40);
+        assert!(!resources.bullets.has_one());
+        assert!(inventory.contains(&ItemId::Gun));
+
+        let p2_state = wrapper
+            .query::<&PlayerState>()
+            .get(wrapper.world(), p2)
+            .unwrap();
+        assert_eq!(p2_state.unstun_frame(), Some(630));
+    }
+}
+
+#[test]
+fn punch_spawns_hitbox() {
+    TestRunner::new().run(
+        "Punch",
+        TestSpec::new(
+            vec![
+                // Players drop in from just above the ground
+                InputClump::Wait(2),
+                InputClump::InputStream("f"),
+                InputClump::Wait(3),
+                InputClump::Assert(Assertion::Move(Player::One, MoveId::new("Punch"))),
+                InputClump::Wait(6),
+                InputClump::Assert(Assertion::HitboxExists(Player::One)),
+                // Out of range from the default spawn
+                InputClump::Assert(Assertion::Health(Player::Two, 100)),
+                InputClump::Assert(Assertion::NotStunned(Player::Two)),
+            ],
+            vec![],
+        ),
+    );
+}
+
+#[test]
+fn handmedownken_with_item() {
+    let mut test_runner = TestRunner::from_setup(RoundSetup {
+        p1: PlayerSetup {
+            items: vec![ItemId::HandMeDownKen],
+            ..default()
+        },
+        ..default()
+    });
+
+    test_runner.run(
+        "HandMeDownKen",
+        TestSpec::new(
+            vec![
+                InputClump::InputStream("236e"),
+                InputClump::Wait(3),
+                InputClump::Assert(Assertion::Move(Player::One, MoveId::new("HandMeDownKen"))),
+                InputClump::Wait(40),
+                InputClump::Assert(Assertion::HitboxExists(Player::One)),
+            ],
+            vec![],
+        ),
+    );
+}
+
+#[test]
+fn counter_hits_depend_on_what_was_hit() {
+    // Close enough for a punch to land, with the dummy on the receiving end doing something
+    let setup = |state: PlayerState| RoundSetup {
+        frame: Some(100),
+        p1: PlayerSetup {
+            position: Some(Vec3::new(-0.4, 0.0, 0.0)),
+            meter: Some(0),
+            ..default()
+        },
+        p2: PlayerSetup {
+            position: Some(Vec3::new(0.4, 0.0, 0.0)),
+            state: Some(state),
+            ..default()
+        },
+    };
+    let punch = |meter_after_hit: i32| {
+        TestSpec::new(
+            vec![
+                InputClump::InputStream("f"),
+                InputClump::Wait(10),
+                InputClump::Assert(Assertion::Health(Player::Two, 90)),
+                InputClump::Assert(Assertion::Meter(Player::One, meter_after_hit)),
+            ],
+            vec![],
+        )
+    };
+
+    // Dashes never attack, so there is nothing to counter
+    TestRunner::from_setup(setup(doing("DashForward", 100))).run("Dash", punch(0));
+
+    // 15 frames in, so the last active frame is already over when the round starts
+    TestRunner::from_setup(setup(doing("Punch", 85))).run(
+        "Punch recovery",
+        punch(CounterType::PunishCounter.meter_reward()),
+    );
+}
+
+/// In the middle of a move that started on that clock frame
+fn doing(id: &'static str, start_frame: i32) -> PlayerState {
+    let mut state = PlayerState::default();
+    state.start_move(MoveSituation {
+        move_id: MoveId::new(id),
+        start_frame,
+        ..default()
+    });
+    state
+}
+
+/// The dummy as it is in the assets folder, with a longer windup on Shoot that has the property
+fn dummy_with_windup(property: &str) -> Character {
+    let definition = fs::read_to_string(game_assets().join(CharacterId::Dummy.path())).unwrap();
+    let (before, shoot) = definition.split_once("\"Shoot\": (").unwrap();
+    let windup = "kind: Animation,\n                        duration: 30,";
+    assert!(shoot.starts_with(&format!(
+        "\n            input: Some(\"e\"),\n            move_type: Normal,\n            phases: [\n                (\n                    default: Phase((\n                        {}",
+        windup
+    )), "Shoot has changed in the file");
+
+    let edited = shoot.replacen(
+        windup,
+        &format!("kind: Animation,\nduration: 200,\n{}", property),
+        1,
+    );
+    Character::from_ron(format!("{}\"Shoot\": ({}", before, edited).as_bytes()).unwrap()
+}
+
+/// Player one next to player two, who is in the windup with the property
+fn defending(property: &str) -> RoundSetup {
+    RoundSetup {
+        frame: Some(100),
+        p1: PlayerSetup {
+            position: Some(Vec3::new(-0.4, 0.0, 0.0)),
+            ..default()
+        },
+        p2: PlayerSetup {
+            character: Some(dummy_with_windup(property)),
+            position: Some(Vec3::new(0.4, 0.0, 0.0)),
+            state: Some(doing("Shoot", 100)),
+            ..default()
+        },
+    }
+}
+
+#[test]
+fn invulnerability_avoids_hits() {
+    let punch = |health: i32| {
+        TestSpec::new(
+            vec![
+                InputClump::InputStream("f"),
+                InputClump::Wait(10),
+                InputClump::Assert(Assertion::Health(Player::Two, health)),
+            ],
+            vec![],
+        )
+    };
+
+    TestRunner::from_setup(defending("")).run("Vulnerable", punch(90));
+    TestRunner::from_setup(defending("invulnerability: Some(Strike),"))
+        .run("Strike invulnerable", punch(100));
+    TestRunner::from_setup(defending("invulnerability: Some(Throw),"))
+        .run("Throw invulnerable", punch(90));
+}
+
+#[test]
+fn throw_invulnerability_avoids_grabs() {
+    let grab = |health: i32| {
+        TestSpec::new(
+            vec![
+                InputClump::InputStream("g"),
+                InputClump::Wait(20),
+                InputClump::Assert(Assertion::Health(Player::Two, health)),
+            ],
+            vec![],
+        )
+    };
+
+    TestRunner::from_setup(defending("")).run("Grabbable", grab(75));
+    TestRunner::from_setup(defending("invulnerability: Some(Throw),"))
+        .run("Throw invulnerable", grab(100));
+    TestRunner::from_setup(defending("invulnerability: Some(Strike),"))
+        .run("Strike invulnerable", grab(75));
+}
+
+#[test]
+fn armor_breaks_after_its_hits() {
+    TestRunner::from_setup(defending("armor: Some((hits: 1)),")).run(
+        "Armor",
+        TestSpec::new(
+            vec![
+                InputClump::InputStream("f"),
+                InputClump::Wait(10),
+                // Damage goes through, stun doesn't
+                InputClump::Assert(Assertion::Health(Player::Two, 90)),
+                InputClump::Assert(Assertion::NotStunned(Player::Two)),
+                InputClump::Wait(20),
+                InputClump::InputStream("f"),
+                InputClump::Wait(10),
+                InputClump::Assert(Assertion::Health(Player::Two, 80)),
+                InputClump::Assert(Assertion::Stunned(Player::Two)),
+            ],
+            vec![],
+        ),
+    );
+}
+
+#[test]
+fn recoverable_damage_comes_back() {
+    TestRunner::from_setup(defending("armor: Some((hits: 1, recoverable: true)),")).run(
+        "Recoverable",
+        TestSpec::new(
+            vec![
+                InputClump::InputStream("f"),
+                InputClump::Wait(10),
+                // Took 10, the first point is back already
+                InputClump::Assert(Assertion::Health(Player::Two, 91)),
+                // The rest come back a point every 10 frames
+                InputClump::Wait(90),
+                InputClump::Assert(Assertion::Health(Player::Two, 100)),
+            ],
+            vec![],
+        ),
+    );
+}
+
+/// The dummy as it is in the assets folder, with Punch on a different input
+fn dummy_with_punch_on(input: &str) -> Character {
+    let definition = fs::read_to_string(game_assets().join(CharacterId::Dummy.path())).unwrap();
+    let punch = "\"Punch\": (\n            input: Some(\"f\"),";
+    assert!(definition.contains(punch), "Punch has moved in the file");
+
+    let edited = punch.replace("\"f\"", &format!("\"{}\"", input));
+    Character::from_ron(definition.replace(punch, &edited).as_bytes()).unwrap()
+}
+
+/// Round in progress with both players on the ground,
+/// player one presses the buttons that many frames from now
+fn reload_runner(buttons: &[(usize, char)]) -> (HeadlessRunner, Entity) {
+    let mut events = vec![
+        None;
+        buttons
+            .iter()
+            .map(|(frame, _)| frame + 1)
+            .max()
+            .unwrap_or(0)
+    ];
+    for (frame, button) in buttons {
+        events[*frame] = Some(InputEvent::try_from(*button).unwrap());
+    }
+
+    let mut runner = HeadlessRunner::new();
+    runner.start_round();
+    runner.run(2);
+    runner.set_inputs(Player::One, events).unwrap();
+    let p1 = runner.world().resource::<Players>().one;
+    (runner, p1)
+}
+
+fn check(runner: &mut HeadlessRunner, assertion: Assertion) {
+    if let Err(actual) = assertion.check(runner.world_mut()) {
+        panic!("Expected {}, but {}", assertion, actual);
+    }
+}
+
+#[test]
+fn reloaded_definition_is_used_for_the_next_move() {
+    let edited = dummy_with_punch_on("s");
+    let (mut runner, p1) = reload_runner(&[(5, 's')]);
+
+    runner
+        .world_mut()
+        .entity_mut(p1)
+        .insert(PendingDefinition::new(edited.clone()).unwrap());
+    runner.run(1);
+    assert_eq!(runner.world().get::<Character>(p1), Some(&edited));
+
+    runner.run(8);
+    check(
+        &mut runner,
+        Assertion::Move(Player::One, MoveId::new("Punch")),
+    );
+
+    // Playback makes the same swap on the same frame, and checksums tell the definitions apart
+    let reloads = &runner.world().resource::<ReplayRecorder>().replay.rounds[0]
+        .one
+        .reloads;
+    assert_eq!(reloads.len(), 1);
+    assert_eq!(reloads[0].1, edited);
+
+    let frames = &runner.checksums().rounds[0];
+    assert_ne!(
+        frames[0].one.character,
+        frames.last().unwrap().one.character
+    );
+}
+
+#[test]
+fn reload_waits_for_the_move_to_end() {
+    let original = CharacterId::Dummy.load(&game_assets()).unwrap();
+    let edited = dummy_with_punch_on("s");
+    let (mut runner, p1) = reload_runner(&[(0, 'f')]);
+
+    runner.run(4);
+    check(
+        &mut runner,
+        Assertion::Move(Player::One, MoveId::new("Punch")),
+    );
+    runner
+        .world_mut()
+        .entity_mut(p1)
+        .insert(PendingDefinition::new(edited.clone()).unwrap());
+
+    // Punch plays out as it was defined when it started
+    runner.run(6);
+    check(&mut runner, Assertion::HitboxExists(Player::One));
+    assert_eq!(runner.world().get::<Character>(p1), Some(&original));
+
+    runner.run(20);
+    assert!(runner
+        .world()
+        .get::<PlayerState>(p1)
+        .unwrap()
+        .get_move_state()
+        .is_none());
+    assert_eq!(runner.world().get::<Character>(p1), Some(&edited));
+}
+
+/// The dummy as it is in the assets folder, with the Punch hitbox growing a few frames in
+fn dummy_with_growing_punch() -> Character {
+    let definition = fs::read_to_string(game_assets().join(CharacterId::Dummy.path())).unwrap();
+    let (before, punch) = definition.split_once("\"Punch\": (").unwrap();
+    let lifetime = "lifetime: Phase,";
+    assert!(
+        punch.contains(lifetime),
+        "Punch hitbox has changed in the file"
+    );
+
+    let keyframes =
+        "keyframes: [(frame: 3, hitbox: [(center: (0.6, 1.2), width: 0.5, height: 0.2)])],";
+    let edited = punch.replacen(lifetime, &format!("{}\n{}", keyframes, lifetime), 1);
+    Character::from_ron(format!("{}\"Punch\": ({}", before, edited).as_bytes()).unwrap()
+}
+
+#[test]
+fn hitbox_changes_shape_on_keyframe() {
+    let (mut runner, p1) = reload_runner(&[(2, 'f')]);
+    runner
+        .world_mut()
+        .entity_mut(p1)
+        .insert(PendingDefinition::new(dummy_with_growing_punch()).unwrap());
+
+    let hitbox = |runner: &mut HeadlessRunner| {
+        runner
+            .world_mut()
+            .query::<(&Hitbox, &Owner)>()
+            .iter(runner.world())
+            .find(|(_, owner)| owner.0 == Player::One)
+            .map(|(hitbox, _)| hitbox.clone())
+    };
+
+    let mut frames = 0;
+    while hitbox(&mut runner).is_none() {
+        runner.run(1);
+        frames += 1;
+        assert!(frames < 20, "Punch hitbox never came out");
+    }
+    let original = hitbox(&mut runner).unwrap();
+
+    runner.run(2);
+    assert_eq!(hitbox(&mut runner), Some(original.clone()));
+    let before_keyframe = Snapshot::capture(runner.world_mut());
+
+    runner.run(1);
+    let grown = hitbox(&mut runner).unwrap();
+    assert!(grown.bounds().width() > original.bounds().width());
+    let after_keyframe = Snapshot::capture(runner.world_mut());
+
+    // Keyframes are counted from when the hitbox came out, not from when it was restored
+    before_keyframe.restore(runner.world_mut());
+    assert_eq!(hitbox(&mut runner), Some(original));
+    runner.run(1);
+    assert_eq!(hitbox(&mut runner), Some(grown.clone()));
+
+    runner.run(1);
+    after_keyframe.restore(runner.world_mut());
+    assert_eq!(hitbox(&mut runner), Some(grown));
+}
+
+#[test]
+fn stun_from_setup_wears_off() {
+    let mut stunned = PlayerState::default();
+    stunned.stun(30);
+
+    let mut test_runner = TestRunner::from_setup(RoundSetup {
+        p2: PlayerSetup {
+            state: Some(stunned),
+            ..default()
+        },
+        ..default()
+    });
+
+    test_runner.run(
+        "Stun",
+        TestSpec::new(
+            vec![],
+            vec![
+                InputClump::Wait(20),
+                InputClump::Assert(Assertion::Stunned(Player::Two)),
+                InputClump::Wait(20),
+                InputClump::Assert(Assertion::NotStunned(Player::Two)),
+            ],
+        ),
+    );
+}
+
+#[test]
+#[should_panic(
+    expected = "failed on frame 5: expected player Two health is 50, but health was 100"
+)]
+fn failed_assertion_reports_frame() {
+    TestRunner::new().run(
+        "Failing",
+        TestSpec::new(
+            vec![
+                InputClump::Wait(5),
+                InputClump::Assert(Assertion::Health(Player::Two, 50)),
+            ],
+            vec![],
+        ),
+    );
+}
@@ -0,0 +1,168 @@
+use std::path::Path;
+
+use bevy::{prelude::*, utils::HashMap};
+use characters::{Character, CharacterId, CharacterLoadErrors};
+use types::Player;
+
+use crate::{
+    netplay::Netplay, player::PendingDefinition, replay::ReplayPlayback, ui::Notifications,
+};
+
+/// Definition files from the assets folder, by the character they define
+#[derive(Debug, Deref, DerefMut)]
+pub struct CharacterDefinitions(pub HashMap<CharacterId, Handle<Character>>);
+
+/// Players spawn with what the files held at startup.
+/// This queues the files once they are loaded, and again every time they are edited.
+/// Replays use the definitions they were recorded with instead, and online matches keep theirs
+#[allow(clippy::too_many_arguments)]
+pub fn queue_definitions(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<Character>>,
+    definitions: Res<CharacterDefinitions>,
+    assets: Res<Assets<Character>>,
+    errors: Res<CharacterLoadErrors>,
+    mut notifications: ResMut<Notifications>,
+    players: Query<(Entity, &CharacterId, &Player, &Character)>,
+    playback: Option<Res<ReplayPlayback>>,
+    netplay: Option<Res<Netplay>>,
+) {
+    // The peer would keep using the old definition
+    if playback.is_some() || netplay.is_some() {
+        return;
+    }
+
+    for (path, error) in errors.drain() {
+        for (_, id, player, _) in players.iter() {
+            if Path::new(id.path()) == path {
+                notifications.add(*player, format!("{:?} not reloaded: {}", id, error));
+            }
+        }
+    }
+
+    for event in events.iter() {
+        let handle = match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => handle,
+            AssetEvent::Removed { .. } => continue,
+        };
+
+        if let Some(loaded) = assets.get(handle) {
+            for (entity, id, player, current) in players.iter() {
+                if definitions.get(id) != Some(handle) {
+                    continue;
+                }
+
+                // Like the first load at startup, or an edit that was undone before the swap
+                if loaded == current {
+                    commands.entity(entity).remove::<PendingDefinition>();
+                    continue;
+                }
+
+                match PendingDefinition::new(loaded.clone()) {
+                    Ok(pending) => {
+                        commands.entity(entity).insert(pending);
+                    }
+                    Err(error) => {
+                        notifications.add(*player, format!("{:?} not reloaded: {}", id, error));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::asset::{AssetPlugin, AssetServerSettings, FileAssetIo};
+    use characters::CharacterLoader;
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+    use crate::assets::loaders;
+
+    /// Loads the definitions from the folder, with player one as the dummy from the game assets
+    fn app(asset_folder: &Path) -> (App, Entity) {
+        let errors = CharacterLoadErrors::default();
+        let mut app = App::new();
+        app.insert_resource(AssetServerSettings {
+            asset_folder: asset_folder.to_str().unwrap().to_owned(),
+            ..default()
+        })
+        .add_plugins(MinimalPlugins)
+        .add_plugin(AssetPlugin)
+        .add_asset::<Character>()
+        .add_asset_loader(CharacterLoader::new(errors.clone()))
+        .insert_resource(errors)
+        .insert_resource(Notifications::new(Entity::from_raw(0), Entity::from_raw(0)))
+        .add_startup_system(loaders::characters)
+        .add_system(queue_definitions);
+
+        let character = CharacterId::Dummy.load(&game_assets()).unwrap();
+        let player = app
+            .world
+            .spawn()
+            .insert_bundle((CharacterId::Dummy, Player::One, character))
+            .id();
+        (app, player)
+    }
+
+    fn game_assets() -> PathBuf {
+        FileAssetIo::get_root_path().join("../main/assets")
+    }
+
+    /// Loading happens on another thread, so it takes a few updates
+    fn update_until(app: &mut App, done: impl Fn(&World) -> bool) {
+        for _ in 0..500 {
+            app.update();
+            if done(&app.world) {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        panic!("Definitions never loaded");
+    }
+
+    #[test]
+    fn unchanged_definition_is_not_queued() {
+        let (mut app, p1) = app(&game_assets());
+        update_until(&mut app, |world| {
+            world
+                .get_resource::<CharacterDefinitions>()
+                .map_or(false, |definitions| {
+                    let handle = &definitions[&CharacterId::Dummy];
+                    world.resource::<Assets<Character>>().contains(handle)
+                })
+        });
+        let handle = app.world.resource::<CharacterDefinitions>()[&CharacterId::Dummy].clone();
+        // Events from the load are read on the update after it
+        app.update();
+        assert!(app.world.get::<PendingDefinition>(p1).is_none());
+
+        let mut edited = app.world.get::<Character>(p1).unwrap().clone();
+        edited.low_block_height += 0.1;
+        app.world
+            .resource_mut::<Assets<Character>>()
+            .set_untracked(handle, edited);
+        // Sent at the end of the first update, read on the second
+        app.update();
+        app.update();
+        assert!(app.world.get::<PendingDefinition>(p1).is_some());
+    }
+
+    #[test]
+    fn invalid_reload_keeps_the_last_good_definition() {
+        let folder = std::env::temp_dir().join("wag_invalid_definition");
+        let path = folder.join(CharacterId::Dummy.path());
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "(moves: {").unwrap();
+
+        let (mut app, p1) = app(&folder);
+        let original = app.world.get::<Character>(p1).unwrap().clone();
+        update_until(&mut app, |world| {
+            world.resource::<Notifications>().queued() > 0
+        });
+
+        assert!(app.world.get::<PendingDefinition>(p1).is_none());
+        assert_eq!(app.world.get::<Character>(p1), Some(&original));
+    }
+}
@@ -2,13 +2,14 @@ use bevy::prelude::*;
 use bevy_hanabi::*;
 use map_macro::map;
 
+use characters::CharacterId;
 use types::VisualEffect;
 
 use super::{
     animations::animation_paths,
     models::model_paths,
     sounds::{get_sound_paths, Sounds},
-    Animations, Colors, Fonts, Models, Particles, Sprites,
+    Animations, CharacterDefinitions, Colors, Fonts, Models, Particles, Sprites,
 };
 
 pub fn colors(mut commands: Commands) {
@@ -61,6 +62,15 @@ pub fn animations(mut commands: Commands, asset_server: Res<AssetServer>) {
     ));
 }
 
+pub fn characters(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(CharacterDefinitions(
+        CharacterId::ALL
+            .into_iter()
+            .map(|id| (id, asset_server.load(id.path())))
+            .collect(),
+    ));
+}
+
 pub fn sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.insert_resource(Sounds::new(
         get_sound_paths()
@@ -1,12 +1,19 @@
-use bevy::prelude::*;
+use bevy::{
+    asset::{AssetServerSettings, FileAssetIo},
+    prelude::*,
+};
+use characters::{Character, CharacterLoadErrors, CharacterLoader};
+use std::path::PathBuf;
 
 mod animations;
+mod character_definitions;
 mod loaders;
 mod models;
 mod particles;
 mod sounds;
 
 pub use animations::{AnimationHelper, AnimationHelperSetup, Animations};
+use character_definitions::CharacterDefinitions;
 pub use models::{ModelRequest, Models};
 pub use particles::{ParticleRequest, Particles};
 pub use sounds::Sounds;
@@ -32,22 +39,37 @@ pub struct Sprites {
     pub background_image: Handle<Image>,
 }
 
+/// Where the asset files are on disk, players are spawned with the character definitions from here.
+/// The asset server sets this up for the game, runs without one have to insert it themselves
+#[derive(Debug, Clone)]
+pub struct AssetFolder(pub PathBuf);
+
 pub struct AssetsPlugin;
 
 impl Plugin for AssetsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system_to_stage(StartupStage::PreStartup, loaders::colors)
+        let character_errors = CharacterLoadErrors::default();
+        let asset_folder = FileAssetIo::get_root_path()
+            .join(&app.world.resource::<AssetServerSettings>().asset_folder);
+
+        app.add_asset::<Character>()
+            .add_asset_loader(CharacterLoader::new(character_errors.clone()))
+            .insert_resource(character_errors)
+            .insert_resource(AssetFolder(asset_folder))
+            .add_startup_system_to_stage(StartupStage::PreStartup, loaders::colors)
             .add_startup_system_to_stage(StartupStage::PreStartup, loaders::fonts)
             .add_startup_system_to_stage(StartupStage::PreStartup, loaders::sprites)
             .add_startup_system_to_stage(StartupStage::PreStartup, loaders::models)
             .add_startup_system_to_stage(StartupStage::PreStartup, loaders::animations)
             .add_startup_system_to_stage(StartupStage::PreStartup, loaders::sounds)
             .add_startup_system_to_stage(StartupStage::PreStartup, loaders::particles)
+            .add_startup_system_to_stage(StartupStage::PreStartup, loaders::characters)
             .add_system(models::model_spawner)
             .add_system(animations::setup_helpers)
             .add_system(animations::update_animation)
             .add_system(animations::mirror_after_load)
             .add_system(sounds::play_queued)
-            .add_system(particles::handle_requests);
+            .add_system(particles::handle_requests)
+            .add_system(character_definitions::queue_definitions);
     }
 }
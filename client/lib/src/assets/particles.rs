@@ -29,6 +29,21 @@ impl Particles {
     pub fn spawn(&mut self, request: ParticleRequest) {
         self.queue.push(request);
     }
+
+    /// For when there is nothing to show the particles with
+    pub fn discard_requests(&mut self) {
+        self.queue.clear();
+    }
+
+    /// How many particles are waiting to be spawned
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Drops the requests that were made after the first ones
+    pub fn truncate_queue(&mut self, queued: usize) {
+        self.queue.truncate(queued);
+    }
 }
 
 pub fn handle_requests(
@@ -4,24 +4,39 @@ use rand::prelude::*;
 
 use types::SoundEffect;
 
+use crate::rng::CosmeticRng;
+
 pub struct Sounds {
     handles: HashMap<SoundEffect, Vec<Handle<AudioSource>>>,
     queue: Vec<Handle<AudioSource>>,
+    /// Which variant plays doesn't matter to the game
+    rng: CosmeticRng,
 }
 impl Sounds {
     pub fn new(handles: HashMap<SoundEffect, Vec<Handle<AudioSource>>>) -> Sounds {
         Sounds {
             handles,
             queue: vec![],
+            rng: CosmeticRng::default(),
         }
     }
 
     pub fn play(&mut self, key: SoundEffect) {
         if let Some(clips) = self.handles.get(&key) {
-            let clip = clips[rand::thread_rng().gen_range(0..clips.len())].clone();
+            let clip = clips[self.rng.gen_range(0..clips.len())].clone();
             self.queue.push(clip);
         }
     }
+
+    /// How many sounds are waiting to be played
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Drops the sounds that were queued after the first ones
+    pub fn truncate_queue(&mut self, queued: usize) {
+        self.queue.truncate(queued);
+    }
 }
 
 pub fn play_queued(mut sounds: ResMut<Sounds>, audio: Option<Res<Audio>>) {
@@ -51,6 +66,14 @@ pub fn get_sound_paths() -> HashMap<SoundEffect, Vec<&'static str>> {
             SoundEffect::Clash,
             vec!["sound_effects/clink1.ogg", "sound_effects/clink2.ogg"],
         ),
+        (
+            SoundEffect::CounterHit,
+            vec!["sound_effects/counter_hit.wav"],
+        ),
+        (
+            SoundEffect::PunishCounter,
+            vec!["sound_effects/punish_counter.wav"],
+        ),
     ]
     .into_iter()
     .collect()
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 use bevy::render::camera::{Camera2d, ScalingMode};
+use time::not_in_combat;
 use types::Player;
 
 use crate::physics::ARENA_WIDTH;
@@ -14,15 +15,22 @@ pub struct CustomCameraPlugin;
 
 impl Plugin for CustomCameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(add_cameras)
-            .add_system_to_stage(CoreStage::PostUpdate, center_camera);
+        // During rounds the camera moves with the combat frames, see physics
+        app.add_startup_system(add_cameras).add_system_to_stage(
+            CoreStage::PostUpdate,
+            center_camera.with_run_criteria(not_in_combat),
+        );
     }
 }
 
+fn camera_start() -> Transform {
+    Transform::from_xyz(0.0, 1.5, 10.0)
+}
+
 fn add_cameras(mut commands: Commands) {
     commands
         .spawn_bundle(OrthographicCameraBundle {
-            transform: Transform::from_xyz(0.0, 1.5, 10.0),
+            transform: camera_start(),
             orthographic_projection: OrthographicProjection {
                 scaling_mode: ScalingMode::FixedHorizontal,
                 scale: 4.0,
@@ -36,8 +44,15 @@ fn add_cameras(mut commands: Commands) {
     commands.spawn_bundle(UiCameraBundle::default());
 }
 
+/// Stage walls follow the camera, so it has to exist and move even when nothing is rendered
+pub(crate) fn add_headless_camera(mut commands: Commands) {
+    commands
+        .spawn_bundle(TransformBundle::from_transform(camera_start()))
+        .insert(WorldCamera);
+}
+
 #[allow(clippy::type_complexity)]
-fn center_camera(
+pub(crate) fn center_camera(
     mut queryies: ParamSet<(
         Query<&Transform, With<Player>>,
         Query<&mut Transform, With<WorldCamera>>,
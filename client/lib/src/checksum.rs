@@ -0,0 +1,319 @@
+use bevy::{prelude::*, utils::HashMap};
+use characters::{Character, HitTracker, Hitbox, OnHitEffect, Resources};
+use player_state::PlayerState;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, fs, io, path::Path, path::PathBuf};
+use time::{Clock, CombatApp, CombatStage, GameState};
+use types::{Owner, Player};
+
+use crate::{
+    damage::Health,
+    physics::{ConstantVelocity, PlayerVelocity},
+    rng::GameRng,
+};
+
+/// Part of the state that has its own checksum, to narrow down where a desync came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChecksumPart {
+    Position,
+    Velocity,
+    Health,
+    Resources,
+    State,
+    Character,
+    Hitboxes,
+    Rng,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerChecksum {
+    pub position: u64,
+    pub velocity: u64,
+    pub health: u64,
+    pub resources: u64,
+    pub state: u64,
+    /// Definition the player is using, changes when one is reloaded
+    #[serde(default)]
+    pub character: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrameChecksum {
+    pub frame: usize,
+    pub one: PlayerChecksum,
+    pub two: PlayerChecksum,
+    pub hitboxes: u64,
+    #[serde(default)]
+    pub rng: u64,
+}
+impl FrameChecksum {
+    fn get(&self, player: Player) -> &PlayerChecksum {
+        match player {
+            Player::One => &self.one,
+            Player::Two => &self.two,
+        }
+    }
+
+    /// First part that differs, player is None for parts that are shared
+    fn difference(&self, other: &Self) -> Option<(Option<Player>, ChecksumPart)> {
+        for player in [Player::One, Player::Two] {
+            let (own, others) = (self.get(player), other.get(player));
+            for (part, a, b) in [
+                (ChecksumPart::Position, own.position, others.position),
+                (ChecksumPart::Velocity, own.velocity, others.velocity),
+                (ChecksumPart::Health, own.health, others.health),
+                (ChecksumPart::Resources, own.resources, others.resources),
+                (ChecksumPart::State, own.state, others.state),
+                (ChecksumPart::Character, own.character, others.character),
+            ] {
+                if a != b {
+                    return Some((Some(player), part));
+                }
+            }
+        }
+
+        if self.hitboxes != other.hitboxes {
+            return Some((None, ChecksumPart::Hitboxes));
+        }
+        if self.rng != other.rng {
+            return Some((None, ChecksumPart::Rng));
+        }
+        None
+    }
+}
+
+/// Where two checksum logs first went different ways
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Desync {
+    pub round: usize,
+    pub frame: usize,
+    pub player: Option<Player>,
+    /// None if the logs have different frames or rounds, like when one round ended early
+    pub part: Option<ChecksumPart>,
+}
+impl Display for Desync {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Desync in round {} on frame {}", self.round, self.frame)?;
+        match (self.player, self.part) {
+            (Some(player), Some(part)) => write!(f, ": player {} {:?}", player, part),
+            (None, Some(part)) => write!(f, ": {:?}", part),
+            (_, None) => write!(f, ": frames don't line up"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ChecksumLogError {
+    Io(io::Error),
+    Parse(ron::Error),
+}
+impl Display for ChecksumLogError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChecksumLogError::Io(error) => write!(f, "{}", error),
+            ChecksumLogError::Parse(error) => write!(f, "Invalid checksum log: {}", error),
+        }
+    }
+}
+impl std::error::Error for ChecksumLogError {}
+
+/// Checksums of every combat frame, by round
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChecksumLog {
+    pub rounds: Vec<Vec<FrameChecksum>>,
+}
+impl ChecksumLog {
+    pub fn start_round(&mut self) {
+        self.rounds.push(vec![]);
+    }
+
+    /// A frame that was already recorded is replaced along with everything after it, like after a rollback
+    pub fn record(&mut self, checksum: FrameChecksum) {
+        if self.rounds.is_empty() {
+            self.start_round();
+        }
+
+        let round = self.rounds.last_mut().unwrap();
+        round.retain(|recorded| recorded.frame < checksum.frame);
+        round.push(checksum);
+    }
+
+    pub fn first_desync(&self, other: &ChecksumLog) -> Option<Desync> {
+        for (round, (own, others)) in self.rounds.iter().zip(other.rounds.iter()).enumerate() {
+            for (a, b) in own.iter().zip(others.iter()) {
+                if a.frame != b.frame {
+                    return Some(Desync {
+                        round,
+                        frame: a.frame.min(b.frame),
+                        player: None,
+                        part: None,
+                    });
+                }
+
+                if let Some((player, part)) = a.difference(b) {
+                    return Some(Desync {
+                        round,
+                        frame: a.frame,
+                        player,
+                        part: Some(part),
+                    });
+                }
+            }
+
+            // One of the logs went on after the other one stopped
+            if let Some(extra) = own.get(others.len()).or_else(|| others.get(own.len())) {
+                return Some(Desync {
+                    round,
+                    frame: extra.frame,
+                    player: None,
+                    part: None,
+                });
+            }
+        }
+
+        let shared_rounds = self.rounds.len().min(other.rounds.len());
+        self.rounds
+            .get(shared_rounds)
+            .or_else(|| other.rounds.get(shared_rounds))
+            .map(|extra| Desync {
+                round: shared_rounds,
+                frame: extra.first().map_or(0, |checksum| checksum.frame),
+                player: None,
+                part: None,
+            })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ChecksumLogError> {
+        let contents = fs::read_to_string(path).map_err(ChecksumLogError::Io)?;
+        ron::from_str(&contents).map_err(ChecksumLogError::Parse)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ChecksumLogError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(ChecksumLogError::Io)?;
+        }
+        let contents = ron::to_string(self).map_err(ChecksumLogError::Parse)?;
+        fs::write(path, contents).map_err(ChecksumLogError::Io)
+    }
+}
+
+/// If present, the checksum log gets written here after every round
+pub struct ChecksumOutput(pub PathBuf);
+
+pub struct ChecksumPlugin;
+
+impl Plugin for ChecksumPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChecksumLog>()
+            .add_system_to_stage(CoreStage::PreUpdate, round_transitions)
+            .add_combat_system(
+                CombatStage::FrameEnd,
+                record_checksum.with_run_criteria(State::on_update(GameState::Combat)),
+            );
+    }
+}
+
+fn round_transitions(
+    state: Res<State<GameState>>,
+    output: Option<Res<ChecksumOutput>>,
+    mut log: ResMut<ChecksumLog>,
+    mut previous_state: Local<Option<GameState>>,
+) {
+    // State drivers mutate the resource several times per transition
+    let current = *state.current();
+    if previous_state.replace(current) == Some(current) {
+        return;
+    }
+
+    match current {
+        GameState::Combat => log.start_round(),
+        GameState::Shop => {
+            if let Some(output) = output {
+                if !log.rounds.is_empty() {
+                    if let Err(error) = log.save(&output.0) {
+                        println!("Failed to save checksums to {:?}: {}", output.0, error);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Stable across runs and machines, unlike the std hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+pub(crate) fn checksum(value: &impl Serialize) -> u64 {
+    fnv1a(&bincode::serialize(value).expect("Failed to serialize state for checksum"))
+}
+
+#[allow(clippy::type_complexity)]
+fn record_checksum(
+    clock: Res<Clock>,
+    rng: Res<GameRng>,
+    mut log: ResMut<ChecksumLog>,
+    players: Query<(
+        &Player,
+        &Transform,
+        &PlayerVelocity,
+        &Health,
+        &Resources,
+        &PlayerState,
+        &Character,
+        ChangeTrackers<Character>,
+    )>,
+    hitboxes: Query<(
+        &Owner,
+        // Local, attached hitboxes are relative to the owner
+        &Transform,
+        &Hitbox,
+        &OnHitEffect,
+        &HitTracker,
+        &ConstantVelocity,
+    )>,
+    // Definitions are big and rarely change, so they are only hashed when they do
+    mut character_checksums: Local<HashMap<Player, u64>>,
+) {
+    let mut frame_checksum = FrameChecksum {
+        frame: clock.frame,
+        rng: checksum(&*rng),
+        ..default()
+    };
+
+    for (player, tf, velocity, health, resources, state, character, character_tracker) in
+        players.iter()
+    {
+        if character_tracker.is_changed() || !character_checksums.contains_key(player) {
+            character_checksums.insert(*player, checksum(character));
+        }
+
+        let player_checksum = PlayerChecksum {
+            position: checksum(&tf.translation),
+            velocity: checksum(velocity),
+            health: checksum(health),
+            resources: checksum(resources),
+            state: checksum(state),
+            character: character_checksums[player],
+        };
+        match player {
+            Player::One => frame_checksum.one = player_checksum,
+            Player::Two => frame_checksum.two = player_checksum,
+        }
+    }
+
+    // Entities and query order differ between runs, so hitboxes are sorted by content
+    let mut hitbox_bytes: Vec<Vec<u8>> = hitboxes
+        .iter()
+        .map(|(owner, tf, hitbox, effect, tracker, velocity)| {
+            bincode::serialize(&(**owner, tf.translation, hitbox, effect, tracker, velocity))
+                .expect("Failed to serialize hitbox for checksum")
+        })
+        .collect();
+    hitbox_bytes.sort();
+    frame_checksum.hitboxes = fnv1a(&hitbox_bytes.concat());
+
+    log.record(frame_checksum);
+}
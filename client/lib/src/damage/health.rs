@@ -1,23 +1,37 @@
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
+use player_state::PlayerState;
 use time::{Clock, GameState, RoundResult};
 use types::Player;
 
-#[derive(Inspectable, Component, Clone, Copy)]
+use crate::netplay::Netplay;
+
+/// How often a point of recoverable health comes back
+const FRAMES_PER_RECOVERED_POINT: usize = 10;
+
+#[derive(Inspectable, Component, Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Health {
     value: i32,
     max: i32,
+    /// Part of the missing health that comes back over time, until a hit gets through
+    recoverable: i32,
 }
 impl Default for Health {
     fn default() -> Self {
         Self {
             value: 100,
             max: 100,
+            recoverable: 0,
         }
     }
 }
 impl Health {
+    pub fn get(&self) -> i32 {
+        self.value
+    }
+
     pub fn get_percentage(&self) -> f32 {
         (self.value as f32 / self.max as f32) * 100.0
     }
@@ -26,16 +40,42 @@ impl Health {
         *self = Health::default();
     }
 
+    pub fn set(&mut self, value: i32) {
+        self.value = value.min(self.max);
+    }
+
     pub fn apply_damage(&mut self, amount: i32) {
         self.value -= amount;
+        self.recoverable = 0;
+    }
+
+    /// Can't take the last point of health
+    pub fn apply_recoverable_damage(&mut self, amount: i32) {
+        let amount = amount.min(self.value - 1).max(0);
+        self.value -= amount;
+        self.recoverable += amount;
+    }
+}
+
+pub fn recover(clock: Res<Clock>, mut query: Query<(&mut Health, &PlayerState)>) {
+    if clock.frame % FRAMES_PER_RECOVERED_POINT != 0 {
+        return;
+    }
+
+    for (mut health, state) in query.iter_mut() {
+        if health.recoverable > 0 && !state.stunned() {
+            health.value += 1;
+            health.recoverable -= 1;
+        }
     }
 }
 
+/// Only records the result, so the frame it happened on can still be rolled back
 pub fn check_dead(
     mut commands: Commands,
     clock: Res<Clock>,
     query: Query<(&Health, &Player)>,
-    mut state: ResMut<State<GameState>>,
+    result: Option<Res<RoundResult>>,
 ) {
     let living_players: Vec<Player> = query
         .iter()
@@ -50,7 +90,7 @@ pub fn check_dead(
 
     let round_over = living_players.len() != 2 || clock.time_out();
 
-    if *state.current() == GameState::Combat && round_over {
+    if result.is_none() && round_over {
         commands.insert_resource(if living_players.len() == 1 {
             RoundResult {
                 winner: Some(living_players[0]),
@@ -58,7 +98,16 @@ pub fn check_dead(
         } else {
             RoundResult { winner: None }
         });
+    }
+}
 
+/// Online, the round only ends once both peers know every input up to the end
+pub fn end_round(
+    result: Option<Res<RoundResult>>,
+    netplay: Option<Res<Netplay>>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if result.is_some() && netplay.is_none() && *state.current() == GameState::Combat {
         state.set(GameState::Shop).unwrap();
     }
 }
@@ -1,12 +1,13 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
 
 use characters::{HitTracker, Hitbox, Lifetime, OnHitEffect, SpawnDescriptor};
 use time::Clock;
-use types::{Area, Facing, Owner, Player};
+use types::{Facing, Owner, Player};
 
 use crate::physics::ConstantVelocity;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 enum DespawnTime {
     Frame(usize),
     StateChange,
@@ -14,13 +15,20 @@ enum DespawnTime {
     EndOfRound,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct DespawnRequest {
     entity: Entity,
     time: DespawnTime,
 }
 
-#[derive(Default, Component)]
+/// Shapes the hitbox changes into, by frames since it came out
+#[derive(Debug, Default, Clone, Component, Serialize, Deserialize)]
+pub struct HitboxKeyframes {
+    spawn_frame: usize,
+    keyframes: Vec<(usize, Hitbox)>,
+}
+
+#[derive(Debug, Default, Clone, Component, Serialize, Deserialize)]
 pub struct HitboxSpawner {
     queue: Vec<SpawnDescriptor>,
     despawn_requests: Vec<DespawnRequest>,
@@ -37,7 +45,11 @@ impl HitboxSpawner {
         player: Player,
         parent_position: Vec3,
     ) {
-        let offset = facing.mirror_vec(descriptor.hitbox.center().extend(0.0));
+        // Entity is at the center of the whole hitbox, the parts are positioned relative to that
+        let center = descriptor.hitbox.bounds().center();
+        let relative = |hitbox: &Hitbox| Hitbox(hitbox.with_offset(-center).mirrored(facing));
+
+        let offset = facing.mirror_vec(center.extend(0.0));
         let absolute_position = parent_position + offset;
         let transform = Transform::from_translation(if descriptor.attached_to_player {
             offset
@@ -60,17 +72,26 @@ impl HitboxSpawner {
         builder
             .insert(OnHitEffect {
                 fixed_height: descriptor.fixed_height,
+                projectile: !descriptor.attached_to_player,
                 damage: descriptor.damage,
                 stun: descriptor.stun,
                 knockback: descriptor.knockback,
                 pushback: descriptor.pushback,
+                counter_damage: descriptor.counter_damage,
+                counter_stun: descriptor.counter_stun,
+                counter_launch: descriptor.counter_launch,
             })
             .insert(HitTracker::new(descriptor.hits))
             .insert(Owner(player))
-            .insert(Hitbox(Area::from_center_size(
-                Vec2::ZERO, // Position is set into the object directly
-                descriptor.hitbox.size(),
-            )))
+            .insert(relative(&descriptor.hitbox))
+            .insert(HitboxKeyframes {
+                spawn_frame: frame,
+                keyframes: descriptor
+                    .keyframes
+                    .iter()
+                    .map(|keyframe| (keyframe.frame, relative(&keyframe.hitbox)))
+                    .collect(),
+            })
             .insert(ConstantVelocity::new(facing.mirror_vec(descriptor.speed)));
 
         if descriptor.attached_to_player {
@@ -122,6 +143,18 @@ impl HitboxSpawner {
     pub fn add_to_queue(&mut self, object: SpawnDescriptor) {
         self.queue.push(object);
     }
+
+    /// For when the hitboxes have been respawned as different entities
+    pub fn remap_entities(&mut self, mapping: &HashMap<Entity, Entity>) {
+        self.despawn_requests
+            .retain_mut(|request| match mapping.get(&request.entity) {
+                Some(new_entity) => {
+                    request.entity = *new_entity;
+                    true
+                }
+                None => false,
+            });
+    }
 }
 
 pub(super) fn spawn_new(
@@ -144,6 +177,22 @@ pub(super) fn spawn_new(
     }
 }
 
+pub(super) fn animate(clock: Res<Clock>, mut query: Query<(&mut Hitbox, &HitboxKeyframes)>) {
+    for (mut hitbox, keyframes) in query.iter_mut() {
+        let latest = keyframes
+            .keyframes
+            .iter()
+            .filter(|(frame, _)| keyframes.spawn_frame + frame <= clock.frame)
+            .max_by_key(|(frame, _)| *frame);
+
+        if let Some((_, shape)) = latest {
+            if *hitbox != *shape {
+                *hitbox = shape.clone();
+            }
+        }
+    }
+}
+
 pub(super) fn despawn_expired(
     mut commands: Commands,
     clock: Res<Clock>,
@@ -3,15 +3,18 @@ use bevy::{
     prelude::*,
 };
 
-use characters::{Character, Grabable, HitTracker, Hitbox, Hurtbox, OnHitEffect, Resources};
+use characters::{
+    Character, CounterType, Grabable, HitTracker, Hitbox, Hurtbox, OnHitEffect, Phase, Resources,
+};
 use input_parsing::InputParser;
 use player_state::PlayerState;
 use time::Clock;
-use types::{Area, Facing, Owner, Player, Players, SoundEffect, VisualEffect};
+use types::{CompoundArea, Facing, Owner, Player, Players, SoundEffect, VisualEffect};
 
 use crate::{
     assets::{ParticleRequest, Particles, Sounds},
     physics::PlayerVelocity,
+    ui::Notifications,
 };
 
 use super::{Health, HitboxSpawner};
@@ -93,11 +96,13 @@ pub(super) fn clash_parry(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(super) fn register_hits(
     mut commands: Commands,
     clock: Res<Clock>,
     mut sounds: ResMut<Sounds>,
     mut particles: ResMut<Particles>,
+    mut notifications: ResMut<Notifications>,
     mut hitboxes: Query<(
         Entity,
         &Owner,
@@ -122,6 +127,7 @@ pub(super) fn register_hits(
                 clock.frame,
                 &mut sounds,
                 &mut particles,
+                &mut notifications,
                 effect,
                 &mut hit_tracker,
                 hitbox.with_offset(hitbox_tf.translation.truncate()),
@@ -141,9 +147,10 @@ fn handle_hit(
     frame: usize,
     sounds: &mut Sounds,
     particles: &mut Particles,
+    notifications: &mut Notifications,
     effect: &OnHitEffect,
     hit_tracker: &mut HitTracker,
-    hitbox: Area,
+    hitbox: CompoundArea,
     hitbox_entity: Entity,
     attacker: &mut <<PlayerQuery as WorldQuery>::Fetch as Fetch>::Item,
     defender: &mut <<PlayerQuery as WorldQuery>::Fetch as Fetch>::Item,
@@ -159,26 +166,61 @@ fn handle_hit(
         .with_offset(defender.tf.translation.truncate())
         .intersection(&hitbox)
     {
+        let phase = current_phase(defender.character, &defender.state);
+        if phase
+            .as_ref()
+            .and_then(|phase| phase.invulnerability)
+            .map_or(false, |invulnerability| {
+                invulnerability.avoids_attack(effect.projectile, !attacker.state.is_grounded())
+            })
+        {
+            // Goes through, the hitbox can still connect once the invulnerability is over
+            return;
+        }
+
         // Hit has happened
         // Handle blocking and state transitions here
         attacker.state.register_hit();
 
         let blocked = defender.state.blocked(
             effect.fixed_height,
-            hitbox,
+            // Height is judged from everything the attack covers
+            hitbox.bounds(),
             defender.character.low_block_height,
             defender.character.high_block_height,
             defender.parser.get_relative_stick_position(),
         );
 
+        // Armor takes the hit without stun or knockback
+        let armor = phase
+            .and_then(|phase| phase.armor)
+            .filter(|armor| !blocked && defender.state.absorb_hit(*armor));
+
+        // Clean hits on an opponent in the middle of a move
+        let counter = if blocked || armor.is_some() {
+            None
+        } else {
+            defender.state.get_move_state().and_then(|situation| {
+                defender
+                    .character
+                    .get_move(situation.move_id)
+                    .get_counter_type(situation)
+            })
+        };
+
         // Damage and meter gain
-        let amount = effect.damage.get(blocked);
-        defender.health.apply_damage(amount);
+        let amount = effect
+            .counter_damage
+            .get(counter, effect.damage.get(blocked));
+        if armor.map_or(false, |armor| armor.recoverable) {
+            defender.health.apply_recoverable_damage(amount);
+        } else {
+            defender.health.apply_damage(amount);
+        }
         attacker.resources.meter.add_combo_meter(amount);
-
-        // Knockback
-        let knockback_impulse = attacker.facing.mirror_vec(effect.knockback.get(blocked));
-        defender.velocity.add_impulse(knockback_impulse);
+        if let Some(counter) = counter {
+            attacker.resources.meter.gain(counter.meter_reward());
+        }
 
         // Pushback
         attacker
@@ -190,20 +232,45 @@ fn handle_hit(
                     .mirror_vec(defender.facing.mirror_vec(effect.pushback.get(blocked))),
             );
 
-        // Stun
-        if knockback_impulse.y > 0.0 {
-            defender.state.launch();
-        } else {
-            defender.state.stun(effect.stun.get(blocked) + frame);
+        if armor.is_none() {
+            // Knockback
+            let knockback_impulse = attacker.facing.mirror_vec(
+                effect
+                    .counter_launch
+                    .get(counter, effect.knockback.get(blocked)),
+            );
+            defender.velocity.add_impulse(knockback_impulse);
+
+            // Stun
+            if knockback_impulse.y > 0.0 {
+                defender.state.launch();
+            } else {
+                defender
+                    .state
+                    .stun(effect.counter_stun.get(counter, effect.stun.get(blocked)) + frame);
+            }
         }
 
         // Sound effect
-        sounds.play(if blocked {
-            SoundEffect::Block
-        } else {
-            SoundEffect::Hit
+        sounds.play(match counter {
+            Some(CounterType::CounterHit) => SoundEffect::CounterHit,
+            Some(CounterType::PunishCounter) => SoundEffect::PunishCounter,
+            None if blocked => SoundEffect::Block,
+            None => SoundEffect::Hit,
         });
 
+        // Notification
+        if let Some(counter) = counter {
+            notifications.add(
+                *attacker.player,
+                match counter {
+                    CounterType::CounterHit => "Counter hit!",
+                    CounterType::PunishCounter => "Punish counter!",
+                }
+                .to_owned(),
+            );
+        }
+
         // Visual effect
         particles.spawn(ParticleRequest {
             effect: if blocked {
@@ -217,7 +284,7 @@ fn handle_hit(
 
         hit_tracker.last_hit_frame = Some(frame);
 
-        if !blocked {
+        if !blocked && armor.is_none() {
             defender.spawner.despawn_on_hit(commands);
         }
 
@@ -239,12 +306,20 @@ pub(super) fn handle_grabs(
         &mut PlayerVelocity,
         &mut Health,
         &Facing,
+        &Character,
     )>,
 ) {
-    for (mut grab_target, mut state, mut spawner, mut velocity, mut health, &facing) in
+    for (mut grab_target, mut state, mut spawner, mut velocity, mut health, &facing, character) in
         query.iter_mut()
     {
         for descriptor in grab_target.queue.drain(..).collect::<Vec<_>>().into_iter() {
+            if current_phase(character, &state)
+                .and_then(|phase| phase.invulnerability)
+                .map_or(false, |invulnerability| invulnerability.avoids_grab())
+            {
+                continue;
+            }
+
             state.throw();
             spawner.despawn_on_hit(&mut commands);
             // Facing is from the one being thrown, but we want to write the vector from the attacker's perspective
@@ -253,3 +328,9 @@ pub(super) fn handle_grabs(
         }
     }
 }
+
+/// For invulnerability and armor, those come from the phase the player is in
+fn current_phase(character: &Character, state: &PlayerState) -> Option<Phase> {
+    let situation = state.get_move_state()?;
+    character.get_move(situation.move_id).get_phase(situation)
+}
@@ -6,31 +6,33 @@ mod health;
 pub use health::Health;
 
 mod hitboxes;
-pub use hitboxes::HitboxSpawner;
+pub use hitboxes::{HitboxKeyframes, HitboxSpawner};
 
-use time::{GameState, WAGStage};
+use time::{CombatApp, CombatStage, GameState, WAGStage};
 
 pub struct DamagePlugin;
 
 impl Plugin for DamagePlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set_to_stage(
-            WAGStage::HitReg,
+        app.add_combat_system_set(
+            CombatStage::HitReg,
             SystemSet::new()
+                .with_run_criteria(State::on_update(GameState::Combat))
                 .with_system(hitboxes::spawn_new)
-                .with_system(hitboxes::despawn_expired.after(hitboxes::spawn_new))
+                .with_system(hitboxes::animate.after(hitboxes::spawn_new))
+                .with_system(hitboxes::despawn_expired.after(hitboxes::animate))
                 .with_system(hitreg::clash_parry.after(hitboxes::despawn_expired))
                 .with_system(hitreg::register_hits.after(hitreg::clash_parry))
                 .with_system(hitreg::handle_grabs.after(hitreg::register_hits))
-                .with_system(
-                    health::check_dead
-                        .after(hitreg::handle_grabs)
-                        .with_run_criteria(State::on_update(GameState::Combat)),
-                )
-                .with_system(
-                    hitboxes::despawn_everything
-                        .with_run_criteria(State::on_exit(GameState::Combat)),
-                ),
-        );
+                .with_system(health::recover.after(hitreg::handle_grabs))
+                .with_system(health::check_dead.after(health::recover)),
+        )
+        // Rounds end in hit registration, on a combat frame
+        .add_combat_system(
+            CombatStage::HitReg,
+            hitboxes::despawn_everything.with_run_criteria(State::on_exit(GameState::Combat)),
+        )
+        // Frames are run by exclusive systems, those go first in the stage
+        .add_system_to_stage(WAGStage::Combat, health::end_round);
     }
 }
@@ -1,91 +1,100 @@
 use bevy::prelude::*;
 use characters::{Hitbox, Hurtbox};
+use types::Area;
 
 use crate::{assets::Colors, physics::Pushbox};
 
-#[derive(Debug, Component)]
-pub(super) enum BoxVisual {
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BoxKind {
     Hurtbox,
     Hitbox,
     Pushbox,
 }
 
+/// Shows one part of a box, compound boxes get one per part
+#[derive(Debug, Component)]
+pub(super) struct BoxVisual {
+    kind: BoxKind,
+    index: usize,
+}
+
 pub(super) fn spawn_boxes(
     mut commands: Commands,
     colors: Res<Colors>,
     hitboxes: Query<(Entity, &Hitbox, Option<&Children>)>,
     hurtboxes: Query<(Entity, &Hurtbox, Option<&Children>)>,
     pushboxes: Query<(Entity, &Pushbox, Option<&Children>)>,
-    sprites: Query<&Sprite, With<BoxVisual>>,
+    visuals: Query<&BoxVisual>,
 ) {
     for (entity, hitbox, maybe_children) in hitboxes.iter() {
         handle_box_spawning(
             &mut commands,
-            &sprites,
+            &visuals,
             entity,
             maybe_children,
-            BoxVisual::Hitbox,
+            BoxKind::Hitbox,
             colors.hitbox,
-            hitbox.center(),
-            Some(hitbox.size()),
+            hitbox.areas(),
         );
     }
     for (entity, hurtbox, maybe_children) in hurtboxes.iter() {
         handle_box_spawning(
             &mut commands,
-            &sprites,
+            &visuals,
             entity,
             maybe_children,
-            BoxVisual::Hurtbox,
+            BoxKind::Hurtbox,
             colors.hurtbox,
-            hurtbox.center(),
-            Some(hurtbox.size()),
+            hurtbox.areas(),
         );
     }
     for (entity, pushbox, maybe_children) in pushboxes.iter() {
         handle_box_spawning(
             &mut commands,
-            &sprites,
+            &visuals,
             entity,
             maybe_children,
-            BoxVisual::Pushbox,
+            BoxKind::Pushbox,
             colors.pushbox,
-            pushbox.center(),
-            Some(pushbox.size()),
+            &[pushbox.0],
         );
     }
 }
 
-#[allow(clippy::too_many_arguments)]
 fn handle_box_spawning(
     commands: &mut Commands,
-    sprites: &Query<&Sprite, With<BoxVisual>>,
+    visuals: &Query<&BoxVisual>,
     entity: Entity,
     maybe_children: Option<&Children>,
-    marker: BoxVisual,
+    kind: BoxKind,
     color: Color,
-    offset: Vec2,
-    custom_size: Option<Vec2>,
+    areas: &[Area],
 ) {
-    if maybe_children.is_none()
-        || !maybe_children
-            .unwrap()
+    let existing = maybe_children.map_or(0, |children| {
+        children
             .iter()
-            .any(|e| sprites.get(*e).is_ok())
-    {
-        // If there is no child entity that has the marker component and a sprite (has been handled)
+            .filter(|child| {
+                visuals
+                    .get(**child)
+                    .map_or(false, |visual| visual.kind == kind)
+            })
+            .count()
+    });
+
+    // Boxes can gain parts, like when a limb extends. Extra visuals are hidden in size_adjustment
+    for (index, area) in areas.iter().enumerate().skip(existing) {
         commands.entity(entity).with_children(|parent| {
             parent
                 .spawn_bundle(SpriteBundle {
-                    transform: Transform::from_translation(offset.extend(0.0)),
+                    transform: Transform::from_translation(area.center().extend(0.0)),
                     sprite: Sprite {
                         color,
-                        custom_size,
+                        custom_size: Some(area.size()),
                         ..default()
                     },
                     ..default()
                 })
-                .insert(marker);
+                .insert(BoxVisual { kind, index });
         });
     }
 }
@@ -93,15 +102,25 @@ fn handle_box_spawning(
 pub(super) fn size_adjustment(
     players: Query<(&Hurtbox, &Pushbox)>,
     hitboxes: Query<&Hitbox>,
-    mut sprites: Query<(&mut Sprite, &mut Transform, &BoxVisual, &Parent)>,
+    mut sprites: Query<(
+        &mut Sprite,
+        &mut Transform,
+        &mut Visibility,
+        &BoxVisual,
+        &Parent,
+    )>,
 ) {
-    for (mut sprite, mut tf, box_type, parent) in sprites.iter_mut() {
-        let area = match box_type {
-            BoxVisual::Hurtbox => players.get(**parent).unwrap().0 .0,
-            BoxVisual::Hitbox => hitboxes.get(**parent).unwrap().0,
-            BoxVisual::Pushbox => players.get(**parent).unwrap().1 .0,
+    for (mut sprite, mut tf, mut visibility, visual, parent) in sprites.iter_mut() {
+        let area = match visual.kind {
+            BoxKind::Hurtbox => players.get(**parent).unwrap().0.areas().get(visual.index),
+            BoxKind::Hitbox => hitboxes.get(**parent).unwrap().areas().get(visual.index),
+            BoxKind::Pushbox => Some(&players.get(**parent).unwrap().1 .0),
         };
-        sprite.custom_size = Some(area.size());
-        tf.translation = area.center().extend(0.0);
+
+        visibility.is_visible = area.is_some();
+        if let Some(area) = area {
+            sprite.custom_size = Some(area.size());
+            tf.translation = area.center().extend(0.0);
+        }
     }
 }
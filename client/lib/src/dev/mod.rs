@@ -10,6 +10,7 @@ use crate::{
     assets::Sounds,
     damage::Health,
     physics::{ConstantVelocity, PlayerVelocity, Pushbox},
+    rng::CosmeticRng,
 };
 
 mod box_visualization;
@@ -44,15 +45,18 @@ fn test_system(
     keys: Res<Input<KeyCode>>,
     mut query: Query<(&mut Inventory, &Character)>,
     mut sounds: ResMut<Sounds>,
+    // Dev rolls are not recorded, the seeded one would roll differently in replays and online
+    mut rng: Local<CosmeticRng>,
 ) {
     // B for Buy
     if keys.just_pressed(KeyCode::B) {
         for (mut inventory, character) in query.iter_mut() {
-            if let Some((id, _)) = character.roll_items(1, &inventory).first() {
+            if let Some((id, _)) = character.roll_items(1, &inventory, &mut **rng).first() {
                 inventory.add_item(*id);
             }
         }
-    } else if keys.just_pressed(KeyCode::S) {
+    } else if keys.just_pressed(KeyCode::P) {
+        // P for Play
         dbg!("Playing");
         sounds.play(SoundEffect::Whoosh)
     }
@@ -0,0 +1,40 @@
+use bevy::{prelude::*, utils::HashMap};
+use time::not_in_combat;
+
+use crate::{
+    assets::{Particles, Sounds},
+    camera::{add_headless_camera, center_camera},
+    replay::DiscardReplays,
+    ui::Notifications,
+};
+
+/// Stands in for the assets, UI and camera when running without a window.
+/// Combat systems still request sounds, particles and notifications, those go nowhere.
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Sounds::new(HashMap::default()))
+            .insert_resource(Particles::new(default()))
+            .insert_resource(DiscardReplays)
+            .add_startup_system(setup_notifications)
+            .add_startup_system(add_headless_camera)
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                center_camera.with_run_criteria(not_in_combat),
+            )
+            .add_system_to_stage(CoreStage::Last, discard_requests);
+    }
+}
+
+fn setup_notifications(mut commands: Commands) {
+    // Never shown, so the containers don't need to be UI nodes
+    let p1_container = commands.spawn().id();
+    let p2_container = commands.spawn().id();
+    commands.insert_resource(Notifications::new(p1_container, p2_container));
+}
+
+fn discard_requests(mut particles: ResMut<Particles>, mut notifications: ResMut<Notifications>) {
+    particles.discard_requests();
+    notifications.discard_requests();
+}
@@ -1,17 +1,35 @@
 #![feature(drain_filter)]
 mod assets;
 mod camera;
+mod checksum;
 mod damage;
 mod dev;
+mod headless;
+mod netplay;
 mod physics;
 mod player;
+mod replay;
+mod rng;
+mod snapshot;
 mod stage;
 mod ui;
 
 use bevy::prelude::*;
 
+pub use assets::AssetFolder;
+pub use checksum::{
+    ChecksumLog, ChecksumLogError, ChecksumOutput, ChecksumPart, Desync, FrameChecksum,
+    PlayerChecksum,
+};
+pub use damage::Health;
 // So it can be disabled in integration tests
 pub use dev::DevPlugin;
+pub use netplay::{LocalController, Netplay};
+pub use physics::{Sides, GROUND_PLANE_HEIGHT};
+pub use player::PendingDefinition;
+pub use replay::ReplayPlayback;
+pub use rng::{CosmeticRng, GameRng};
+pub use snapshot::Snapshot;
 // Only thing exported out of this crate
 pub struct WAGLib;
 impl PluginGroup for WAGLib {
@@ -19,6 +37,7 @@ impl PluginGroup for WAGLib {
         group // Order matters here, loaded in the defined order
             .add(bevy_hanabi::HanabiPlugin)
             .add(time::TimePlugin) // Has to be first, since it defines labels for ordering other systems
+            .add(rng::RngPlugin)
             .add(assets::AssetsPlugin) // Has to be before those assets are used
             .add(ui::UIPlugin)
             .add(camera::CustomCameraPlugin)
@@ -27,6 +46,28 @@ impl PluginGroup for WAGLib {
             .add(DevPlugin)
             .add(physics::PhysicsPlugin)
             .add(input_parsing::InputParsingPlugin)
+            .add(replay::ReplayPlugin)
+            .add(netplay::NetplayPlugin)
+            .add(checksum::ChecksumPlugin)
             .add(stage::StagePlugin);
     }
 }
+
+/// Only the combat simulation, nothing that needs a window, audio or assets.
+/// Made for running matches as fast as possible
+pub struct WAGHeadless;
+impl PluginGroup for WAGHeadless {
+    fn build(&mut self, group: &mut bevy::app::PluginGroupBuilder) {
+        group // Same order as in WAGLib
+            .add(time::TimePlugin)
+            .add(rng::RngPlugin)
+            .add(headless::HeadlessPlugin) // In place of assets, UI and camera
+            .add(player::PlayerPlugin)
+            .add(damage::DamagePlugin)
+            .add(physics::PhysicsPlugin)
+            .add(input_parsing::InputParsingPlugin)
+            .add(replay::ReplayPlugin)
+            .add(netplay::NetplayPlugin)
+            .add(checksum::ChecksumPlugin);
+    }
+}
@@ -0,0 +1,261 @@
+use bevy::prelude::*;
+use characters::Character;
+use input_parsing::{Diff, InputStream, KeyboardStream, PadStream, ParrotStream};
+use netcode::{GameSimulation, Handshake, RollbackRunner, RollbackSession, Transport};
+use time::{ClockControl, DueFrames, GameState, WAGStage};
+use types::{Player, Players};
+
+use crate::{checksum::checksum, snapshot::Snapshot, ui::Notifications};
+
+type BoxedTransport = Box<dyn Transport + Send + Sync>;
+
+/// Where the peers are between and during rounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RoundStatus {
+    /// Ready once start was pressed, the round starts when the peer is ready too
+    Shop {
+        ready: bool,
+    },
+    /// Agreed on, waiting for the game to enter combat
+    Starting,
+    Playing,
+    /// The peer has a different seed or definitions, rounds can't be played
+    Disagreed,
+}
+
+/// Present in an online match, where both players read their inputs from the session.
+/// Has to be inserted before the plugins, since players are spawned with it.
+/// Peers check that they roll with the same seed and start from the same character definitions.
+/// Rounds start when both are ready, and end once both know every input up to the end,
+/// so rollback never reaches into another round
+pub struct Netplay {
+    session: RollbackSession<BoxedTransport>,
+    runner: RollbackRunner<Snapshot>,
+    seed: u64,
+    /// Computed once the players have spawned
+    handshake: Option<Handshake>,
+    status: RoundStatus,
+    /// Read from the controllers, but the peer was too far behind for it to be used yet
+    held_input: Option<Option<Diff>>,
+}
+impl Netplay {
+    pub fn new(
+        transport: impl Transport + Send + Sync + 'static,
+        local_player: Player,
+        seed: u64,
+    ) -> Self {
+        Self {
+            session: RollbackSession::new(Box::new(transport), local_player),
+            runner: RollbackRunner::default(),
+            seed,
+            handshake: None,
+            status: RoundStatus::Shop { ready: false },
+            held_input: None,
+        }
+    }
+
+    pub fn local_player(&self) -> Player {
+        self.session.local_player()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Both peers are ready, the next round starts on this update
+    pub fn round_starting(&self) -> bool {
+        self.status == RoundStatus::Starting
+    }
+
+    /// The peer has a different seed or definitions
+    pub fn mismatched(&self) -> bool {
+        self.status == RoundStatus::Disagreed
+    }
+
+    /// Rounds started so far
+    pub fn round(&self) -> usize {
+        self.session.round()
+    }
+
+    /// Frames of the current round simulated through the session so far
+    pub fn frame(&self) -> usize {
+        self.runner.frame()
+    }
+
+    /// How many times a guess about the peer's input was wrong
+    pub fn rollbacks(&self) -> usize {
+        self.runner.rollbacks()
+    }
+}
+
+/// The local player's keyboard and pad, read by netplay and sent through the session
+#[derive(Debug, Component)]
+pub struct LocalController;
+
+pub struct NetplayPlugin;
+
+impl Plugin for NetplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(spawn_local_controller)
+            .add_system_to_stage(
+                WAGStage::Combat,
+                run_netplay_frames.exclusive_system().before(DueFrames),
+            );
+    }
+}
+
+fn spawn_local_controller(mut commands: Commands, netplay: Option<Res<Netplay>>) {
+    if let Some(netplay) = netplay {
+        // No input parser, the player entity parses what comes out of the session
+        commands
+            .spawn()
+            .insert(LocalController)
+            .insert(KeyboardStream::new(netplay.local_player()))
+            .insert(PadStream::default())
+            .insert(ParrotStream::default())
+            .insert(Name::new("Local controller"));
+    }
+}
+
+/// Agrees on rounds with the peer and simulates the frames that are due through the session
+fn run_netplay_frames(world: &mut World) {
+    if !world.contains_resource::<Netplay>() {
+        return;
+    }
+
+    world.resource_scope(|world, mut netplay: Mut<Netplay>| {
+        if netplay.handshake.is_none() {
+            let handshake = Handshake {
+                seed: netplay.seed,
+                definitions: definitions_checksum(world),
+            };
+            netplay.handshake = Some(handshake);
+        }
+
+        match netplay.status {
+            RoundStatus::Shop { .. } | RoundStatus::Disagreed => {
+                between_rounds(world, &mut netplay)
+            }
+            RoundStatus::Starting => {
+                if in_combat(world) {
+                    netplay.status = RoundStatus::Playing;
+                    play(world, &mut netplay);
+                }
+            }
+            RoundStatus::Playing => play(world, &mut netplay),
+        }
+    });
+}
+
+/// Both players' definitions, the peer has to start with the same ones
+fn definitions_checksum(world: &mut World) -> u64 {
+    let players = world.resource::<Players>();
+    let (one, two) = (players.one, players.two);
+    let character = |entity| world.get::<Character>(entity).unwrap();
+    checksum(&(character(one), character(two)))
+}
+
+/// Keeps talking to the peer, it may still need inputs or acknowledgements from the last round
+fn between_rounds(world: &mut World, netplay: &mut Netplay) {
+    let handshake = netplay.handshake.unwrap();
+    let session = &mut netplay.session;
+    session.poll();
+    session.send_hello(handshake);
+    session.send_inputs();
+
+    let ready = match netplay.status {
+        // Same as offline, holding start is enough
+        RoundStatus::Shop { ready } => {
+            ready || world.resource::<Input<KeyCode>>().pressed(KeyCode::Return)
+        }
+        _ => return,
+    };
+    netplay.status = RoundStatus::Shop { ready };
+
+    match session.peer_handshake() {
+        // Start may be pressed before the peer is there, the round waits for it
+        None => return,
+        Some(peer) if peer != handshake => {
+            netplay.status = RoundStatus::Disagreed;
+            if let Some(mut notifications) = world.get_resource_mut::<Notifications>() {
+                notifications.add(
+                    session.local_player(),
+                    "Peer has a different seed or characters".to_owned(),
+                );
+            }
+            return;
+        }
+        Some(_) => {}
+    }
+
+    if ready {
+        session.send_ready();
+        if session.peer_ready() {
+            session.start_round();
+            netplay.runner = RollbackRunner::default();
+            netplay.held_input = None;
+            netplay.status = RoundStatus::Starting;
+        }
+    }
+}
+
+fn play(world: &mut World, netplay: &mut Netplay) {
+    let Netplay {
+        session,
+        runner,
+        held_input,
+        status,
+        ..
+    } = netplay;
+
+    // Inputs that arrive are handled right away, even if no frame is due
+    runner.sync(session, &mut GameSimulation::<Snapshot>::new(world));
+
+    let mut advanced = false;
+    while runner.end().is_none() && world.resource_mut::<ClockControl>().take_frame() {
+        let input = held_input.take().unwrap_or_else(|| read_local_input(world));
+        let mut simulation = GameSimulation::<Snapshot>::new(world);
+        if !runner.advance_frame(session, &mut simulation, input.clone()) {
+            // Waiting for the peer, the other due frames are skipped with this one
+            *held_input = Some(input);
+            while world.resource_mut::<ClockControl>().take_frame() {}
+            return;
+        }
+        advanced = true;
+    }
+
+    if let Some(end) = runner.end() {
+        // The round may have ended on a guess, it's only over once the inputs are confirmed
+        while world.resource_mut::<ClockControl>().take_frame() {}
+        session.send_inputs();
+        if session.settled(end) {
+            world
+                .resource_mut::<State<GameState>>()
+                .set(GameState::Shop)
+                .unwrap();
+            *status = RoundStatus::Shop { ready: false };
+        }
+    } else if !advanced {
+        // Nothing new to send, but what was sent may have been lost
+        session.send_inputs();
+    }
+}
+
+fn in_combat(world: &World) -> bool {
+    *world.resource::<State<GameState>>().current() == GameState::Combat
+}
+
+fn read_local_input(world: &mut World) -> Option<Diff> {
+    let mut controllers =
+        world.query_filtered::<(&mut KeyboardStream, &mut PadStream), With<LocalController>>();
+    let (mut keyboard, mut pad) = controllers
+        .iter_mut(world)
+        .next()
+        .expect("Netplay without a local controller");
+
+    // Both can be used at the same time
+    match (pad.read(), keyboard.read()) {
+        (Some(pad), Some(keyboard)) => Some(pad.merge(keyboard)),
+        (pad, keyboard) => pad.or(keyboard),
+    }
+}
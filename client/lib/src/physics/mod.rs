@@ -1,24 +1,25 @@
 mod player_velocity;
 pub use player_velocity::PlayerVelocity;
 
-use bevy::{ecs::query::WorldQuery, prelude::*};
+use bevy::{ecs::query::WorldQuery, prelude::*, transform::transform_propagate_system};
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
 use characters::Character;
 use constants::PLAYER_GRAVITY_PER_FRAME;
 use player_state::PlayerState;
-use time::{once_per_combat_frame, WAGStage};
+use time::{CombatApp, CombatStage, GameState};
 use types::{Area, Facing, Players};
 
 use crate::{
-    camera::{WorldCamera, VIEWPORT_HALFWIDTH},
+    camera::{center_camera, WorldCamera, VIEWPORT_HALFWIDTH},
     damage::HitboxSpawner,
 };
 
 pub const GROUND_PLANE_HEIGHT: f32 = 0.0;
 pub const ARENA_WIDTH: f32 = 10.0;
 
-#[derive(Debug, Default, Inspectable, Component)]
+#[derive(Debug, Default, Clone, Copy, Inspectable, Component, Serialize, Deserialize)]
 pub struct ConstantVelocity {
     pub shift: Vec3,
     pub speed: Vec3,
@@ -32,16 +33,24 @@ impl ConstantVelocity {
     }
 }
 
-#[derive(Debug, Default, Inspectable, Component, Deref, DerefMut)]
+#[derive(
+    Debug, Default, Clone, Copy, Inspectable, Component, Deref, DerefMut, Serialize, Deserialize,
+)]
 pub struct Pushbox(pub Area);
 
+/// Which side player one was on after the last frame
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sides {
+    pub one_on_right: bool,
+}
+
 pub struct PhysicsPlugin;
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set_to_stage(
-            WAGStage::Physics,
+        app.add_combat_system_set(
+            CombatStage::Physics,
             SystemSet::new()
-                .with_run_criteria(once_per_combat_frame)
+                .with_run_criteria(State::on_update(GameState::Combat))
                 .with_system(sideswitcher)
                 .with_system(player_gravity.after(sideswitcher))
                 .with_system(player_input.after(player_gravity))
@@ -49,7 +58,16 @@ impl Plugin for PhysicsPlugin {
                 .with_system(push_players.after(move_players))
                 .with_system(clamp_players.after(push_players))
                 .with_system(move_constants.after(clamp_players)),
-        );
+        )
+        // Hitreg and the stage walls use these, and several frames can run back to back
+        .add_combat_system_set(
+            CombatStage::FrameEnd,
+            SystemSet::new()
+                .with_run_criteria(State::on_update(GameState::Combat))
+                .with_system(center_camera)
+                .with_system(transform_propagate_system.after(center_camera)),
+        )
+        .init_resource::<Sides>();
     }
 }
 
@@ -59,12 +77,18 @@ struct SideswitcherQuery<'a> {
     tf: &'a Transform,
     direction: &'a mut Facing,
 }
-fn sideswitcher(players: Res<Players>, mut query: Query<SideswitcherQuery>) {
+/// Players turn to face each other when they switch sides, so setups can have them face away
+fn sideswitcher(
+    players: Res<Players>,
+    mut sides: ResMut<Sides>,
+    mut query: Query<SideswitcherQuery>,
+) {
     if let Ok([mut p1, mut p2]) = query.get_many_mut([players.one, players.two]) {
-        let p1_flipped = p1.tf.translation.x > p2.tf.translation.x;
-        if p1.direction.to_flipped() != p1_flipped {
-            p1.direction.set_flipped(p1_flipped);
-            p2.direction.set_flipped(!p1_flipped);
+        let one_on_right = p1.tf.translation.x > p2.tf.translation.x;
+        if sides.one_on_right != one_on_right {
+            sides.one_on_right = one_on_right;
+            p1.direction.set_flipped(one_on_right);
+            p2.direction.set_flipped(!one_on_right);
         }
     }
 }
@@ -1,15 +1,16 @@
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
 use characters::{MoveId, MoveMobility};
 use types::Facing;
 
-#[derive(Debug, Inspectable, Clone, Default, Copy)]
+#[derive(Debug, Inspectable, Clone, Default, Copy, Serialize, Deserialize)]
 pub struct CurrentMove {
     id: (MoveId, i32),
     base_velocity: Vec3,
 }
-#[derive(Debug, Inspectable, Clone, Default, Copy, Component)]
+#[derive(Debug, Inspectable, Clone, Default, Copy, Component, Serialize, Deserialize)]
 pub struct PlayerVelocity {
     velocity: Vec3,
     /// Keep track of if pushing is currently happening for wall clamp reasons
@@ -1,29 +1,16 @@
 use bevy::prelude::*;
-use characters::{Character, Resources};
+use characters::{Charge, Resources};
 use input_parsing::InputParser;
 use time::Clock;
 
-const CHARGE_EXPIRATION_TIME: f32 = 0.2;
-const CHARGE_EXPIRATION_FRAMES: usize = (CHARGE_EXPIRATION_TIME * constants::FPS) as usize;
-
-pub fn manage_charge(
-    mut query: Query<(&mut Resources, &InputParser, &Character)>,
-    clock: Res<Clock>,
-) {
-    for (mut resources, parser, character) in query.iter_mut() {
-        let charge = &mut resources.charge;
-
-        let player_charging = character
-            .charge_directions
-            .contains(&parser.get_relative_stick_position());
-
-        if player_charging {
-            // Bump charge
-            charge.progress += 1;
-            charge.last_update = clock.frame;
-        } else if charge.last_update + CHARGE_EXPIRATION_FRAMES < clock.frame {
-            // Charge expiration
-            charge.reset();
-        }
+pub fn manage_charge(mut query: Query<(&mut Resources, &InputParser)>, clock: Res<Clock>) {
+    for (mut resources, parser) in query.iter_mut() {
+        resources.charge = parser
+            .get_charge_progress(clock.frame)
+            .map(|charge| Charge {
+                progress: charge.progress,
+                full_progress: charge.required,
+            })
+            .unwrap_or_default();
     }
 }
@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+use characters::Character;
+use input_parsing::{InputParser, InvalidInput, ReplayRecorder, ReplayStream};
+use player_state::PlayerState;
+use time::Clock;
+use types::Player;
+
+use super::MoveBuffer;
+
+/// Definition that passed the checks, waits for the player to finish their move
+#[derive(Debug, Component)]
+pub struct PendingDefinition(Character);
+impl PendingDefinition {
+    /// The parser is only swapped later, this makes sure that can't fail
+    pub fn new(character: Character) -> Result<Self, InvalidInput> {
+        InputParser::load(character.get_inputs())?;
+        Ok(Self(character))
+    }
+}
+
+/// Swapping in the middle of a move could leave it on a phase that no longer exists,
+/// so the new definition is used from the next move on.
+/// Swaps go in the replay, playback makes them on the same frames
+#[allow(clippy::type_complexity)]
+pub fn swap_definitions(
+    mut commands: Commands,
+    clock: Res<Clock>,
+    mut recorder: Option<ResMut<ReplayRecorder>>,
+    mut players: Query<(
+        Entity,
+        &Player,
+        Option<&PendingDefinition>,
+        Option<&mut ReplayStream>,
+        &PlayerState,
+        &mut Character,
+        &mut InputParser,
+        &mut MoveBuffer,
+    )>,
+) {
+    for (entity, player, pending, stream, state, mut character, mut parser, mut buffer) in
+        players.iter_mut()
+    {
+        let new_definition = if let Some(mut stream) = stream {
+            // Recorded swaps already waited for the move to end
+            match stream.take_reload(clock.frame) {
+                Some(recorded) => recorded,
+                None => continue,
+            }
+        } else {
+            match pending {
+                Some(pending) if state.get_move_state().is_none() => {
+                    commands.entity(entity).remove::<PendingDefinition>();
+                    if let Some(recorder) = recorder.as_mut() {
+                        recorder.record_reload(*player, clock.frame, pending.0.clone());
+                    }
+                    pending.0.clone()
+                }
+                _ => continue,
+            }
+        };
+
+        parser
+            .reload(new_definition.get_inputs())
+            .expect("Inputs were checked when the definition was queued");
+        *character = new_definition;
+        // Buffered moves may have been removed or changed
+        buffer.clear();
+    }
+}
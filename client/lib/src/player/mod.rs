@@ -1,4 +1,5 @@
 mod charge_accumulator;
+mod definition_swap;
 mod move_activation;
 mod move_advancement;
 mod movement;
@@ -6,21 +7,28 @@ mod recovery;
 mod size_adjustment;
 mod update_animation;
 
-use characters::{dummy, Character, Grabable, Hurtbox, Inventory, Resources};
-use input_parsing::{InputParser, PadBundle};
+use characters::{Character, CharacterId, Grabable, Hurtbox, Inventory, Resources};
+use input_parsing::{
+    InputParser, InputSource, InputSources, PadBundle, ReplayCharacters, ReplayDefinitions,
+    ReplayRecorder,
+};
 use player_state::PlayerState;
-use time::{once_per_combat_frame, Clock, GameState, RoundResult};
+use time::{Clock, CombatApp, CombatStage, GameState, RoundResult};
 use types::{Facing, Player, Players};
 
 use crate::{
-    assets::{AnimationHelperSetup, ModelRequest},
+    assets::{AnimationHelperSetup, AssetFolder, ModelRequest},
     damage::{Health, HitboxSpawner},
-    physics::{PlayerVelocity, Pushbox, GROUND_PLANE_HEIGHT},
+    netplay::Netplay,
+    physics::{PlayerVelocity, Pushbox, Sides, GROUND_PLANE_HEIGHT},
+    replay::ReplayPlayback,
+    rng::GameRng,
 };
 
 use bevy::{ecs::query::WorldQuery, prelude::*};
 
-use self::move_activation::MoveBuffer;
+pub use self::definition_swap::PendingDefinition;
+pub(crate) use self::move_activation::{MoveBuffer, MoveBufferState};
 
 const PLAYER_SPAWN_DISTANCE: f32 = 2.5; // Distance from x=0(middle)
 const PLAYER_SPAWN_HEIGHT: f32 = GROUND_PLANE_HEIGHT + 0.001;
@@ -46,12 +54,16 @@ impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_startup_system(setup)
             .add_system(reset.with_run_criteria(State::on_update(GameState::Shop)))
-            .add_system_set(
+            .add_combat_system_set(
+                CombatStage::Players,
                 SystemSet::new()
-                    .with_run_criteria(once_per_combat_frame)
-                    .with_system(move_advancement::move_advancement.after(reset))
+                    .with_run_criteria(State::on_update(GameState::Combat))
+                    .with_system(move_advancement::move_advancement)
+                    .with_system(
+                        definition_swap::swap_definitions.after(move_advancement::move_advancement),
+                    )
                     .with_system(
-                        move_activation::move_activator.after(move_advancement::move_advancement),
+                        move_activation::move_activator.after(definition_swap::swap_definitions),
                     )
                     .with_system(recovery::stun_recovery.after(move_activation::move_activator))
                     .with_system(recovery::ground_recovery.after(recovery::stun_recovery))
@@ -67,13 +79,63 @@ impl Plugin for PlayerPlugin {
     }
 }
 
-fn setup(mut commands: Commands) {
+fn setup(
+    mut commands: Commands,
+    assets: Res<AssetFolder>,
+    sources: Res<InputSources>,
+    rng: Res<GameRng>,
+    playback: Option<Res<ReplayPlayback>>,
+    netplay: Option<Res<Netplay>>,
+) {
+    let replaying = playback.is_some();
+    let controls = |player| {
+        if replaying {
+            Controls::Replay
+        } else if netplay.is_some() {
+            Controls::Network
+        } else {
+            Controls::Local(sources.get(player))
+        }
+    };
+    let characters = playback
+        .as_ref()
+        .map_or_else(ReplayCharacters::default, |playback| playback.characters());
+    let load = |id: CharacterId| {
+        id.load(&assets.0)
+            .unwrap_or_else(|error| panic!("Can't load {:?} from {:?}: {}", id, assets.0, error))
+    };
+    // Replays bring their own, the files may have been edited since
+    let definitions = playback.map_or_else(
+        || ReplayDefinitions {
+            one: load(characters.one),
+            two: load(characters.two),
+        },
+        |playback| playback.definitions().clone(),
+    );
+
     let players = Players {
-        one: spawn_player(&mut commands, -PLAYER_SPAWN_DISTANCE, Player::One),
-        two: spawn_player(&mut commands, PLAYER_SPAWN_DISTANCE, Player::Two),
+        one: spawn_player(
+            &mut commands,
+            -PLAYER_SPAWN_DISTANCE,
+            Player::One,
+            controls(Player::One),
+            characters.one,
+            definitions.one.clone(),
+        ),
+        two: spawn_player(
+            &mut commands,
+            PLAYER_SPAWN_DISTANCE,
+            Player::Two,
+            controls(Player::Two),
+            characters.two,
+            definitions.two.clone(),
+        ),
     };
 
     commands.insert_resource(players);
+    if !replaying {
+        commands.insert_resource(ReplayRecorder::new(characters, definitions, rng.seed()));
+    }
 }
 
 #[derive(Bundle, Default)]
@@ -87,24 +149,61 @@ struct PlayerDefaults {
     move_buffer: MoveBuffer,
 }
 
-fn spawn_player(commands: &mut Commands, offset: f32, player: Player) -> Entity {
+/// Where a player's inputs come from
+#[derive(Debug, Clone, Copy)]
+enum Controls {
+    Local(InputSource),
+    Replay,
+    /// Netplay session, the local player's controllers are read elsewhere
+    Network,
+}
+
+fn spawn_player(
+    commands: &mut Commands,
+    offset: f32,
+    player: Player,
+    controls: Controls,
+    character_id: CharacterId,
+    character: Character,
+) -> Entity {
     let state = PlayerState::default();
-    let character = dummy();
 
     let mut spawn_handle = commands.spawn_bundle(TransformBundle {
         local: Transform::from_translation((offset, PLAYER_SPAWN_HEIGHT, 0.0).into()),
         ..default()
     });
 
+    // Inputs are parsed here, so broken character data is caught at startup
+    let inputs = character.get_inputs();
+    let loaded = match controls {
+        Controls::Local(InputSource::Gamepad) => PadBundle::new(inputs).map(|bundle| {
+            spawn_handle.insert_bundle(bundle);
+        }),
+        Controls::Local(InputSource::Keyboard) => {
+            PadBundle::keyboard(inputs, player).map(|bundle| {
+                spawn_handle.insert_bundle(bundle);
+            })
+        }
+        Controls::Replay => PadBundle::replay(inputs, player).map(|bundle| {
+            spawn_handle.insert_bundle(bundle);
+        }),
+        Controls::Network => PadBundle::network(inputs).map(|bundle| {
+            spawn_handle.insert_bundle(bundle);
+        }),
+    };
+    if let Err(error) = loaded {
+        panic!("Failed to load character for player {}: {}", player, error);
+    }
+
     spawn_handle
         .insert_bundle(PlayerDefaults::default())
-        .insert_bundle(PadBundle::new(character.get_inputs()))
         .insert(Name::new(format!("Player {}", player)))
         .insert(AnimationHelperSetup)
         .insert(Facing::from_flipped(offset.is_sign_positive()))
-        .insert(Hurtbox(character.get_hurtbox(false)))
+        .insert(Hurtbox(character.get_hurtbox(false).into()))
         .insert(Pushbox(character.get_pushbox(false)))
         .insert(character.clone())
+        .insert(character_id)
         .insert(player)
         .insert(state);
 
@@ -117,6 +216,7 @@ fn spawn_player(commands: &mut Commands, offset: f32, player: Player) -> Entity
     spawn_handle.id()
 }
 
+#[allow(clippy::too_many_arguments)]
 fn reset(
     mut commands: Commands,
     keys: Res<Input<KeyCode>>,
@@ -124,23 +224,45 @@ fn reset(
         &mut Health,
         &mut Resources,
         &mut Transform,
+        &mut Facing,
         &Player,
         &mut PlayerState,
         &mut MoveBuffer,
         &mut InputParser,
     )>,
+    mut sides: ResMut<Sides>,
     mut game_state: ResMut<State<GameState>>,
     mut clock: ResMut<Clock>,
-    bevy_time: Res<Time>,
+    playback: Option<Res<ReplayPlayback>>,
+    netplay: Option<Res<Netplay>>,
 ) {
-    // Just pressed would be better, but it's difficult in tests and the difference is very minor.
-    if keys.pressed(KeyCode::Return) {
+    // Replays move on to the next round on their own
+    let replay_continues = playback.map_or(false, |playback| playback.has_next_round());
+
+    let start = if let Some(netplay) = netplay {
+        // Both peers have to agree on it
+        netplay.round_starting()
+    } else {
+        // Just pressed would be better, but it's difficult in tests and the difference is very minor.
+        keys.pressed(KeyCode::Return) || replay_continues
+    };
+
+    if start {
         game_state.set(GameState::Combat).unwrap();
-        clock.reset(bevy_time.seconds_since_startup());
+        clock.reset();
         commands.remove_resource::<RoundResult>();
 
-        for (mut health, mut resources, mut tf, player, mut player_state, mut buffer, mut parser) in
-            query.iter_mut()
+        *sides = Sides::default();
+        for (
+            mut health,
+            mut resources,
+            mut tf,
+            mut facing,
+            player,
+            mut player_state,
+            mut buffer,
+            mut parser,
+        ) in query.iter_mut()
         {
             health.reset();
             resources.reset();
@@ -148,14 +270,12 @@ fn reset(
             buffer.clear();
             parser.clear();
 
-            tf.translation = Vec3::new(
-                match *player {
-                    Player::One => -PLAYER_SPAWN_DISTANCE,
-                    Player::Two => PLAYER_SPAWN_DISTANCE,
-                },
-                PLAYER_SPAWN_HEIGHT,
-                0.0,
-            );
+            let offset = match *player {
+                Player::One => -PLAYER_SPAWN_DISTANCE,
+                Player::Two => PLAYER_SPAWN_DISTANCE,
+            };
+            tf.translation = Vec3::new(offset, PLAYER_SPAWN_HEIGHT, 0.0);
+            *facing = Facing::from_flipped(offset.is_sign_positive());
         }
     }
 }
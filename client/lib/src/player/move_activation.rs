@@ -2,6 +2,7 @@ use bevy::{
     ecs::query::{Fetch, WorldQuery},
     prelude::*,
 };
+use serde::{Deserialize, Serialize};
 
 use characters::{Character, Move, MoveId, MoveSituation};
 use time::Clock;
@@ -22,7 +23,33 @@ pub struct MoveBuffer {
     buffer: Vec<(usize, MoveId)>,
     force_start: Option<(MoveId, Move, Option<i32>)>,
 }
+
+/// MoveBuffer without the forced move's data, which is looked up again when loading
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveBufferState {
+    buffer: Vec<(usize, MoveId)>,
+    force_start: Option<(MoveId, Option<i32>)>,
+}
+
 impl MoveBuffer {
+    pub fn save_state(&self) -> MoveBufferState {
+        MoveBufferState {
+            buffer: self.buffer.clone(),
+            force_start: self
+                .force_start
+                .as_ref()
+                .map(|(move_id, _, frame)| (*move_id, *frame)),
+        }
+    }
+
+    /// Forced moves belong to whoever forced them, like the thrower of a throw
+    pub fn load_state(&mut self, state: &MoveBufferState, forcer: &Character) {
+        self.buffer = state.buffer.clone();
+        self.force_start = state
+            .force_start
+            .map(|(move_id, frame)| (move_id, forcer.get_move(move_id), frame));
+    }
+
     pub fn set_force_starter(&mut self, move_id: MoveId, move_data: Move) {
         self.force_start = Some((move_id, move_data, None));
     }
@@ -43,7 +70,7 @@ impl MoveBuffer {
             .filter(|(_, move_data, _)| {
                 situation.fulfills(&move_data.requirements, Some(move_data.move_type))
             })
-            .min_by(|(id1, _, _), (id2, _, _)| id1.cmp(id2))
+            .min_by_key(|(id, _, _)| (character.priority(*id), *id))
         {
             self.buffer.retain(|(_, id)| selected_id != *id);
             Some((selected_id, move_data, Some(frame as i32)))
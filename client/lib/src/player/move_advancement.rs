@@ -74,6 +74,7 @@ pub(super) fn activate_phase(
 
         // Start next phase
         move_state.phase_index = phase_index;
+        move_state.absorbed_hits = 0;
 
         if let Some((action, requirements)) = move_data.get_action(move_state) {
             if let Some(req) = requirements {
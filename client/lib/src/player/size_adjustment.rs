@@ -2,14 +2,32 @@ use bevy::prelude::*;
 
 use characters::{Character, Hurtbox};
 use player_state::PlayerState;
+use time::Clock;
+use types::Facing;
 
 use crate::physics::Pushbox;
 
 pub fn size_adjustment(
-    mut query: Query<(&mut PlayerState, &mut Pushbox, &mut Hurtbox, &Character)>,
+    clock: Res<Clock>,
+    mut query: Query<(
+        &mut PlayerState,
+        &mut Pushbox,
+        &mut Hurtbox,
+        &Character,
+        &Facing,
+    )>,
 ) {
-    for (state, mut pushbox, mut hurtbox, character) in query.iter_mut() {
-        **hurtbox = character.get_hurtbox(state.is_crouching());
+    for (state, mut pushbox, mut hurtbox, character, facing) in query.iter_mut() {
+        let base = character.get_hurtbox(state.is_crouching());
+        let new_hurtbox = if let Some(situation) = state.get_move_state() {
+            character
+                .get_move(situation.move_id)
+                .get_hurtbox(situation, clock.frame as i32, base)
+        } else {
+            base.into()
+        };
+
+        **hurtbox = new_hurtbox.mirrored(facing);
         **pushbox = character.get_pushbox(state.is_crouching());
     }
 }
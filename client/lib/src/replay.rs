@@ -0,0 +1,164 @@
+use bevy::{asset::FileAssetIo, prelude::*};
+use characters::Inventory;
+use input_parsing::{
+    Replay, ReplayCharacters, ReplayDefinitions, ReplayError, ReplayRecorder, ReplayStream,
+    RoundRecording,
+};
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use time::{ClockControl, GameState};
+use types::Player;
+
+const REPLAY_DIRECTORY: &str = "assets/replays";
+const PAUSE_KEY: KeyCode = KeyCode::Space;
+const STEP_KEY: KeyCode = KeyCode::Period;
+const FAST_FORWARD_KEY: KeyCode = KeyCode::F;
+const FAST_FORWARD_SPEEDS: [f32; 3] = [1.0, 2.0, 4.0];
+
+/// Present when a replay is being watched instead of a match being played
+#[derive(Debug)]
+pub struct ReplayPlayback {
+    replay: Replay,
+    next_round: usize,
+}
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next_round: 0,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ReplayError> {
+        Ok(Self::new(Replay::load(path)?))
+    }
+
+    pub fn characters(&self) -> ReplayCharacters {
+        self.replay.characters
+    }
+
+    pub fn definitions(&self) -> &ReplayDefinitions {
+        &self.replay.definitions
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.replay.settings.seed
+    }
+
+    pub fn has_next_round(&self) -> bool {
+        self.next_round < self.replay.rounds.len()
+    }
+
+    fn start_round(&mut self) -> Option<&RoundRecording> {
+        let round = self.replay.rounds.get(self.next_round)?;
+        self.next_round += 1;
+        Some(round)
+    }
+}
+
+/// Rounds are still recorded, but never written to the replay directory
+pub struct DiscardReplays;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::PreUpdate, round_transitions)
+            .add_system(playback_controls);
+    }
+}
+
+fn round_transitions(
+    state: Res<State<GameState>>,
+    recorder: Option<ResMut<ReplayRecorder>>,
+    playback: Option<ResMut<ReplayPlayback>>,
+    discard: Option<Res<DiscardReplays>>,
+    mut players: Query<(&Player, &mut Inventory, Option<&mut ReplayStream>)>,
+    mut previous_state: Local<Option<GameState>>,
+    mut replay_file: Local<Option<PathBuf>>,
+) {
+    // State drivers mutate the resource several times per transition
+    let current = *state.current();
+    if previous_state.replace(current) == Some(current) {
+        return;
+    }
+
+    match current {
+        GameState::Combat => {
+            if let Some(mut recorder) = recorder {
+                let inventory = |player| {
+                    players
+                        .iter()
+                        .find(|(candidate, _, _)| **candidate == player)
+                        .map(|(_, inventory, _)| inventory.clone())
+                        .unwrap_or_default()
+                };
+                recorder.start_round(inventory(Player::One), inventory(Player::Two));
+            }
+
+            if let Some(round) = playback.and_then(|mut playback| playback.start_round().cloned()) {
+                for (player, mut inventory, stream) in players.iter_mut() {
+                    *inventory = round.get(*player).inventory.clone();
+                    if let Some(mut stream) = stream {
+                        stream.start_round(&round);
+                    }
+                }
+            }
+        }
+        GameState::Shop => {
+            if let Some(mut recorder) = recorder {
+                recorder.end_round();
+                if recorder.replay.rounds.is_empty() || discard.is_some() {
+                    // Game just started or nobody wants the file
+                    return;
+                }
+
+                let path = replay_file.get_or_insert_with(new_replay_path);
+                // Saved after every round, so a crash doesn't lose the whole set
+                if let Err(error) = recorder.replay.save(path) {
+                    println!("Failed to save replay to {:?}: {}", path, error);
+                }
+            }
+        }
+    }
+}
+
+fn new_replay_path() -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+
+    FileAssetIo::get_root_path()
+        .join(REPLAY_DIRECTORY)
+        .join(format!("{}.ron", timestamp))
+}
+
+fn playback_controls(
+    keys: Res<Input<KeyCode>>,
+    playback: Option<Res<ReplayPlayback>>,
+    mut control: ResMut<ClockControl>,
+) {
+    if playback.is_none() {
+        return;
+    }
+
+    if keys.just_pressed(PAUSE_KEY) {
+        control.paused = !control.paused;
+    }
+
+    if keys.just_pressed(STEP_KEY) {
+        control.paused = true;
+        control.step();
+    }
+
+    if keys.just_pressed(FAST_FORWARD_KEY) {
+        let current = FAST_FORWARD_SPEEDS
+            .iter()
+            .position(|speed| *speed == control.speed)
+            .unwrap_or_default();
+        control.speed = FAST_FORWARD_SPEEDS[(current + 1) % FAST_FORWARD_SPEEDS.len()];
+    }
+}
@@ -0,0 +1,87 @@
+use bevy::prelude::*;
+use input_parsing::ParrotStream;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use types::Player;
+
+use crate::{netplay::Netplay, replay::ReplayPlayback};
+
+/// Randomness that affects the game, like shop rolls.
+/// Seeded and part of snapshots, so replays and rollback roll the same things
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameRng {
+    seed: u64,
+    rng: ChaCha8Rng,
+}
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// Randomness that only changes how things look or sound.
+/// Kept apart from GameRng so that presentation can't desync the game
+#[derive(Deref, DerefMut)]
+pub struct CosmeticRng(StdRng);
+impl Default for CosmeticRng {
+    fn default() -> Self {
+        Self(StdRng::from_entropy())
+    }
+}
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        // Replays roll what the original match rolled, online peers what was agreed on
+        // and headless runs may bring their own
+        if !app.world.contains_resource::<GameRng>() {
+            let seed = if let Some(playback) = app.world.get_resource::<ReplayPlayback>() {
+                playback.seed()
+            } else if let Some(netplay) = app.world.get_resource::<Netplay>() {
+                netplay.seed()
+            } else {
+                rand::random()
+            };
+            app.insert_resource(GameRng::new(seed));
+        }
+
+        app.add_system_to_stage(CoreStage::PreUpdate, seed_parrots);
+    }
+}
+
+/// From the seed and not the rng, so connecting a controller doesn't change what the game rolls
+fn seed_parrots(
+    rng: Res<GameRng>,
+    mut parrots: Query<(&mut ParrotStream, &Player), Added<ParrotStream>>,
+) {
+    for (mut parrot, player) in parrots.iter_mut() {
+        parrot.seed(rng.seed() ^ *player as u64);
+    }
+}
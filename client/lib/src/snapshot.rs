@@ -0,0 +1,327 @@
+use bevy::{prelude::*, utils::HashMap};
+use characters::{
+    Character, Grabable, HitTracker, Hitbox, Hurtbox, Inventory, OnHitEffect, Resources,
+};
+use input_parsing::{InputParser, ParserState, ReplayRecorder};
+use player_state::PlayerState;
+use serde::{Deserialize, Serialize};
+use time::{Clock, RoundResult};
+use types::{Facing, Owner, Player, Players};
+
+use crate::{
+    assets::{Particles, Sounds},
+    camera::WorldCamera,
+    damage::{Health, HitboxKeyframes, HitboxSpawner},
+    physics::{ConstantVelocity, PlayerVelocity, Pushbox, Sides},
+    player::{MoveBuffer, MoveBufferState},
+    rng::GameRng,
+    ui::Notifications,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PlayerSnapshot {
+    player: Player,
+    /// Definitions can be swapped mid round
+    character: Character,
+    translation: Vec3,
+    facing: Facing,
+    state: PlayerState,
+    resources: Resources,
+    inventory: Inventory,
+    health: Health,
+    velocity: PlayerVelocity,
+    buffer: MoveBufferState,
+    parser: ParserState,
+    spawner: HitboxSpawner,
+    grabable: Grabable,
+    hurtbox: Hurtbox,
+    pushbox: Pushbox,
+}
+impl PlayerSnapshot {
+    fn capture(world: &World, player: Player, entity: Entity) -> Self {
+        fn get<T: Component + Clone>(world: &World, entity: Entity) -> T {
+            world
+                .get::<T>(entity)
+                .expect("Player is missing a component")
+                .clone()
+        }
+
+        Self {
+            player,
+            character: get(world, entity),
+            translation: get::<Transform>(world, entity).translation,
+            facing: get(world, entity),
+            state: get(world, entity),
+            resources: get(world, entity),
+            inventory: get(world, entity),
+            health: get(world, entity),
+            velocity: get(world, entity),
+            buffer: world.get::<MoveBuffer>(entity).unwrap().save_state(),
+            parser: world.get::<InputParser>(entity).unwrap().save_state(),
+            spawner: get(world, entity),
+            grabable: get(world, entity),
+            hurtbox: get(world, entity),
+            pushbox: get(world, entity),
+        }
+    }
+
+    /// Goes before the rest, as the parser and the buffer of either player depend on it
+    fn restore_character(&self, world: &mut World, entity: Entity) {
+        let mut player = world.entity_mut(entity);
+        if player.get::<Character>() == Some(&self.character) {
+            return;
+        }
+
+        player
+            .get_mut::<InputParser>()
+            .unwrap()
+            .reload(self.character.get_inputs())
+            .expect("Inputs were checked when the definition was swapped in");
+        player.insert(self.character.clone());
+    }
+
+    fn restore(&self, world: &mut World, entity: Entity, opponent: Character) {
+        let mut player = world.entity_mut(entity);
+
+        player.get_mut::<Transform>().unwrap().translation = self.translation;
+        player.insert_bundle((
+            self.facing,
+            self.state.clone(),
+            self.resources,
+            self.inventory.clone(),
+            self.health,
+            self.velocity,
+            self.spawner.clone(),
+            self.grabable.clone(),
+            self.hurtbox.clone(),
+            self.pushbox,
+        ));
+        player
+            .get_mut::<MoveBuffer>()
+            .unwrap()
+            .load_state(&self.buffer, &opponent);
+        player
+            .get_mut::<InputParser>()
+            .unwrap()
+            .load_state(&self.parser);
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct HitboxSnapshot {
+    /// Entity the hitbox had when captured, spawners refer to it
+    entity: Entity,
+    owner: Player,
+    /// Attached hitboxes are children of the owner and move with them
+    attached: bool,
+    translation: Vec3,
+    global_translation: Vec3,
+    hitbox: Hitbox,
+    keyframes: HitboxKeyframes,
+    effect: OnHitEffect,
+    tracker: HitTracker,
+    velocity: ConstantVelocity,
+}
+
+/// Everything the combat simulation depends on, can be restored to get the exact same round back
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    frame: usize,
+    rng: GameRng,
+    /// Stage walls follow the camera, so where it was matters on the next frame
+    camera_x: f32,
+    /// Players only turn around when they switch sides
+    sides: Sides,
+    /// Online, a round can end on a frame that gets rolled back
+    result: Option<RoundResult>,
+    players: Vec<PlayerSnapshot>,
+    hitboxes: Vec<HitboxSnapshot>,
+}
+impl Snapshot {
+    pub fn capture(world: &mut World) -> Self {
+        let players = {
+            let entities = world.resource::<Players>();
+            [(Player::One, entities.one), (Player::Two, entities.two)]
+        };
+
+        let mut hitboxes: Vec<_> = world
+            .query::<(
+                Entity,
+                &Owner,
+                Option<&Parent>,
+                &Transform,
+                &GlobalTransform,
+                &Hitbox,
+                &HitboxKeyframes,
+                &OnHitEffect,
+                &HitTracker,
+                &ConstantVelocity,
+            )>()
+            .iter(world)
+            .map(
+                |(
+                    entity,
+                    owner,
+                    parent,
+                    tf,
+                    global_tf,
+                    hitbox,
+                    keyframes,
+                    effect,
+                    tracker,
+                    velocity,
+                )| {
+                    HitboxSnapshot {
+                        entity,
+                        owner: **owner,
+                        attached: parent.is_some(),
+                        translation: tf.translation,
+                        global_translation: global_tf.translation,
+                        hitbox: hitbox.clone(),
+                        keyframes: keyframes.clone(),
+                        effect: *effect,
+                        tracker: *tracker,
+                        velocity: *velocity,
+                    }
+                },
+            )
+            .collect();
+        // Query order depends on archetype storage, which rollbacks shuffle
+        hitboxes.sort_by_key(|snapshot| snapshot.entity);
+
+        Self {
+            frame: world.resource::<Clock>().frame,
+            rng: world.resource::<GameRng>().clone(),
+            camera_x: world
+                .query_filtered::<&Transform, With<WorldCamera>>()
+                .iter(world)
+                .next()
+                .map_or(0.0, |tf| tf.translation.x),
+            sides: *world.resource::<Sides>(),
+            result: world.get_resource::<RoundResult>().copied(),
+            players: players
+                .into_iter()
+                .map(|(player, entity)| PlayerSnapshot::capture(world, player, entity))
+                .collect(),
+            hitboxes,
+        }
+    }
+
+    pub fn restore(&self, world: &mut World) {
+        world.resource_mut::<Clock>().frame = self.frame;
+        // The frames after this get played again
+        if let Some(mut recorder) = world.get_resource_mut::<ReplayRecorder>() {
+            recorder.rewind(self.frame);
+        }
+        world.insert_resource(self.rng.clone());
+        world.insert_resource(self.sides);
+        match self.result {
+            Some(result) => world.insert_resource(result),
+            None => {
+                world.remove_resource::<RoundResult>();
+            }
+        }
+        for mut tf in world
+            .query_filtered::<&mut Transform, With<WorldCamera>>()
+            .iter_mut(world)
+        {
+            tf.translation.x = self.camera_x;
+        }
+
+        let old_hitboxes: Vec<Entity> = world
+            .query_filtered::<Entity, (With<Hitbox>, With<Owner>)>()
+            .iter(world)
+            .collect();
+        for entity in old_hitboxes {
+            despawn_with_children_recursive(world, entity);
+        }
+
+        // Hitboxes come back as new entities, spawners need to know which
+        let mut mapping = HashMap::default();
+        for snapshot in &self.hitboxes {
+            let parent = world.resource::<Players>().get(snapshot.owner);
+            let new_entity = world
+                .spawn()
+                .insert_bundle(TransformBundle {
+                    local: Transform::from_translation(snapshot.translation),
+                    global: GlobalTransform::from_translation(snapshot.global_translation),
+                })
+                .insert_bundle((
+                    Owner(snapshot.owner),
+                    snapshot.hitbox.clone(),
+                    snapshot.keyframes.clone(),
+                    snapshot.effect,
+                    snapshot.tracker,
+                    snapshot.velocity,
+                ))
+                .id();
+
+            if snapshot.attached {
+                world.entity_mut(parent).push_children(&[new_entity]);
+            }
+            mapping.insert(snapshot.entity, new_entity);
+        }
+
+        for snapshot in &self.players {
+            let entity = world.resource::<Players>().get(snapshot.player);
+            snapshot.restore_character(world, entity);
+        }
+        for snapshot in &self.players {
+            let players = world.resource::<Players>();
+            let (entity, opponent) = (
+                players.get(snapshot.player),
+                players.get(snapshot.player.other()),
+            );
+            let opponent = world.get::<Character>(opponent).unwrap().clone();
+
+            snapshot.restore(world, entity, opponent);
+            world
+                .get_mut::<HitboxSpawner>(entity)
+                .unwrap()
+                .remap_entities(&mapping);
+        }
+    }
+
+    /// Compact binary form, for storing or sending
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // Only fails for types serde can't represent, which a snapshot never contains
+        bincode::serialize(self).expect("Failed to encode snapshot")
+    }
+
+    /// None if the bytes are not a snapshot
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        bincode::deserialize(bytes).ok()
+    }
+}
+
+impl netcode::StateStore for Snapshot {
+    type State = Snapshot;
+
+    fn save(world: &mut World) -> Snapshot {
+        Snapshot::capture(world)
+    }
+
+    fn load(world: &mut World, state: &Snapshot) {
+        state.restore(world);
+    }
+
+    fn mute(world: &mut World, frame: impl FnOnce(&mut World)) {
+        let sounds = world.get_resource::<Sounds>().map(Sounds::queued);
+        let particles = world.get_resource::<Particles>().map(Particles::queued);
+        let notifications = world
+            .get_resource::<Notifications>()
+            .map(Notifications::queued);
+
+        frame(world);
+
+        if let Some(queued) = sounds {
+            world.resource_mut::<Sounds>().truncate_queue(queued);
+        }
+        if let Some(queued) = particles {
+            world.resource_mut::<Particles>().truncate_queue(queued);
+        }
+        if let Some(queued) = notifications {
+            world.resource_mut::<Notifications>().truncate_queue(queued);
+        }
+    }
+}
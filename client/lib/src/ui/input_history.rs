@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+use input_parsing::{HistoryEntry, InputParser};
+use time::Clock;
+use types::{GameButton, Player, StickPosition};
+
+use crate::assets::{Colors, Fonts};
+
+use super::utils::{div, div_style};
+
+const TOGGLE_KEY: KeyCode = KeyCode::F1;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InputHistoryMode {
+    #[default]
+    Hidden,
+    Inputs,
+    /// Inputs annotated with the moves the parser recognized
+    Events,
+}
+
+#[derive(Debug, Component)]
+pub struct InputHistoryPanel(Player);
+
+pub fn setup_input_history(commands: &mut Commands, colors: &Colors, fonts: &Fonts) {
+    spawn_panel(commands, colors, fonts, Player::One);
+    spawn_panel(commands, colors, fonts, Player::Two);
+}
+
+fn spawn_panel(commands: &mut Commands, colors: &Colors, fonts: &Fonts, player: Player) {
+    // Notifications take the outermost 20%
+    let top = Val::Percent(15.0);
+    let position = match player {
+        Player::One => Rect {
+            left: Val::Percent(20.0),
+            top,
+            ..default()
+        },
+        Player::Two => Rect {
+            right: Val::Percent(20.0),
+            top,
+            ..default()
+        },
+    };
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position,
+                size: Size::new(Val::Auto, Val::Auto),
+                padding: Rect::all(Val::Px(5.0)),
+                display: Display::None,
+                ..div_style()
+            },
+            color: colors.notification_background.into(),
+            ..div()
+        })
+        .insert(Name::new(format!("Input history {}", player)))
+        .insert(InputHistoryPanel(player))
+        .with_children(|panel| {
+            panel.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    "",
+                    TextStyle {
+                        font: fonts.basic.clone(),
+                        font_size: 16.0,
+                        color: colors.notification_text,
+                    },
+                    TextAlignment::default(),
+                ),
+                ..default()
+            });
+        });
+}
+
+pub fn toggle_mode(keys: Res<Input<KeyCode>>, mut mode: ResMut<InputHistoryMode>) {
+    if keys.just_pressed(TOGGLE_KEY) {
+        *mode = match *mode {
+            InputHistoryMode::Hidden => InputHistoryMode::Inputs,
+            InputHistoryMode::Inputs => InputHistoryMode::Events,
+            InputHistoryMode::Events => InputHistoryMode::Hidden,
+        };
+    }
+}
+
+pub fn update(
+    mode: Res<InputHistoryMode>,
+    clock: Res<Clock>,
+    players: Query<(&Player, &InputParser)>,
+    mut panels: Query<(&mut Style, &InputHistoryPanel, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (mut style, panel, children) in panels.iter_mut() {
+        if *mode == InputHistoryMode::Hidden {
+            style.display = Display::None;
+            continue;
+        }
+        style.display = Display::Flex;
+
+        let parser = players
+            .iter()
+            .find(|(player, _)| **player == panel.0)
+            .map(|(_, parser)| parser);
+
+        let text = children
+            .first()
+            .and_then(|&child| texts.get_mut(child).ok());
+
+        if let (Some(parser), Some(mut text)) = (parser, text) {
+            text.sections[0].value = format_history(parser, clock.frame, *mode);
+        }
+    }
+}
+
+fn format_history(parser: &InputParser, current_frame: usize, mode: InputHistoryMode) -> String {
+    let mut end_frame = current_frame;
+
+    parser
+        .get_history()
+        .map(|entry| {
+            let line = format_entry(entry, end_frame.saturating_sub(entry.frame), mode);
+            end_frame = entry.frame;
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_entry(entry: &HistoryEntry, held: usize, mode: InputHistoryMode) -> String {
+    let mut buttons: Vec<GameButton> = entry.pressed.iter().copied().collect();
+    buttons.sort_by_key(|button| *button as usize);
+    let buttons: String = buttons.into_iter().map(button_symbol).collect();
+
+    let mut line = format!("{:>3} {} {}", held, stick_icon(entry.stick), buttons);
+    if mode == InputHistoryMode::Events && !entry.events.is_empty() {
+        line.push_str(&format!(" {:?}", entry.events));
+    }
+    line
+}
+
+fn stick_icon(stick: StickPosition) -> char {
+    match stick {
+        StickPosition::NW => '↖',
+        StickPosition::N => '↑',
+        StickPosition::NE => '↗',
+        StickPosition::W => '←',
+        StickPosition::Neutral => '•',
+        StickPosition::E => '→',
+        StickPosition::SW => '↙',
+        StickPosition::S => '↓',
+        StickPosition::SE => '↘',
+    }
+}
+
+// Same symbols as in the input notation
+fn button_symbol(button: GameButton) -> char {
+    match button {
+        GameButton::Default => '?',
+        GameButton::Grab => 'g',
+        GameButton::Strong => 's',
+        GameButton::Fast => 'f',
+        GameButton::Equipment => 'e',
+        GameButton::Taunt => 't',
+    }
+}
@@ -6,14 +6,17 @@ use types::Player;
 use crate::assets::{Colors, Fonts};
 
 mod bars;
+mod input_history;
 mod text;
 mod utils;
 
 use bars::{spawn_charge_bars, spawn_health_bar, spawn_meter_bars};
+use input_history::setup_input_history;
 use notifications::setup_toasts;
 use text::{setup_round_info_text, spawn_timer};
 use utils::*;
 
+pub use input_history::InputHistoryMode;
 pub use notifications::Notifications;
 
 // Top bars
@@ -32,17 +35,22 @@ pub struct UIPlugin;
 
 impl Plugin for UIPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup_ui).add_system_set_to_stage(
-            CoreStage::Last,
-            SystemSet::new()
-                .with_system(bars::update)
-                .with_system(notifications::update)
-                .with_system(
-                    text::update_timer.with_run_criteria(State::on_update(GameState::Combat)),
-                )
-                .with_system(text::hide_round_text.after(text::update_timer))
-                .with_system(text::update_round_text.after(text::hide_round_text)),
-        );
+        app.init_resource::<InputHistoryMode>()
+            .add_startup_system(setup_ui)
+            .add_system_set_to_stage(
+                CoreStage::Last,
+                SystemSet::new()
+                    .with_system(bars::update)
+                    .with_system(notifications::parrot_status.before(notifications::update))
+                    .with_system(notifications::update)
+                    .with_system(input_history::toggle_mode)
+                    .with_system(input_history::update.after(input_history::toggle_mode))
+                    .with_system(
+                        text::update_timer.with_run_criteria(State::on_update(GameState::Combat)),
+                    )
+                    .with_system(text::hide_round_text.after(text::update_timer))
+                    .with_system(text::update_round_text.after(text::hide_round_text)),
+            );
     }
 }
 
@@ -51,6 +59,7 @@ fn setup_ui(mut commands: Commands, colors: Res<Colors>, fonts: Res<Fonts>) {
     setup_bottom_bars(&mut commands, &colors);
     setup_round_info_text(&mut commands, &colors, &fonts);
     setup_toasts(&mut commands);
+    setup_input_history(&mut commands, &colors, &fonts);
 }
 
 fn setup_top_bars(commands: &mut Commands, colors: &Colors, fonts: &Fonts) {
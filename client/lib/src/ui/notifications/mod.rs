@@ -1,4 +1,5 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, utils::HashMap};
+use input_parsing::{ParrotMode, ParrotStatus, ParrotStream, SlotSelection};
 use time::Clock;
 use types::Player;
 
@@ -20,10 +21,34 @@ pub struct Notifications {
     p2_container: Entity,
 }
 impl Notifications {
+    pub(crate) fn new(p1_container: Entity, p2_container: Entity) -> Self {
+        Self {
+            spawned: vec![],
+            requests: vec![],
+            p1_container,
+            p2_container,
+        }
+    }
+
     pub fn add(&mut self, player: Player, content: String) {
         self.requests.push((player, content));
     }
 
+    /// For when there is nothing to show the notifications with
+    pub fn discard_requests(&mut self) {
+        self.requests.clear();
+    }
+
+    /// How many notifications are waiting to be shown
+    pub fn queued(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Drops the requests that were made after the first ones
+    pub fn truncate_queue(&mut self, queued: usize) {
+        self.requests.truncate(queued);
+    }
+
     fn get_parent(&self, parent: Player) -> Entity {
         match parent {
             Player::One => self.p1_container,
@@ -36,12 +61,7 @@ pub fn setup_toasts(commands: &mut Commands) {
     let p1_container = create_notification_container(commands, Player::One);
     let p2_container = create_notification_container(commands, Player::Two);
 
-    commands.insert_resource(Notifications {
-        spawned: vec![],
-        requests: vec![],
-        p1_container,
-        p2_container,
-    });
+    commands.insert_resource(Notifications::new(p1_container, p2_container));
 }
 
 fn create_notification_container(commands: &mut Commands, side: Player) -> Entity {
@@ -150,3 +170,45 @@ fn spawn_notification(
         })
         .id()
 }
+
+pub fn parrot_status(
+    mut toasts: ResMut<Notifications>,
+    mut previous: Local<HashMap<Player, ParrotStatus>>,
+    parrots: Query<(&Player, &ParrotStream)>,
+) {
+    for (player, parrot) in parrots.iter() {
+        let status = parrot.status();
+        if previous.get(player) == Some(&status) {
+            continue;
+        }
+
+        if previous.insert(*player, status).is_some() {
+            toasts.add(*player, describe_parrot(status));
+        }
+    }
+}
+
+fn describe_parrot(status: ParrotStatus) -> String {
+    let mode = match status.mode {
+        ParrotMode::Listening => "Recording",
+        ParrotMode::Repeating => "Playing",
+        ParrotMode::Noop => "Off",
+    };
+    let selection = match status.selection {
+        SlotSelection::Sequential => "sequential",
+        SlotSelection::Random => "random",
+    };
+    let relative = if status.relative_to_facing {
+        ", relative"
+    } else {
+        ""
+    };
+
+    format!(
+        "Parrot: {} slot {} ({}{})",
+        mode,
+        status.slot + 1,
+        selection,
+        relative
+    )
+}
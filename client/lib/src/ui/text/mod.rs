@@ -1,5 +1,5 @@
 use bevy::prelude::*;
-use time::{Clock, GameState, RoundResult, RoundTimer, ROUND_TIME};
+use time::{Clock, GameState, RoundResult, RoundTimer};
 
 use crate::assets::{Colors, Fonts};
 
@@ -12,7 +12,7 @@ use super::utils::{div, div_style, FULL};
 pub struct RoundText;
 
 pub fn update_timer(mut query: Query<&mut Text, With<RoundTimer>>, clock: Res<Clock>) {
-    query.single_mut().sections[0].value = (ROUND_TIME - clock.elapsed_time).floor().to_string();
+    query.single_mut().sections[0].value = clock.seconds_left().to_string();
 }
 
 pub fn hide_round_text(
@@ -1,15 +1,102 @@
 // use bevy::{diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin}};
 // use bevy::ecs::schedule::ReportExecutionOrderAmbiguities;
-use bevy::prelude::*;
-use whoops_all_grapplers_lib::WAGLib;
+use bevy::{asset::AssetServerSettings, prelude::*};
+use netcode::UdpTransport;
+use std::path::{Path, PathBuf};
+use types::Player;
+use whoops_all_grapplers_lib::{ChecksumLog, ChecksumOutput, Netplay, ReplayPlayback, WAGLib};
 
 fn main() {
+    let mut app = App::new();
+
+    // Usage: whoops-all-grapplers [--replay <file>] [--checksums <file>]
+    //        whoops-all-grapplers --netplay <local address> <peer address> <1|2> <seed>
+    //        whoops-all-grapplers --compare-checksums <file> <file>
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(index) = args.iter().position(|arg| arg == "--compare-checksums") {
+        let (first, second) = match (args.get(index + 1), args.get(index + 2)) {
+            (Some(first), Some(second)) => (first, second),
+            _ => panic!("--compare-checksums requires two file paths"),
+        };
+        std::process::exit(compare_checksums(Path::new(first), Path::new(second)));
+    }
+
+    if let Some(index) = args.iter().position(|arg| arg == "--checksums") {
+        let path = args
+            .get(index + 1)
+            .expect("--checksums requires a file path");
+        app.insert_resource(ChecksumOutput(PathBuf::from(path)));
+    }
+
+    if let Some(index) = args.iter().position(|arg| arg == "--replay") {
+        let path = args.get(index + 1).expect("--replay requires a file path");
+        match ReplayPlayback::load(Path::new(path)) {
+            Ok(playback) => {
+                app.insert_resource(playback);
+            }
+            Err(error) => {
+                eprintln!("Could not play {}: {}", path, error);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(index) = args.iter().position(|arg| arg == "--netplay") {
+        app.insert_resource(netplay(&args[index + 1..]));
+    }
+
+    // Character definitions are reloaded when edited, for quick tuning
+    app.insert_resource(AssetServerSettings {
+        watch_for_changes: true,
+        ..default()
+    });
+
     // Happens roughly in order, so add stages, click and assets before using them
-    App::new()
-        .add_plugins(DefaultPlugins)
+    app.add_plugins(DefaultPlugins)
         .add_plugins(WAGLib)
         // .add_plugin(LogDiagnosticsPlugin::default())
         // .add_plugin(FrameTimeDiagnosticsPlugin::default())
         // .insert_resource(ReportExecutionOrderAmbiguities)
         .run();
 }
+
+fn netplay(args: &[String]) -> Netplay {
+    let (local, peer, player, seed) = match args {
+        [local, peer, player, seed, ..] => (local, peer, player, seed),
+        _ => panic!("--netplay requires a local address, a peer address, a player and a seed"),
+    };
+    let local_player = match player.as_str() {
+        "1" => Player::One,
+        "2" => Player::Two,
+        _ => panic!("--netplay player has to be 1 or 2"),
+    };
+    // Both peers have to roll the same items
+    let seed = seed.parse().expect("--netplay seed has to be a number");
+
+    let transport = UdpTransport::bind(
+        local.parse().expect("Invalid local address"),
+        peer.parse().expect("Invalid peer address"),
+    )
+    .unwrap_or_else(|error| {
+        eprintln!("Could not open {}: {}", local, error);
+        std::process::exit(1);
+    });
+    Netplay::new(transport, local_player, seed)
+}
+
+fn compare_checksums(first: &Path, second: &Path) -> i32 {
+    let load = |path: &Path| {
+        ChecksumLog::load(path).unwrap_or_else(|error| {
+            eprintln!("Could not read {:?}: {}", path, error);
+            std::process::exit(2);
+        })
+    };
+
+    if let Some(desync) = load(first).first_desync(&load(second)) {
+        println!("{}", desync);
+        1
+    } else {
+        println!("No desyncs");
+        0
+    }
+}
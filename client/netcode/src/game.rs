@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use input_parsing::NetworkStream;
+use std::marker::PhantomData;
+use types::Player;
+
+use crate::{session::FrameInputs, simulation::Simulation};
+
+/// Captures and restores everything the combat systems touch
+pub trait StateStore {
+    type State;
+
+    fn save(world: &mut World) -> Self::State;
+    fn load(world: &mut World, state: &Self::State);
+    /// Runs a frame that was already shown once, without showing or playing anything from it again
+    fn mute(world: &mut World, frame: impl FnOnce(&mut World));
+}
+
+/// The game as a rollback simulation.
+/// Players need a `NetworkStream` to read input from, each advance runs the combat schedule once
+pub struct GameSimulation<'w, S: StateStore> {
+    world: &'w mut World,
+    store: PhantomData<S>,
+}
+impl<'w, S: StateStore> GameSimulation<'w, S> {
+    pub fn new(world: &'w mut World) -> Self {
+        Self {
+            world,
+            store: PhantomData,
+        }
+    }
+}
+impl<'w, S: StateStore> Simulation for GameSimulation<'w, S> {
+    type State = S::State;
+
+    fn save(&mut self) -> Self::State {
+        S::save(self.world)
+    }
+
+    fn load(&mut self, state: &Self::State) {
+        S::load(self.world, state);
+    }
+
+    fn advance(&mut self, inputs: FrameInputs, resimulating: bool) {
+        for (player, mut stream) in self
+            .world
+            .query::<(&Player, &mut NetworkStream)>()
+            .iter_mut(self.world)
+        {
+            stream.set(inputs.get(*player).clone());
+        }
+
+        if resimulating {
+            S::mute(self.world, time::run_combat_frame);
+        } else {
+            time::run_combat_frame(self.world);
+        }
+    }
+
+    fn is_over(&mut self) -> bool {
+        self.world.contains_resource::<time::RoundResult>()
+    }
+}
@@ -0,0 +1,16 @@
+//! Rollback networking for online versus.
+//!
+//! Peers only exchange inputs. Missing remote input is predicted and when the real
+//! input turns out different, the simulation is rewound and replayed with it.
+
+mod game;
+mod protocol;
+mod session;
+mod simulation;
+mod transport;
+
+pub use game::{GameSimulation, StateStore};
+pub use protocol::Handshake;
+pub use session::{FrameInputs, RollbackSession, MAX_PREDICTION_FRAMES};
+pub use simulation::{RollbackRunner, Simulation};
+pub use transport::{LinkConditions, LoopbackTransport, Transport, UdpTransport};
@@ -0,0 +1,65 @@
+use input_parsing::Diff;
+use serde::{Deserialize, Serialize};
+
+/// What the peers have to agree on before they can play together
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Handshake {
+    pub seed: u64,
+    /// Checksum of the character definitions both players start with
+    pub definitions: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Message {
+    /// Resent between rounds, so a peer that missed it still gets it
+    Hello(Handshake),
+    /// Start was pressed for the round, resent until the round starts
+    Ready { round: usize },
+    /// Local inputs the peer has not acknowledged yet, resent every frame until they are
+    Inputs {
+        round: usize,
+        start_frame: usize,
+        inputs: Vec<Option<Diff>>,
+        /// How many of the peer's frames have been received without gaps
+        ack: usize,
+    },
+}
+impl Message {
+    pub fn encode(&self) -> Vec<u8> {
+        // Only fails for types serde can't represent, which a message never contains
+        bincode::serialize(self).expect("Failed to encode message")
+    }
+
+    /// None if the packet is garbage
+    pub fn decode(packet: &[u8]) -> Option<Self> {
+        bincode::deserialize(packet).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use input_parsing::InputEvent;
+    use types::{GameButton, StickPosition};
+
+    use super::*;
+
+    #[test]
+    fn message_round_trip() {
+        let message = Message::Inputs {
+            round: 2,
+            start_frame: 12,
+            inputs: vec![
+                None,
+                Some(
+                    Diff::default()
+                        .apply(InputEvent::Point(StickPosition::SE))
+                        .apply(InputEvent::Press(GameButton::Fast)),
+                ),
+            ],
+            ack: 10,
+        };
+
+        assert_eq!(Message::decode(&message.encode()), Some(message));
+        assert_eq!(Message::decode(&[1, 2, 3]), None);
+    }
+}
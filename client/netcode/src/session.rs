@@ -0,0 +1,371 @@
+use input_parsing::Diff;
+use std::collections::BTreeMap;
+use types::Player;
+
+use crate::{
+    protocol::{Handshake, Message},
+    transport::Transport,
+};
+
+/// How far the local side may run ahead of confirmed remote input before it waits
+pub const MAX_PREDICTION_FRAMES: usize = 8;
+
+/// Inputs of both players for a single frame
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrameInputs {
+    pub one: Option<Diff>,
+    pub two: Option<Diff>,
+}
+impl FrameInputs {
+    pub fn get(&self, player: Player) -> &Option<Diff> {
+        match player {
+            Player::One => &self.one,
+            Player::Two => &self.two,
+        }
+    }
+}
+
+/// Exchanges inputs with the peer and keeps track of which remote inputs were guessed.
+/// Frames count from the start of the current round, every round starts over from frame 0
+pub struct RollbackSession<T: Transport> {
+    transport: T,
+    local_player: Player,
+    /// 0 before the first round
+    round: usize,
+    peer_handshake: Option<Handshake>,
+    /// Highest round the peer pressed start for
+    peer_ready: usize,
+    local_inputs: Vec<Option<Diff>>,
+    /// None for frames that have not arrived yet
+    remote_inputs: Vec<Option<Option<Diff>>>,
+    /// Remote frames received without gaps
+    confirmed_frames: usize,
+    /// Local frames the peer has received without gaps
+    acknowledged_frames: usize,
+    predictions: BTreeMap<usize, Option<Diff>>,
+    first_misprediction: Option<usize>,
+}
+impl<T: Transport> RollbackSession<T> {
+    pub fn new(transport: T, local_player: Player) -> Self {
+        Self {
+            transport,
+            local_player,
+            round: 0,
+            peer_handshake: None,
+            peer_ready: 0,
+            local_inputs: vec![],
+            remote_inputs: vec![],
+            confirmed_frames: 0,
+            acknowledged_frames: 0,
+            predictions: BTreeMap::default(),
+            first_misprediction: None,
+        }
+    }
+
+    pub fn local_player(&self) -> Player {
+        self.local_player
+    }
+
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    /// Tells the peer what this side plays with, sent until a round starts
+    pub fn send_hello(&mut self, handshake: Handshake) {
+        self.transport.send(Message::Hello(handshake).encode());
+    }
+
+    /// What the peer plays with, once it has arrived
+    pub fn peer_handshake(&self) -> Option<Handshake> {
+        self.peer_handshake
+    }
+
+    /// Tells the peer this side wants to start the next round, resent until it starts
+    pub fn send_ready(&mut self) {
+        let message = Message::Ready {
+            round: self.round + 1,
+        };
+        self.transport.send(message.encode());
+    }
+
+    /// The peer wants to start the next round
+    pub fn peer_ready(&self) -> bool {
+        self.peer_ready > self.round
+    }
+
+    /// Moves on to the next round, inputs of the last one are dropped
+    pub fn start_round(&mut self) {
+        self.round += 1;
+        self.local_inputs.clear();
+        self.remote_inputs.clear();
+        self.confirmed_frames = 0;
+        self.acknowledged_frames = 0;
+        self.predictions.clear();
+        self.first_misprediction = None;
+    }
+
+    /// Both peers know every input up to the frame, so neither can roll back before it
+    pub fn settled(&self, frames: usize) -> bool {
+        self.confirmed_frames() >= frames && self.acknowledged_frames >= frames
+    }
+
+    /// Frames of local input that have been added
+    pub fn local_frames(&self) -> usize {
+        self.local_inputs.len()
+    }
+
+    /// Frames up to which both players' inputs are known for certain
+    pub fn confirmed_frames(&self) -> usize {
+        self.confirmed_frames.min(self.local_inputs.len())
+    }
+
+    pub fn can_advance(&self) -> bool {
+        self.local_inputs.len() < self.confirmed_frames + MAX_PREDICTION_FRAMES
+    }
+
+    /// Adds input for the next local frame and sends everything the peer is missing
+    pub fn add_local_input(&mut self, input: Option<Diff>) {
+        self.local_inputs.push(input);
+        self.send_inputs();
+    }
+
+    /// Sends everything the peer has not acknowledged, also happens when adding input
+    pub fn send_inputs(&mut self) {
+        let start_frame = self.acknowledged_frames.min(self.local_inputs.len());
+        let message = Message::Inputs {
+            round: self.round,
+            start_frame,
+            inputs: self.local_inputs[start_frame..].to_vec(),
+            ack: self.confirmed_frames,
+        };
+        self.transport.send(message.encode());
+    }
+
+    /// Handles everything that arrived from the peer
+    pub fn poll(&mut self) {
+        for packet in self.transport.receive() {
+            if let Some(message) = Message::decode(&packet) {
+                self.handle(message);
+            }
+        }
+    }
+
+    fn handle(&mut self, message: Message) {
+        match message {
+            Message::Hello(handshake) => {
+                self.peer_handshake = Some(handshake);
+            }
+            Message::Ready { round } => {
+                self.peer_ready = self.peer_ready.max(round);
+            }
+            Message::Inputs {
+                round,
+                start_frame,
+                inputs,
+                ack,
+            } => {
+                if round > self.round {
+                    // The peer started the next round, so it was ready for it
+                    self.peer_ready = self.peer_ready.max(round);
+                }
+                if round != self.round {
+                    // Frame numbers only mean something within a round
+                    return;
+                }
+
+                self.acknowledged_frames = self.acknowledged_frames.max(ack);
+
+                for (offset, input) in inputs.into_iter().enumerate() {
+                    self.receive_input(start_frame + offset, input);
+                }
+
+                while let Some(Some(_)) = self.remote_inputs.get(self.confirmed_frames) {
+                    self.confirmed_frames += 1;
+                }
+                // Predictions for confirmed frames are no longer needed
+                self.predictions = self.predictions.split_off(&self.confirmed_frames);
+            }
+        }
+    }
+
+    fn receive_input(&mut self, frame: usize, input: Option<Diff>) {
+        if self.remote_inputs.len() <= frame {
+            self.remote_inputs.resize(frame + 1, None);
+        }
+        if self.remote_inputs[frame].is_some() {
+            // Resent input
+            return;
+        }
+
+        if let Some(prediction) = self.predictions.get(&frame) {
+            if *prediction != input {
+                self.first_misprediction =
+                    Some(self.first_misprediction.map_or(frame, |old| old.min(frame)));
+            }
+        }
+        self.remote_inputs[frame] = Some(input);
+    }
+
+    /// Earliest frame that was simulated with a wrong guess, clears it
+    pub fn take_rollback(&mut self) -> Option<usize> {
+        self.first_misprediction.take()
+    }
+
+    /// Inputs for a frame, remote input is predicted if it hasn't arrived
+    pub fn inputs(&mut self, frame: usize) -> FrameInputs {
+        let local = self.local_inputs.get(frame).cloned().flatten();
+        let remote = self.remote_input(frame);
+
+        match self.local_player {
+            Player::One => FrameInputs {
+                one: local,
+                two: remote,
+            },
+            Player::Two => FrameInputs {
+                one: remote,
+                two: local,
+            },
+        }
+    }
+
+    fn remote_input(&mut self, frame: usize) -> Option<Diff> {
+        if let Some(Some(input)) = self.remote_inputs.get(frame) {
+            return input.clone();
+        }
+
+        // Diffs are changes, so no change means the remote player keeps doing the same thing
+        let prediction = None;
+        self.predictions.insert(frame, prediction.clone());
+        prediction
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use input_parsing::InputEvent;
+    use types::StickPosition;
+
+    use crate::transport::{LinkConditions, LoopbackTransport};
+
+    use super::*;
+
+    fn stick(position: StickPosition) -> Option<Diff> {
+        Some(Diff::default().apply(InputEvent::Point(position)))
+    }
+
+    fn sessions(
+        latency: usize,
+    ) -> (
+        RollbackSession<LoopbackTransport>,
+        RollbackSession<LoopbackTransport>,
+    ) {
+        let (one, two) = LoopbackTransport::pair(LinkConditions {
+            latency,
+            ..Default::default()
+        });
+        (
+            RollbackSession::new(one, Player::One),
+            RollbackSession::new(two, Player::Two),
+        )
+    }
+
+    #[test]
+    fn inputs_arrive() {
+        let (mut one, mut two) = sessions(0);
+
+        one.add_local_input(stick(StickPosition::N));
+        two.poll();
+
+        assert_eq!(two.confirmed_frames(), 0);
+        two.add_local_input(None);
+        assert_eq!(two.confirmed_frames(), 1);
+        assert_eq!(two.inputs(0).one, stick(StickPosition::N));
+    }
+
+    #[test]
+    fn wrong_prediction_requests_rollback() {
+        let (mut one, mut two) = sessions(1);
+
+        one.add_local_input(None);
+        one.add_local_input(stick(StickPosition::S));
+        two.add_local_input(None);
+        two.add_local_input(None);
+
+        // Two simulates before anything arrives
+        assert_eq!(two.inputs(0).one, None);
+        assert_eq!(two.inputs(1).one, None);
+
+        two.poll();
+        assert_eq!(two.take_rollback(), None);
+        two.poll();
+        assert_eq!(two.take_rollback(), Some(1));
+        assert_eq!(two.inputs(1).one, stick(StickPosition::S));
+    }
+
+    #[test]
+    fn handshake_arrives() {
+        let (mut one, mut two) = sessions(0);
+        let handshake = Handshake {
+            seed: 5,
+            definitions: 7,
+        };
+
+        assert_eq!(two.peer_handshake(), None);
+        one.send_hello(handshake);
+        two.poll();
+        assert_eq!(two.peer_handshake(), Some(handshake));
+    }
+
+    #[test]
+    fn rounds_start_over() {
+        let (mut one, mut two) = sessions(0);
+
+        one.send_ready();
+        two.poll();
+        assert!(two.peer_ready());
+        one.start_round();
+        two.start_round();
+        assert!(!two.peer_ready());
+
+        one.add_local_input(stick(StickPosition::N));
+        two.add_local_input(None);
+        one.poll();
+        two.poll();
+        assert!(!one.settled(1));
+        // Acknowledgements go out with the next send
+        one.send_inputs();
+        two.send_inputs();
+        one.poll();
+        two.poll();
+        assert!(one.settled(1));
+        assert!(two.settled(1));
+
+        one.start_round();
+        two.start_round();
+        assert_eq!(two.confirmed_frames(), 0);
+
+        // Late inputs from the last round are not mixed into the next one
+        let stale = Message::Inputs {
+            round: 1,
+            start_frame: 0,
+            inputs: vec![stick(StickPosition::S)],
+            ack: 1,
+        };
+        one.transport.send(stale.encode());
+        two.poll();
+        two.add_local_input(None);
+        assert_eq!(two.confirmed_frames(), 0);
+        assert!(!two.settled(1));
+    }
+
+    #[test]
+    fn prediction_is_limited() {
+        let (mut one, _two) = sessions(0);
+
+        for _ in 0..MAX_PREDICTION_FRAMES {
+            assert!(one.can_advance());
+            one.add_local_input(None);
+        }
+        assert!(!one.can_advance());
+    }
+}
@@ -0,0 +1,263 @@
+use input_parsing::Diff;
+use std::collections::VecDeque;
+
+use crate::{
+    session::{FrameInputs, RollbackSession},
+    transport::Transport,
+};
+
+/// Something that can be saved, restored and stepped one frame at a time
+pub trait Simulation {
+    type State;
+
+    fn save(&mut self) -> Self::State;
+    fn load(&mut self, state: &Self::State);
+    /// Resimulating is true for frames that are simulated again after a rollback
+    fn advance(&mut self, inputs: FrameInputs, resimulating: bool);
+    /// Nothing happens after this, like when the round has ended
+    fn is_over(&mut self) -> bool {
+        false
+    }
+}
+
+/// Steps a simulation with a session's inputs, rolling back when a guess was wrong.
+/// Outlives the simulations it steps, those can borrow the game for a single update
+pub struct RollbackRunner<State> {
+    /// State at the start of each unconfirmed frame
+    saved: VecDeque<(usize, State)>,
+    frame: usize,
+    /// Frames simulated when it became over, may still be rolled back
+    end: Option<usize>,
+    rollbacks: usize,
+}
+impl<State> Default for RollbackRunner<State> {
+    fn default() -> Self {
+        Self {
+            saved: VecDeque::default(),
+            frame: 0,
+            end: None,
+            rollbacks: 0,
+        }
+    }
+}
+impl<State> RollbackRunner<State> {
+    /// Frames simulated so far
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Frames it took for the simulation to be over, if it is
+    pub fn end(&self) -> Option<usize> {
+        self.end
+    }
+
+    /// How many times the simulation had to be rewound
+    pub fn rollbacks(&self) -> usize {
+        self.rollbacks
+    }
+
+    /// Handles inputs that have arrived and rolls back if needed, without advancing
+    pub fn sync<S, T>(&mut self, session: &mut RollbackSession<T>, simulation: &mut S)
+    where
+        S: Simulation<State = State>,
+        T: Transport,
+    {
+        session.poll();
+
+        if let Some(rollback_frame) = session.take_rollback() {
+            self.rollback(session, simulation, rollback_frame);
+        }
+    }
+
+    /// Returns false if too far ahead of the peer, the same input should be given again next time
+    pub fn advance_frame<S, T>(
+        &mut self,
+        session: &mut RollbackSession<T>,
+        simulation: &mut S,
+        local_input: Option<Diff>,
+    ) -> bool
+    where
+        S: Simulation<State = State>,
+        T: Transport,
+    {
+        self.sync(session, simulation);
+
+        if !session.can_advance() {
+            // The peer may be waiting on inputs that got lost
+            session.send_inputs();
+            return false;
+        }
+
+        session.add_local_input(local_input);
+        self.simulate(session, simulation, false);
+
+        // Confirmed frames can't be rolled back to
+        let confirmed = session.confirmed_frames();
+        while self.saved.len() > 1 && self.saved[1].0 <= confirmed {
+            self.saved.pop_front();
+        }
+
+        true
+    }
+
+    fn simulate<S, T>(
+        &mut self,
+        session: &mut RollbackSession<T>,
+        simulation: &mut S,
+        resimulating: bool,
+    ) where
+        S: Simulation<State = State>,
+        T: Transport,
+    {
+        self.saved.push_back((self.frame, simulation.save()));
+        if self.end.is_none() {
+            simulation.advance(session.inputs(self.frame), resimulating);
+            if simulation.is_over() {
+                self.end = Some(self.frame + 1);
+            }
+        }
+        self.frame += 1;
+    }
+
+    fn rollback<S, T>(
+        &mut self,
+        session: &mut RollbackSession<T>,
+        simulation: &mut S,
+        to_frame: usize,
+    ) where
+        S: Simulation<State = State>,
+        T: Transport,
+    {
+        let index = self
+            .saved
+            .iter()
+            .position(|(frame, _)| *frame == to_frame)
+            .expect("Rolling back to a frame that was not saved");
+        simulation.load(&self.saved[index].1);
+        self.saved.truncate(index);
+        self.rollbacks += 1;
+        // It may not end the same way with the right inputs
+        self.end = self.end.filter(|end| *end <= to_frame);
+
+        let end = self.frame;
+        self.frame = to_frame;
+        while self.frame < end {
+            self.simulate(session, simulation, true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use input_parsing::InputEvent;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use types::{GameButton, Player, StickPosition};
+
+    use crate::transport::{LinkConditions, LoopbackTransport};
+
+    use super::*;
+
+    /// Deterministic stand in for the game, order of inputs matters
+    #[derive(Debug, Clone, Default, PartialEq)]
+    struct Toy {
+        position: [i32; 2],
+        history: Vec<i32>,
+    }
+    impl Simulation for Toy {
+        type State = Toy;
+
+        fn save(&mut self) -> Toy {
+            self.clone()
+        }
+
+        fn load(&mut self, state: &Toy) {
+            *self = state.clone();
+        }
+
+        fn advance(&mut self, inputs: FrameInputs, _resimulating: bool) {
+            for (index, player) in [Player::One, Player::Two].into_iter().enumerate() {
+                if let Some(diff) = inputs.get(player) {
+                    if let Some(stick) = diff.stick_move {
+                        let vector: bevy::prelude::IVec2 = stick.into();
+                        self.position[index] =
+                            self.position[index].wrapping_mul(3) + vector.x + vector.y;
+                    }
+                    if diff.pressed_contains(&GameButton::Fast) {
+                        self.history.push(self.position[index]);
+                    }
+                }
+            }
+        }
+    }
+
+    fn random_input(rng: &mut StdRng) -> Option<Diff> {
+        if rng.gen_bool(0.7) {
+            return None;
+        }
+
+        let sticks = [StickPosition::N, StickPosition::SW, StickPosition::E];
+        let diff = Diff::default().apply(InputEvent::Point(sticks[rng.gen_range(0..sticks.len())]));
+        Some(if rng.gen_bool(0.5) {
+            diff.apply(InputEvent::Press(GameButton::Fast))
+        } else {
+            diff
+        })
+    }
+
+    #[test]
+    fn peers_converge_under_bad_conditions() {
+        let (transport_one, transport_two) = LoopbackTransport::pair(LinkConditions {
+            latency: 4,
+            packet_loss: 0.25,
+            seed: 3,
+        });
+
+        let mut peers = [
+            (
+                RollbackSession::new(transport_one, Player::One),
+                RollbackRunner::<Toy>::default(),
+                Toy::default(),
+                StdRng::seed_from_u64(10),
+                None,
+            ),
+            (
+                RollbackSession::new(transport_two, Player::Two),
+                RollbackRunner::<Toy>::default(),
+                Toy::default(),
+                StdRng::seed_from_u64(20),
+                None,
+            ),
+        ];
+
+        let frames = 300;
+        // Extra ticks with no input so everything gets confirmed
+        for tick in 0..frames * 2 {
+            for (session, runner, toy, rng, pending) in peers.iter_mut() {
+                if runner.frame() >= frames {
+                    // Keep exchanging inputs until the peer has everything
+                    runner.sync(session, toy);
+                    session.send_inputs();
+                    continue;
+                }
+
+                let input = pending.take().unwrap_or_else(|| {
+                    if tick < frames {
+                        random_input(rng)
+                    } else {
+                        None
+                    }
+                });
+                if !runner.advance_frame(session, toy, input.clone()) {
+                    *pending = Some(input);
+                }
+            }
+        }
+
+        let (one, two) = peers.split_at(1);
+        assert_eq!(one[0].1.frame(), frames);
+        assert_eq!(two[0].1.frame(), frames);
+        assert!(one[0].1.rollbacks() > 0);
+        assert_eq!(one[0].2, two[0].2);
+        assert!(!one[0].2.history.is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    collections::VecDeque,
+    io::ErrorKind,
+    net::{SocketAddr, UdpSocket},
+    sync::{Arc, Mutex},
+};
+
+// Inputs for a handful of frames fit easily
+const MAX_PACKET_SIZE: usize = 4096;
+
+/// Unreliable, unordered packet delivery to the other peer
+pub trait Transport {
+    fn send(&mut self, packet: Vec<u8>);
+    /// Everything that has arrived since the last call
+    fn receive(&mut self) -> Vec<Vec<u8>>;
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn send(&mut self, packet: Vec<u8>) {
+        (**self).send(packet);
+    }
+
+    fn receive(&mut self) -> Vec<Vec<u8>> {
+        (**self).receive()
+    }
+}
+
+pub struct UdpTransport {
+    socket: UdpSocket,
+    peer: SocketAddr,
+}
+impl UdpTransport {
+    pub fn bind(local: SocketAddr, peer: SocketAddr) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind(local)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket, peer })
+    }
+}
+impl Transport for UdpTransport {
+    fn send(&mut self, packet: Vec<u8>) {
+        // Lost packets are resent by the session, so errors can be ignored
+        if let Err(error) = self.socket.send_to(&packet, self.peer) {
+            println!("Failed to send packet to {}: {}", self.peer, error);
+        }
+    }
+
+    fn receive(&mut self) -> Vec<Vec<u8>> {
+        let mut packets = vec![];
+        let mut buffer = [0; MAX_PACKET_SIZE];
+
+        loop {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((size, sender)) => {
+                    if sender == self.peer {
+                        packets.push(buffer[..size].to_vec());
+                    }
+                }
+                Err(error) => {
+                    if error.kind() != ErrorKind::WouldBlock {
+                        println!("Failed to receive packet: {}", error);
+                    }
+                    break;
+                }
+            }
+        }
+
+        packets
+    }
+}
+
+/// Simulated network quality for loopback transports
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LinkConditions {
+    /// How many receive calls a packet waits for before arriving
+    pub latency: usize,
+    /// Chance of a packet getting lost, from 0 to 1
+    pub packet_loss: f32,
+    /// Losses are random, but reproducible
+    pub seed: u64,
+}
+
+#[derive(Default)]
+struct Link {
+    in_flight: VecDeque<(usize, Vec<u8>)>,
+}
+
+/// In-process transport for running both peers on one machine
+pub struct LoopbackTransport {
+    outgoing: Arc<Mutex<Link>>,
+    incoming: Arc<Mutex<Link>>,
+    conditions: LinkConditions,
+    rng: StdRng,
+}
+impl LoopbackTransport {
+    pub fn pair(conditions: LinkConditions) -> (Self, Self) {
+        let one_to_two = Arc::new(Mutex::new(Link::default()));
+        let two_to_one = Arc::new(Mutex::new(Link::default()));
+
+        (
+            Self {
+                outgoing: one_to_two.clone(),
+                incoming: two_to_one.clone(),
+                conditions,
+                rng: StdRng::seed_from_u64(conditions.seed),
+            },
+            Self {
+                outgoing: two_to_one,
+                incoming: one_to_two,
+                conditions,
+                rng: StdRng::seed_from_u64(conditions.seed.wrapping_add(1)),
+            },
+        )
+    }
+}
+impl Transport for LoopbackTransport {
+    fn send(&mut self, packet: Vec<u8>) {
+        if self.rng.gen::<f32>() < self.conditions.packet_loss {
+            return;
+        }
+
+        self.outgoing
+            .lock()
+            .unwrap()
+            .in_flight
+            .push_back((self.conditions.latency, packet));
+    }
+
+    fn receive(&mut self) -> Vec<Vec<u8>> {
+        let mut link = self.incoming.lock().unwrap();
+
+        let mut arrived = vec![];
+        link.in_flight.retain_mut(|(wait, packet)| {
+            if *wait == 0 {
+                arrived.push(std::mem::take(packet));
+                false
+            } else {
+                *wait -= 1;
+                true
+            }
+        });
+        arrived
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn loopback_latency() {
+        let (mut one, mut two) = LoopbackTransport::pair(LinkConditions {
+            latency: 2,
+            ..Default::default()
+        });
+
+        one.send(vec![1]);
+        assert!(two.receive().is_empty());
+        assert!(two.receive().is_empty());
+        assert_eq!(two.receive(), vec![vec![1]]);
+        assert!(one.receive().is_empty());
+    }
+
+    #[test]
+    fn loopback_loss_is_reproducible() {
+        let conditions = LinkConditions {
+            packet_loss: 0.5,
+            seed: 7,
+            ..Default::default()
+        };
+
+        let delivered = || {
+            let (mut one, mut two) = LoopbackTransport::pair(conditions);
+            for i in 0..100 {
+                one.send(vec![i]);
+            }
+            two.receive()
+        };
+
+        let first = delivered();
+        assert!(!first.is_empty() && first.len() < 100);
+        assert_eq!(first, delivered());
+    }
+}
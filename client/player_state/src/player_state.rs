@@ -1,19 +1,20 @@
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
-use characters::{AttackHeight, MoveSituation};
+use characters::{Armor, AttackHeight, MoveSituation};
 use types::{AnimationType, Area, Facing, StickPosition};
 
 use crate::sub_state::{AirState, CrouchState, StandState};
 
-#[derive(Inspectable, Debug, Component, Clone)]
+#[derive(Inspectable, Debug, Component, Clone, Serialize, Deserialize)]
 enum MainState {
     Air(AirState),
     Stand(StandState),
     Crouch(CrouchState),
 }
 
-#[derive(Inspectable, Debug, Component, Clone)]
+#[derive(Inspectable, Debug, Component, Clone, Serialize, Deserialize)]
 pub struct PlayerState {
     main: MainState,
     pub free_since: Option<usize>,
@@ -82,6 +83,11 @@ impl PlayerState {
             situation.hit_registered = true;
         }
     }
+    /// False if not in a move or the armor has already taken all the hits it can
+    pub fn absorb_hit(&mut self, armor: Armor) -> bool {
+        self.get_move_state_mut()
+            .map_or(false, |situation| situation.absorb_hit(armor))
+    }
 
     // Stun
     pub fn stun(&mut self, recovery_frame: usize) {
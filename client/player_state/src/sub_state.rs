@@ -1,9 +1,12 @@
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
 use characters::MoveSituation;
 use types::Facing;
 
-#[derive(Inspectable, Eq, PartialEq, Clone, Debug, Default)]
+// Being in a move is the common case, so the size is rarely wasted
+#[allow(clippy::large_enum_variant)]
+#[derive(Inspectable, Eq, PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
 pub enum AirState {
     Freefall,
     Move(MoveSituation),
@@ -11,7 +14,7 @@ pub enum AirState {
     Idle,
 }
 
-#[derive(Inspectable, Eq, PartialEq, Clone, Debug, Default)]
+#[derive(Inspectable, Eq, PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
 pub enum StandState {
     Stun(usize),
     Move(MoveSituation),
@@ -20,7 +23,7 @@ pub enum StandState {
     Idle,
 }
 
-#[derive(Inspectable, Eq, PartialEq, Clone, Debug, Default)]
+#[derive(Inspectable, Eq, PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
 pub enum CrouchState {
     Stun(usize),
     Move(MoveSituation),
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use types::Player;
 
 #[derive(Clone, Copy, Hash, Debug, PartialEq, Eq)]
@@ -6,6 +7,8 @@ pub enum GameState {
     Shop,
 }
 
+/// Inserted on the frame the round ends, combat frames stop after it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RoundResult {
     pub winner: Option<Player>,
 }
@@ -1,28 +1,105 @@
+use bevy::ecs::schedule::{IntoSystemDescriptor, ShouldRun};
 use bevy::prelude::*;
-use bevy::{core::FixedTimestep, ecs::schedule::ShouldRun};
 use bevy_inspector_egui::Inspectable;
 
 mod game_flow;
 pub use game_flow::{GameState, RoundResult};
 
 pub const ROUND_TIME: f32 = 99.0;
+const ROUND_FRAMES: usize = (ROUND_TIME * constants::FPS) as usize;
+const FRAMES_PER_SECOND: usize = constants::FPS as usize;
 
 /// The component for measuring time in frames
 #[derive(Inspectable, Default)]
 pub struct Clock {
     pub frame: usize,
-    start_time: f32,
-    pub elapsed_time: f32,
 }
 impl Clock {
     pub fn time_out(&self) -> bool {
-        self.elapsed_time >= ROUND_TIME - 1.0
+        // Round ends when the timer would show zero
+        self.frame + FRAMES_PER_SECOND >= ROUND_FRAMES
     }
 
-    pub fn reset(&mut self, time: f64) {
+    /// Whole seconds left on the round timer
+    pub fn seconds_left(&self) -> usize {
+        ROUND_FRAMES.saturating_sub(self.frame) / FRAMES_PER_SECOND
+    }
+
+    pub fn reset(&mut self) {
         self.frame = 0;
-        self.elapsed_time = 0.0;
-        self.start_time = time as f32;
+    }
+}
+
+/// Pausing, stepping and speeding up the simulation, used in replay playback
+#[derive(Debug, Clone, Copy)]
+pub struct ClockControl {
+    pub paused: bool,
+    /// Multiplier for how fast frames advance.
+    /// Above 1, updates simulate several frames when they have to
+    pub speed: f32,
+    /// When false, every update is a new frame regardless of how much real time has passed
+    pub realtime: bool,
+    step: bool,
+    /// Frames this update should simulate, that have not been simulated yet
+    due: usize,
+    /// Real time that has passed, but not enough of it for another frame
+    accumulator: f64,
+}
+impl Default for ClockControl {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            speed: 1.0,
+            realtime: true,
+            step: false,
+            due: 0,
+            accumulator: 0.0,
+        }
+    }
+}
+impl ClockControl {
+    /// For headless runs, which go as fast as the CPU allows
+    pub fn unpaced() -> Self {
+        Self {
+            realtime: false,
+            ..default()
+        }
+    }
+
+    /// Advance a single frame while paused
+    pub fn step(&mut self) {
+        self.step = true;
+    }
+
+    /// Decides how many frames are due, after real_time seconds have passed since the last update.
+    /// Real time only decides when the next frame happens, never how much gets simulated
+    pub fn advance(&mut self, real_time: f64) {
+        let frame_time = 1.0 / constants::FPS as f64;
+
+        self.due = if self.paused {
+            self.accumulator = 0.0;
+            std::mem::take(&mut self.step) as usize
+        } else if !self.realtime {
+            1
+        } else {
+            self.accumulator += real_time * self.speed as f64;
+            // At most as many frames as the speed asks for per update,
+            // so a slow machine slows down instead of skipping
+            let max_frames = (self.speed.ceil() as usize).max(1);
+            let frames = ((self.accumulator / frame_time) as usize).min(max_frames);
+            self.accumulator = (self.accumulator - frames as f64 * frame_time).min(frame_time);
+            frames
+        };
+    }
+
+    /// Claims the next frame that is due this update, if there is one
+    pub fn take_frame(&mut self) -> bool {
+        if self.due == 0 {
+            return false;
+        }
+
+        self.due -= 1;
+        true
     }
 }
 /// The component for the round timer
@@ -31,9 +108,84 @@ pub struct RoundTimer;
 
 #[derive(Debug, StageLabel, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum WAGStage {
+    /// Reads controllers every update, whether a frame gets simulated or not
     Inputs,
-    Physics,
+    /// Simulates the frames that are due, by running the combat schedule
+    Combat,
+}
+
+/// Simulating the frames that are due, anything that takes them itself goes before this
+#[derive(Debug, SystemLabel, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DueFrames;
+
+/// Stages of the combat schedule, in order
+#[derive(Debug, StageLabel, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CombatStage {
+    Inputs,
+    Players,
     HitReg,
+    Physics,
+    /// For looking at the finished frame
+    FrameEnd,
+}
+
+/// Every system that changes the combat state, they all run once per simulated frame.
+/// Kept out of the main schedule, so an update can simulate several frames
+/// and rollback can resimulate without rendering anything
+pub struct CombatSchedule(Schedule);
+impl Default for CombatSchedule {
+    fn default() -> Self {
+        let mut schedule = Schedule::default();
+        for stage in [
+            CombatStage::Inputs,
+            CombatStage::Players,
+            CombatStage::HitReg,
+            CombatStage::Physics,
+            CombatStage::FrameEnd,
+        ] {
+            schedule.add_stage(stage, SystemStage::parallel());
+            // Rounds end mid frame, the stages after that have to know about it
+            schedule.add_system_set_to_stage(stage, State::<GameState>::get_driver());
+        }
+        Self(schedule)
+    }
+}
+
+/// Adding systems to the combat schedule
+pub trait CombatApp {
+    fn add_combat_system<Params>(
+        &mut self,
+        stage: CombatStage,
+        system: impl IntoSystemDescriptor<Params>,
+    ) -> &mut Self;
+    fn add_combat_system_set(&mut self, stage: CombatStage, system_set: SystemSet) -> &mut Self;
+}
+impl CombatApp for App {
+    fn add_combat_system<Params>(
+        &mut self,
+        stage: CombatStage,
+        system: impl IntoSystemDescriptor<Params>,
+    ) -> &mut Self {
+        self.world
+            .resource_mut::<CombatSchedule>()
+            .0
+            .add_system_to_stage(stage, system);
+        self
+    }
+
+    fn add_combat_system_set(&mut self, stage: CombatStage, system_set: SystemSet) -> &mut Self {
+        self.world
+            .resource_mut::<CombatSchedule>()
+            .0
+            .add_system_set_to_stage(stage, system_set);
+        self
+    }
+}
+
+/// Simulates the next frame and nothing else, no matter what the clock control says
+pub fn run_combat_frame(world: &mut World) {
+    world.resource_mut::<Clock>().frame += 1;
+    world.resource_scope(|world, mut schedule: Mut<CombatSchedule>| schedule.0.run_once(world));
 }
 
 pub struct TimePlugin;
@@ -45,25 +197,22 @@ impl Plugin for TimePlugin {
             WAGStage::Inputs,
             SystemStage::parallel(),
         )
-        .add_stage_after(CoreStage::Update, WAGStage::HitReg, SystemStage::parallel())
-        .add_stage_before(
-            CoreStage::PostUpdate,
-            WAGStage::Physics,
-            SystemStage::parallel(),
-        )
+        .add_stage_after(WAGStage::Inputs, WAGStage::Combat, SystemStage::parallel())
+        .init_resource::<CombatSchedule>()
         .insert_resource(Clock::default())
+        .init_resource::<ClockControl>()
         .add_state_to_stage(CoreStage::Last, GameState::Shop)
         .add_system_set_to_stage(CoreStage::PostUpdate, State::<GameState>::get_driver())
         .add_system_set_to_stage(CoreStage::Update, State::<GameState>::get_driver())
         .add_system_set_to_stage(CoreStage::PreUpdate, State::<GameState>::get_driver())
         .add_system_set_to_stage(CoreStage::First, State::<GameState>::get_driver())
-        .add_system_set_to_stage(WAGStage::HitReg, State::<GameState>::get_driver())
         .add_system_set_to_stage(WAGStage::Inputs, State::<GameState>::get_driver())
-        .add_system_set_to_stage(WAGStage::Physics, State::<GameState>::get_driver())
+        .add_system_set_to_stage(WAGStage::Combat, State::<GameState>::get_driver())
         .add_system_to_stage(
-            CoreStage::First,
-            update_clock.with_run_criteria(FixedTimestep::steps_per_second(constants::FPS as f64)),
+            WAGStage::Combat,
+            run_due_frames.exclusive_system().label(DueFrames),
         )
+        .add_system_to_stage(CoreStage::First, update_clock)
         .add_system_to_stage(
             CoreStage::First,
             reset_clock
@@ -73,13 +222,26 @@ impl Plugin for TimePlugin {
     }
 }
 
-fn update_clock(mut clock: ResMut<Clock>, bevy_clock: Res<Time>) {
-    clock.frame += 1;
-    clock.elapsed_time = bevy_clock.seconds_since_startup() as f32 - clock.start_time;
+fn update_clock(mut control: ResMut<ClockControl>, bevy_clock: Res<Time>) {
+    control.advance(bevy_clock.delta_seconds_f64());
 }
 
-fn reset_clock(mut clock: ResMut<Clock>, bevy_clock: Res<Time>) {
-    clock.reset(bevy_clock.seconds_since_startup());
+/// Outside of combat frames only move the clock
+fn run_due_frames(world: &mut World) {
+    while world.resource_mut::<ClockControl>().take_frame() {
+        if *world.resource::<State<GameState>>().current() == GameState::Combat {
+            // Nothing happens after the round is over, it ends on this update
+            if !world.contains_resource::<RoundResult>() {
+                run_combat_frame(world);
+            }
+        } else {
+            world.resource_mut::<Clock>().frame += 1;
+        }
+    }
+}
+
+fn reset_clock(mut clock: ResMut<Clock>) {
+    clock.reset();
 }
 
 pub fn not_in_combat(state: Res<State<GameState>>) -> ShouldRun {
@@ -90,16 +252,86 @@ pub fn not_in_combat(state: Res<State<GameState>>) -> ShouldRun {
     }
 }
 
-pub fn once_per_combat_frame(
-    mut last_frame: Local<usize>,
-    clock: Res<Clock>,
-    state: Res<State<GameState>>,
-) -> ShouldRun {
-    let value = if *state.current() == GameState::Combat && *last_frame < clock.frame {
-        ShouldRun::Yes
-    } else {
-        ShouldRun::No
-    };
-    *last_frame = clock.frame;
-    value
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_timer_counts_frames() {
+        let mut clock = Clock::default();
+        assert_eq!(clock.seconds_left(), ROUND_TIME as usize);
+        assert!(!clock.time_out());
+
+        clock.frame = ROUND_FRAMES - FRAMES_PER_SECOND - 1;
+        assert_eq!(clock.seconds_left(), 1);
+        assert!(!clock.time_out());
+
+        clock.frame += 1;
+        assert!(clock.time_out());
+    }
+
+    /// Frames the combat schedule ran on
+    #[derive(Default)]
+    struct Simulated(Vec<usize>);
+
+    fn record_frame(clock: Res<Clock>, mut simulated: ResMut<Simulated>) {
+        simulated.0.push(clock.frame);
+    }
+
+    #[test]
+    fn combat_schedule_runs_on_due_frames() {
+        let mut app = App::new();
+        app.add_plugin(TimePlugin)
+            .insert_resource(Time::default())
+            .insert_resource(ClockControl::unpaced())
+            .init_resource::<Simulated>()
+            .add_combat_system(CombatStage::Players, record_frame);
+
+        // Outside of rounds the clock moves on, but nothing gets simulated
+        for _ in 0..10 {
+            app.update();
+        }
+        assert_eq!(app.world.resource::<Clock>().frame, 10);
+        assert!(app.world.resource::<Simulated>().0.is_empty());
+
+        app.world
+            .resource_mut::<State<GameState>>()
+            .set(GameState::Combat)
+            .unwrap();
+        app.update();
+        app.update();
+        assert_eq!(app.world.resource::<Simulated>().0, [1, 2]);
+
+        let mut control = app.world.resource_mut::<ClockControl>();
+        control.paused = true;
+        control.step();
+        app.update();
+        app.update();
+        assert_eq!(app.world.resource::<Simulated>().0, [1, 2, 3]);
+    }
+
+    #[test]
+    fn fast_forward_simulates_several_frames_per_update() {
+        let frame_time = 1.0 / constants::FPS as f64;
+        let mut control = ClockControl::default();
+        let due_frames = |control: &mut ClockControl, real_time: f64| {
+            control.advance(real_time);
+            let mut frames = 0;
+            while control.take_frame() {
+                frames += 1;
+            }
+            frames
+        };
+
+        assert_eq!(due_frames(&mut control, frame_time), 1);
+        assert_eq!(due_frames(&mut control, frame_time / 2.0), 0);
+        assert_eq!(due_frames(&mut control, frame_time / 2.0), 1);
+
+        control.speed = 4.0;
+        assert_eq!(due_frames(&mut control, frame_time), 4);
+
+        // A hitch doesn't make up for the lost time all at once
+        control.speed = 1.0;
+        assert_eq!(due_frames(&mut control, 1.0), 1);
+    }
 }
@@ -0,0 +1,169 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use characters::{Character, CharacterId, FrameData};
+
+enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+const COLUMNS: [&str; 14] = [
+    "move",
+    "variant",
+    "continues into",
+    "startup",
+    "active",
+    "recovery",
+    "total",
+    "cancel windows",
+    "damage on hit",
+    "damage on block",
+    "stun on hit",
+    "stun on block",
+    "advantage on hit",
+    "advantage on block",
+];
+
+fn main() {
+    // Usage: frame-data [--json | --csv] [<file>...]
+    // Exports the shipped characters if no files are given
+    let mut format = Format::Table;
+    let mut paths = vec![];
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => format = Format::Json,
+            "--csv" => format = Format::Csv,
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    if paths.is_empty() {
+        let assets = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../main/assets");
+        paths = CharacterId::ALL
+            .iter()
+            .map(|id| assets.join(id.path()))
+            .collect();
+    }
+
+    let mut characters = BTreeMap::new();
+    for path in paths {
+        match load(&path) {
+            Ok(character) => {
+                characters.insert(name(&path), character.frame_data());
+            }
+            Err(error) => {
+                eprintln!("Could not load {:?}: {}", path, error);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    match format {
+        Format::Table => {
+            for (name, rows) in characters {
+                println!("{}", name);
+                print_table(&rows);
+                println!();
+            }
+        }
+        Format::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&characters).expect("Frame data is always valid json")
+        ),
+        Format::Csv => {
+            println!("character,{}", COLUMNS.join(","));
+            for (name, rows) in characters {
+                for row in rows {
+                    println!("{},{}", name, cells(&row).join(","));
+                }
+            }
+        }
+    }
+}
+
+fn load(path: &Path) -> Result<Character, String> {
+    let contents = std::fs::read(path).map_err(|error| error.to_string())?;
+    Character::from_ron(&contents).map_err(|error| error.to_string())
+}
+
+/// "dummy" for "characters/dummy.character.ron"
+fn name(path: &Path) -> String {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    file_name.split('.').next().unwrap_or_default().to_owned()
+}
+
+/// Cells are never empty or contain commas, so they work for both the table and csv
+fn cells(row: &FrameData) -> Vec<String> {
+    fn optional<T: ToString>(value: Option<T>) -> String {
+        value.map_or("-".to_owned(), |value| value.to_string())
+    }
+
+    fn list(items: Vec<String>) -> String {
+        if items.is_empty() {
+            "-".to_owned()
+        } else {
+            items.join(" ")
+        }
+    }
+
+    vec![
+        row.move_id.to_string(),
+        optional(row.variant.as_ref()),
+        list(row.continues_into.iter().map(|id| id.to_string()).collect()),
+        optional(row.startup),
+        row.active.to_string(),
+        optional(row.recovery),
+        row.total.to_string(),
+        list(
+            row.cancel_windows
+                .iter()
+                .map(|(first, last)| format!("{}-{}", first, last))
+                .collect(),
+        ),
+        optional(row.damage_on_hit),
+        optional(row.damage_on_block),
+        optional(row.stun_on_hit),
+        optional(row.stun_on_block),
+        optional(row.advantage_on_hit.map(signed)),
+        optional(row.advantage_on_block.map(signed)),
+    ]
+}
+
+/// Frame advantage is usually written with the sign, like +3 or -12
+fn signed(advantage: i32) -> String {
+    format!("{:+}", advantage)
+}
+
+fn print_table(rows: &[FrameData]) {
+    let rows: Vec<Vec<String>> = rows.iter().map(cells).collect();
+    let widths: Vec<usize> = COLUMNS
+        .iter()
+        .enumerate()
+        .map(|(index, header)| {
+            rows.iter()
+                .map(|row| row[index].len())
+                .chain(std::iter::once(header.len()))
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+
+    let print_row = |row: Vec<&str>| {
+        let padded: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        println!("{}", padded.join(" | ").trim_end());
+    };
+
+    print_row(COLUMNS.to_vec());
+    let separator: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+    print_row(separator.iter().map(String::as_str).collect());
+    for row in &rows {
+        print_row(row.iter().map(String::as_str).collect());
+    }
+}
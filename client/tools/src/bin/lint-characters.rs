@@ -0,0 +1,66 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use characters::{Character, CharacterId};
+use input_parsing::InputParser;
+
+fn main() {
+    // Usage: lint-characters [<file>...]
+    // Checks the shipped characters if no files are given
+    let mut paths: Vec<PathBuf> = std::env::args().skip(1).map(PathBuf::from).collect();
+    if paths.is_empty() {
+        let assets = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../main/assets");
+        paths = CharacterId::ALL
+            .iter()
+            .map(|id| assets.join(id.path()))
+            .collect();
+    }
+
+    let mut failed = false;
+    for path in paths {
+        let problems = match lint(&path) {
+            Ok(problems) => problems,
+            Err(error) => vec![error],
+        };
+
+        if problems.is_empty() {
+            println!("{}: ok", path.display());
+        } else {
+            failed = true;
+            println!("{}: {} problem(s)", path.display(), problems.len());
+            for problem in problems {
+                println!("  {}", problem);
+            }
+        }
+    }
+
+    std::process::exit(if failed { 1 } else { 0 });
+}
+
+fn lint(path: &Path) -> Result<Vec<String>, String> {
+    let contents = std::fs::read(path).map_err(|error| error.to_string())?;
+    // Not Character::from_ron, that stops at the first problem that would crash the game
+    let character: Character = ron::de::from_bytes(&contents).map_err(|error| error.to_string())?;
+
+    let mut problems: Vec<String> = character
+        .lint()
+        .into_iter()
+        .map(|problem| problem.to_string())
+        .collect();
+
+    // One at a time, the parser stops at the first invalid input
+    let mut inputs: Vec<_> = character.get_inputs().into_iter().collect();
+    inputs.sort();
+    for (id, input) in inputs {
+        if let Err(invalid) = InputParser::load(HashMap::from([(id, input)])) {
+            problems.push(format!(
+                "{}.input \"{}\" is invalid: {}",
+                id, invalid.input, invalid.error
+            ));
+        }
+    }
+
+    Ok(problems)
+}
@@ -1,7 +1,8 @@
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Default, Debug, Inspectable, PartialEq)]
+#[derive(Clone, Copy, Default, Debug, Inspectable, PartialEq, Serialize, Deserialize)]
 pub struct Area {
     center: Vec2,
     width: f32,
@@ -31,7 +32,6 @@ impl Area {
         }
     }
 
-    // TODO: Plan is to allow multiple boxes in a single object, so keep struct elements private
     pub fn center(&self) -> Vec2 {
         self.center
     }
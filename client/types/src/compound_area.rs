@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
+
+use crate::{Area, Facing};
+
+/// Several areas that act as one box, like a body with an extended limb
+#[derive(Clone, Default, Debug, Inspectable, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CompoundArea(Vec<Area>);
+
+impl CompoundArea {
+    pub fn new(areas: Vec<Area>) -> Self {
+        Self(areas)
+    }
+    pub fn areas(&self) -> &[Area] {
+        &self.0
+    }
+
+    /// Smallest area that covers all the parts
+    pub fn bounds(&self) -> Area {
+        self.0
+            .iter()
+            .copied()
+            .reduce(|bounds, area| {
+                Area::from_sides(
+                    bounds.top().max(area.top()),
+                    bounds.bottom().min(area.bottom()),
+                    bounds.left().min(area.left()),
+                    bounds.right().max(area.right()),
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    // Utilities
+    pub fn with_offset(&self, new_origin: Vec2) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|area| area.with_offset(new_origin))
+                .collect(),
+        )
+    }
+    pub fn mirrored(&self, facing: &Facing) -> Self {
+        Self(
+            self.0
+                .iter()
+                .map(|area| facing.mirror_area(*area))
+                .collect(),
+        )
+    }
+    pub fn extended(&self, other: &CompoundArea) -> Self {
+        Self(self.0.iter().chain(other.0.iter()).copied().collect())
+    }
+    /// Overlap of the first two parts that overlap
+    pub fn intersection(&self, other: &CompoundArea) -> Option<Area> {
+        self.0
+            .iter()
+            .flat_map(|area| other.0.iter().map(|other| area.intersection(other)))
+            .flatten()
+            .next()
+    }
+    pub fn intersects(&self, other: &CompoundArea) -> bool {
+        self.intersection(other).is_some()
+    }
+}
+impl From<Area> for CompoundArea {
+    fn from(area: Area) -> Self {
+        Self(vec![area])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn any_part_collides() {
+        let body = Area::new(0.0, 1.0, 1.0, 2.0);
+        let limb = Area::new(1.0, 1.5, 1.0, 0.2);
+        let compound = CompoundArea::new(vec![body, limb]);
+
+        let in_front = CompoundArea::from(Area::new(1.4, 1.5, 0.2, 0.2));
+        assert!(!CompoundArea::from(body).intersects(&in_front));
+        assert_eq!(
+            compound.intersection(&in_front),
+            limb.intersection(&in_front.areas()[0])
+        );
+    }
+
+    #[test]
+    fn bounds_cover_every_part() {
+        let compound = CompoundArea::new(vec![
+            Area::new(0.0, 1.0, 1.0, 2.0),
+            Area::new(1.0, 1.5, 1.0, 0.2),
+        ]);
+        assert_eq!(compound.bounds(), Area::from_sides(2.0, 0.0, -0.5, 1.5));
+        assert_eq!(CompoundArea::default().bounds(), Area::default());
+    }
+
+    #[test]
+    fn mirroring_flips_the_parts() {
+        let compound = CompoundArea::new(vec![Area::new(1.0, 1.5, 1.0, 0.2)]);
+        assert_eq!(compound.mirrored(&Facing::Right), compound);
+        assert_eq!(
+            compound.mirrored(&Facing::Left).areas(),
+            [Area::new(-1.0, 1.5, 1.0, 0.2)]
+        );
+    }
+}
@@ -4,6 +4,8 @@ pub enum SoundEffect {
     Clash,
     Block,
     Hit,
+    CounterHit,
+    PunishCounter,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -1,9 +1,12 @@
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 
-use crate::StickPosition;
+use crate::{Area, StickPosition};
 
-#[derive(Inspectable, PartialEq, Eq, Clone, Copy, Debug, Component, Default)]
+#[derive(
+    Inspectable, PartialEq, Eq, Clone, Copy, Debug, Component, Default, Serialize, Deserialize,
+)]
 pub enum Facing {
     #[default]
     Right,
@@ -66,6 +69,10 @@ impl Facing {
         }
     }
 
+    pub fn mirror_area(&self, area: Area) -> Area {
+        area.with_center(self.mirror_vec(area.center().extend(0.0)).truncate())
+    }
+
     pub fn mirror_stick(&self, stick: StickPosition) -> StickPosition {
         let vector: IVec2 = stick.into();
 
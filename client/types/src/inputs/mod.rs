@@ -1,10 +1,25 @@
 mod stick_position;
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 pub use stick_position::StickPosition;
 
 use strum_macros::EnumIter;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, EnumIter, Inspectable, Default)]
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    EnumIter,
+    Inspectable,
+    Default,
+    Serialize,
+    Deserialize,
+)]
 /// Buttons of the game
 /// The name 'Button' is in prelude
 pub enum GameButton {
@@ -1,9 +1,10 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use std::fmt::Debug;
 use strum_macros::EnumIter;
 
-#[derive(EnumIter, Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[derive(EnumIter, Clone, Copy, Debug, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 pub enum StickPosition {
     NW,
     N,
@@ -1,6 +1,9 @@
 mod area;
 pub use area::Area;
 
+mod compound_area;
+pub use compound_area::CompoundArea;
+
 mod facing;
 pub use facing::Facing;
 
@@ -16,5 +19,8 @@ pub use inputs::{GameButton, StickPosition};
 mod player;
 pub use player::{Owner, Player, Players};
 
+mod sorted_set;
+pub use sorted_set::{serialize_sorted, serialize_sorted_map, serialize_sorted_option};
+
 // This crate will be as small as possible so that types are where they are used
 // It's meant for common universal types to circumvent circular dependencies.
@@ -1,4 +1,5 @@
 use bevy_inspector_egui::Inspectable;
+use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Display};
 use strum_macros::EnumIter;
 
@@ -19,7 +20,19 @@ impl Players {
 #[derive(Component, Deref, DerefMut, Clone, Copy)]
 pub struct Owner(pub Player);
 
-#[derive(EnumIter, Inspectable, PartialEq, Eq, Clone, Copy, Debug, Hash, Component)]
+#[derive(
+    EnumIter,
+    Inspectable,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    Debug,
+    Hash,
+    Component,
+    Serialize,
+    Deserialize,
+)]
 pub enum Player {
     One,
     Two,
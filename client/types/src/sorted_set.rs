@@ -0,0 +1,45 @@
+use serde::{Serialize, Serializer};
+use std::collections::{HashMap, HashSet};
+
+/// Serializes a set in order instead of hash order, so equal sets always give the same bytes.
+/// Use with `#[serde(serialize_with = "types::serialize_sorted")]`
+pub fn serialize_sorted<S, T>(set: &HashSet<T>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Ord + Serialize,
+{
+    let mut items: Vec<&T> = set.iter().collect();
+    items.sort();
+    serializer.collect_seq(items)
+}
+
+/// Same as `serialize_sorted`, for sets that can be left out
+pub fn serialize_sorted_option<S, T>(
+    set: &Option<HashSet<T>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Ord + Serialize,
+{
+    match set {
+        Some(set) => {
+            let mut items: Vec<&T> = set.iter().collect();
+            items.sort();
+            serializer.serialize_some(&items)
+        }
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Same as `serialize_sorted`, for maps, which are ordered by key
+pub fn serialize_sorted_map<S, K, V>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Ord + Serialize,
+    V: Serialize,
+{
+    let mut entries: Vec<(&K, &V)> = map.iter().collect();
+    entries.sort_by_key(|(key, _)| *key);
+    serializer.collect_map(entries)
+}